@@ -1,6 +1,10 @@
+use async_graphql::Enum;
 use serde::{Deserialize, Serialize};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef};
 use sqlx::types::{chrono::{DateTime, Utc}, Uuid};
-use sqlx::FromRow;
+use sqlx::{Decode, Encode, FromRow, Postgres, Type};
 
 #[derive(Clone, Debug, PartialEq, FromRow, Serialize, Deserialize)]
 pub struct AccountModel {
@@ -9,6 +13,62 @@ pub struct AccountModel {
     pub avatar: Option<String>,
     pub name: Option<String>,
     pub twitter: Option<String>,
+    pub role: AccountRole, // What the account is allowed to do
+    #[serde(default)]
+    pub referral_code: Option<String>, // Code shared to refer other accounts, once generated
+    #[serde(default)]
+    pub referrer_id: Option<Uuid>, // Account which referred this one
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
-}
\ No newline at end of file
+}
+
+/// Roles are ordered, each role is allowed everything the previous ones are.
+#[derive(Default, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Enum, Copy)]
+pub enum AccountRole {
+    #[default]
+    Player,
+    Operator,
+    Admin,
+}
+
+impl std::fmt::Display for AccountRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccountRole::Player => write!(f, "PLAYER"),
+            AccountRole::Operator => write!(f, "OPERATOR"),
+            AccountRole::Admin => write!(f, "ADMIN"),
+        }
+    }
+}
+
+impl Encode<'_, Postgres> for AccountRole {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        let str_value = match self {
+            AccountRole::Player => "PLAYER",
+            AccountRole::Operator => "OPERATOR",
+            AccountRole::Admin => "ADMIN",
+        };
+        Encode::<Postgres>::encode(str_value, buf)
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for AccountRole {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        let str_value = value.as_str().unwrap_or("");
+        match str_value {
+            "PLAYER" => Ok(AccountRole::Player),
+            "OPERATOR" => Ok(AccountRole::Operator),
+            "ADMIN" => Ok(AccountRole::Admin),
+            _ => Err(sqlx::Error::Decode(
+                format!("Invalid account_role value: {}", str_value).into(),
+            )
+            .into()),
+        }
+    }
+}
+
+impl Type<Postgres> for AccountRole {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("VARCHAR")
+    }
+}
@@ -0,0 +1,20 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Represents an achievement unlocked by an account.
+///
+/// # Fields
+///
+/// - `achievement` - The key of the achievement rule, e.g. `first_ticket`.
+/// - `context` - The chain event which unlocked it, if any.
+/// - `unlocked_at` - When the event which unlocked it happened.
+#[derive(Clone, Debug, PartialEq, Eq, FromRow, Serialize, Deserialize)]
+pub struct AccountAchievementModel {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub achievement: String,
+    pub context: Option<serde_json::Value>,
+    pub unlocked_at: DateTime<Utc>,
+}
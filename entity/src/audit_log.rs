@@ -0,0 +1,23 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Represents a change made by an account through the admin API.
+///
+/// # Fields
+///
+/// - `account_id` - The account which made the change.
+/// - `action` - What was done, e.g. `LOTTERY_FEATURED`.
+/// - `entity_type`, `entity_id` - The changed entity.
+/// - `changes` - The changed fields, as `{"before": {..}, "after": {..}}`.
+#[derive(Clone, Debug, PartialEq, Eq, FromRow, Serialize, Deserialize)]
+pub struct AuditLogModel {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: Uuid,
+    pub changes: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,23 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Represents an event published on the message broker.
+///
+/// Events are kept so subscribers reconnecting with the last id they received
+/// can replay what they missed before switching back to live delivery.
+///
+/// # Fields
+///
+/// - `id` - Monotonically increasing id of the event.
+/// - `channel` - Broker channel the event was published on.
+/// - `payload` - The published model, as JSON.
+/// - `dedup_key` - Key of the outbox message the event was published from, if any.
+#[derive(Clone, Debug, PartialEq, Eq, FromRow, Serialize, Deserialize)]
+pub struct BrokerEventModel {
+    pub id: i64,
+    pub channel: String,
+    pub payload: serde_json::Value,
+    pub dedup_key: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
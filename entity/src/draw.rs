@@ -1,7 +1,7 @@
 use async_graphql::Enum;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::prelude::FromRow;
+use sqlx::{prelude::FromRow, types::Decimal};
 use uuid::Uuid;
 use sqlx::encode::IsNull;
 use sqlx::error::BoxDynError;
@@ -16,6 +16,13 @@ pub struct DrawModel {
     pub draw_date: Option<DateTime<Utc>>, // The date the draw was made
     pub status: DrawStatus, // Enum to represent the status of the draw
     pub transaction_hash: Option<String>,
+    #[serde(default)]
+    pub log_index: Option<i32>, // Log of the payout event within the transaction
+    pub winning_number: Option<i32>, // Ticket number picked by the draw
+    pub winning_ticket: Option<Uuid>, // Purchase owning the winning ticket number
+    pub tier: i32, // Place paid by the draw, 1 being the first place
+    pub amount: Option<Decimal>, // Amount paid to the winner
+    pub share: Option<Decimal>, // Percentage of the prize pool paid to the winner
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
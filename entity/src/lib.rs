@@ -7,6 +7,15 @@ pub mod draw;
 pub mod chain_state;
 pub mod transaction_log;
 pub mod transaction_log_side_effect;
+pub mod reconciliation_report;
+pub mod raw_log;
+pub mod lottery_status_history;
+pub mod broker_event;
+pub mod outbox;
+pub mod session;
+pub mod audit_log;
+pub mod referral_reward;
+pub mod account_achievement;
 
 // Export prelude
 pub mod prelude {
@@ -19,4 +28,13 @@ pub mod prelude {
     pub use super::chain_state::*;
     pub use super::transaction_log::*;
     pub use super::transaction_log_side_effect::*;
+    pub use super::reconciliation_report::*;
+    pub use super::raw_log::*;
+    pub use super::lottery_status_history::*;
+    pub use super::broker_event::*;
+    pub use super::outbox::*;
+    pub use super::session::*;
+    pub use super::audit_log::*;
+    pub use super::referral_reward::*;
+    pub use super::account_achievement::*;
 }
\ No newline at end of file
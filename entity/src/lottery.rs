@@ -18,6 +18,18 @@ pub struct LotteryModel {
     pub fee_ticket_amount: Decimal,
     pub max_tickets: Option<i32>,
     pub status: LotteryStatus, // Enum to represent the status of the lottery
+    #[serde(default)]
+    pub featured_position: Option<i32>, // Slot among the featured lotteries, lowest first
+    #[serde(default)]
+    pub hidden: bool, // Hidden lotteries are left out of the public listings
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub cover_image_url: Option<String>,
+    #[serde(default)]
+    pub category: Option<LotteryCategory>,
+    #[serde(default)]
+    pub tags: Vec<String>, // Lowercase labels used to build themed sections
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -31,6 +43,20 @@ pub enum LotteryStatus {
     Cancelled,
 }
 
+impl LotteryStatus {
+    /// Whether the lottery lifecycle allows moving from this status to `next`.
+    ///
+    /// A lottery is scheduled, goes on, and ends either completed or cancelled.
+    pub fn can_transition_to(&self, next: LotteryStatus) -> bool {
+        matches!(
+            (self, next),
+            (LotteryStatus::Scheduled, LotteryStatus::Ongoing)
+                | (LotteryStatus::Ongoing, LotteryStatus::Completed)
+                | (LotteryStatus::Ongoing, LotteryStatus::Cancelled)
+        )
+    }
+}
+
 impl ToString for LotteryStatus {
     fn to_string(&self) -> String {
         match self {
@@ -74,4 +100,98 @@ impl Type<Postgres> for LotteryStatus {
     fn type_info() -> PgTypeInfo {
         PgTypeInfo::with_name("VARCHAR")
     }
-}
\ No newline at end of file
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Enum, Copy)]
+pub enum LotteryCategory {
+    Daily,
+    Weekly,
+    Charity,
+    Partner,
+}
+
+impl std::fmt::Display for LotteryCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LotteryCategory::Daily => write!(f, "DAILY"),
+            LotteryCategory::Weekly => write!(f, "WEEKLY"),
+            LotteryCategory::Charity => write!(f, "CHARITY"),
+            LotteryCategory::Partner => write!(f, "PARTNER"),
+        }
+    }
+}
+
+impl Encode<'_, Postgres> for LotteryCategory {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        let str_value = match self {
+            LotteryCategory::Daily => "DAILY",
+            LotteryCategory::Weekly => "WEEKLY",
+            LotteryCategory::Charity => "CHARITY",
+            LotteryCategory::Partner => "PARTNER",
+        };
+        Encode::<Postgres>::encode(str_value, buf)
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for LotteryCategory {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        let str_value = value.as_str().unwrap_or("");
+        match str_value {
+            "DAILY" => Ok(LotteryCategory::Daily),
+            "WEEKLY" => Ok(LotteryCategory::Weekly),
+            "CHARITY" => Ok(LotteryCategory::Charity),
+            "PARTNER" => Ok(LotteryCategory::Partner),
+            _ => Err(sqlx::Error::Decode(
+                format!("Invalid lottery_category value: {}", str_value).into(),
+            )
+            .into()),
+        }
+    }
+}
+
+impl Type<Postgres> for LotteryCategory {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("VARCHAR")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATUSES: [LotteryStatus; 4] = [
+        LotteryStatus::Scheduled,
+        LotteryStatus::Ongoing,
+        LotteryStatus::Completed,
+        LotteryStatus::Cancelled,
+    ];
+
+    #[test]
+    fn test_can_transition_to() {
+        let allowed = [
+            (LotteryStatus::Scheduled, LotteryStatus::Ongoing),
+            (LotteryStatus::Ongoing, LotteryStatus::Completed),
+            (LotteryStatus::Ongoing, LotteryStatus::Cancelled),
+        ];
+
+        for from in STATUSES {
+            for to in STATUSES {
+                assert_eq!(
+                    from.can_transition_to(to),
+                    allowed.contains(&(from, to)),
+                    "{} -> {}",
+                    from.to_string(),
+                    to.to_string(),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_final_statuses_are_never_left() {
+        for to in STATUSES {
+            assert!(!LotteryStatus::Completed.can_transition_to(to));
+            assert!(!LotteryStatus::Cancelled.can_transition_to(to));
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::lottery::LotteryStatus;
+
+/// Represents a status transition applied to a lottery.
+///
+/// # Fields
+///
+/// - `from_status` - The status before the transition, empty for the status the lottery was created with.
+/// - `to_status` - The status after the transition.
+/// - `chain`, `block_number`, `transaction_hash`, `log_index` - The event which caused the transition,
+///   empty when it was not triggered by a chain event.
+#[derive(Clone, Debug, PartialEq, Eq, FromRow, Serialize, Deserialize)]
+pub struct LotteryStatusHistoryModel {
+    pub id: Uuid,
+    pub lottery_id: Uuid,
+    pub from_status: Option<LotteryStatus>,
+    pub to_status: LotteryStatus,
+    pub chain: Option<String>,
+    pub block_number: Option<i64>,
+    pub transaction_hash: Option<String>,
+    pub log_index: Option<i32>,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Represents a broker message written in the same transaction as the change it announces.
+///
+/// Messages are published by the outbox relay once the transaction is committed, so
+/// subscribers never see changes that were rolled back.
+///
+/// # Fields
+///
+/// - `dedup_key` - Identifies the change, a message enqueued twice for the same change is ignored.
+/// - `attempts` - Number of failed publication attempts.
+/// - `last_error` - Why the last publication attempt failed.
+/// - `claimed_until` - Until when a relay is publishing the message, other relays skip it meanwhile.
+/// - `published_at` - When the message was handed over to the broker.
+#[derive(Clone, Debug, PartialEq, Eq, FromRow, Serialize, Deserialize)]
+pub struct OutboxModel {
+    pub id: Uuid,
+    pub channel: String,
+    pub payload: serde_json::Value,
+    pub dedup_key: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    #[serde(default)]
+    pub claimed_until: Option<DateTime<Utc>>,
+    pub published_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
@@ -15,6 +15,8 @@ pub struct PrizeModel {
     pub prize_asset: Uuid,
     pub value: Decimal,
     pub status: PrizeStatus, // Enum to represent the status of the prize
+    pub source: PrizeSource, // Who funds the prize
+    pub sponsor: Option<Uuid>, // Sponsor account, only for sponsor prizes
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -67,4 +69,50 @@ impl Type<Postgres> for PrizeStatus {
     fn type_info() -> PgTypeInfo {
         PgTypeInfo::with_name("VARCHAR")
     }
-}
\ No newline at end of file
+}
+#[derive(Default, Clone, Debug, Serialize, Deserialize, Enum, Copy, Eq, PartialEq)]
+pub enum PrizeSource {
+    #[default]
+    Tickets,
+    Sponsor,
+}
+
+impl std::fmt::Display for PrizeSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str_value = match self {
+            PrizeSource::Tickets => "TICKETS",
+            PrizeSource::Sponsor => "SPONSOR",
+        };
+        write!(f, "{}", str_value)
+    }
+}
+
+impl Encode<'_, Postgres> for PrizeSource {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        let str_value = match self {
+            PrizeSource::Tickets => "TICKETS",
+            PrizeSource::Sponsor => "SPONSOR",
+        };
+        Encode::<Postgres>::encode(str_value, buf)
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for PrizeSource {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        let str_value = value.as_str().unwrap_or("");
+        match str_value {
+            "TICKETS" => Ok(PrizeSource::Tickets),
+            "SPONSOR" => Ok(PrizeSource::Sponsor),
+            _ => Err(sqlx::Error::Decode(
+                format!("Invalid prize_source value: {}", str_value).into(),
+            )
+            .into()),
+        }
+    }
+}
+
+impl Type<Postgres> for PrizeSource {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("VARCHAR")
+    }
+}
@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Represents a chain log the indexer could not turn into an event.
+///
+/// The log is stored verbatim, so it can be decoded again once the contract bindings
+/// are updated instead of being re-fetched from the chain.
+///
+/// # Fields
+///
+/// - `topics` - Hex encoded topics of the log, the first one being the event signature.
+/// - `data` - Hex encoded data of the log.
+/// - `reason` - Why the log was rejected by the transformer.
+/// - `reprocessed_at` - When the log was successfully decoded and handled from the archive.
+#[derive(Clone, Debug, PartialEq, Eq, FromRow, Serialize, Deserialize)]
+pub struct RawLogModel {
+    pub id: Uuid,
+    pub chain: String,
+    pub address: String,
+    pub topics: Vec<String>,
+    pub data: String,
+    pub block_number: Option<i64>,
+    pub transaction_hash: Option<String>,
+    pub log_index: Option<i32>,
+    pub reason: String,
+    pub reprocessed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
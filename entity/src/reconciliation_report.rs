@@ -0,0 +1,83 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef};
+use sqlx::{Decode, Encode, FromRow, Postgres, Type};
+use uuid::Uuid;
+
+/// Represents a drift found between the indexed state of a lottery and the chain.
+///
+/// # Fields
+///
+/// - `chain` - The name of the chain the lottery was checked against.
+/// - `account_id` - The participant the drift relates to, if any.
+/// - `expected` - The value reported by the contract.
+/// - `actual` - The value stored in the database.
+/// - `repaired` - Whether the database was corrected to match the chain.
+#[derive(Clone, Debug, PartialEq, Eq, FromRow, Serialize, Deserialize)]
+pub struct ReconciliationReportModel {
+    pub id: Uuid,
+    pub chain: String,
+    pub lottery_id: Uuid,
+    pub account_id: Option<Uuid>,
+    pub kind: ReconciliationDriftKind,
+    pub expected: String,
+    pub actual: String,
+    pub repaired: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Copy)]
+pub enum ReconciliationDriftKind {
+    SoldTickets,
+    Status,
+    Winner,
+    ParticipantTickets,
+}
+
+impl std::fmt::Display for ReconciliationDriftKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str_value = match self {
+            ReconciliationDriftKind::SoldTickets => "SOLD_TICKETS",
+            ReconciliationDriftKind::Status => "STATUS",
+            ReconciliationDriftKind::Winner => "WINNER",
+            ReconciliationDriftKind::ParticipantTickets => "PARTICIPANT_TICKETS",
+        };
+        write!(f, "{}", str_value)
+    }
+}
+
+impl Encode<'_, Postgres> for ReconciliationDriftKind {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        let str_value = match self {
+            ReconciliationDriftKind::SoldTickets => "SOLD_TICKETS",
+            ReconciliationDriftKind::Status => "STATUS",
+            ReconciliationDriftKind::Winner => "WINNER",
+            ReconciliationDriftKind::ParticipantTickets => "PARTICIPANT_TICKETS",
+        };
+        Encode::<Postgres>::encode(str_value, buf)
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for ReconciliationDriftKind {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        let str_value = value.as_str().unwrap_or("");
+        match str_value {
+            "SOLD_TICKETS" => Ok(ReconciliationDriftKind::SoldTickets),
+            "STATUS" => Ok(ReconciliationDriftKind::Status),
+            "WINNER" => Ok(ReconciliationDriftKind::Winner),
+            "PARTICIPANT_TICKETS" => Ok(ReconciliationDriftKind::ParticipantTickets),
+            _ => Err(sqlx::Error::Decode(
+                format!("Invalid reconciliation_drift_kind value: {}", str_value).into(),
+            )
+            .into()),
+        }
+    }
+}
+
+impl Type<Postgres> for ReconciliationDriftKind {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("VARCHAR")
+    }
+}
@@ -0,0 +1,26 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{prelude::FromRow, types::Decimal};
+use uuid::Uuid;
+
+/// Represents the reward accrued to a referrer on a purchase of an account it referred.
+///
+/// # Fields
+///
+/// - `referrer_id` - The account rewarded.
+/// - `referred_id` - The account which bought the tickets.
+/// - `ticket_id` - The purchase, rewarded at most once.
+/// - `spent` - Amount spent on the purchase, in `asset_id`.
+/// - `rate_bps` - Basis points of `spent` accrued at the time of the purchase.
+#[derive(Clone, Debug, PartialEq, Eq, FromRow, Serialize, Deserialize)]
+pub struct ReferralRewardModel {
+    pub id: Uuid,
+    pub referrer_id: Uuid,
+    pub referred_id: Uuid,
+    pub ticket_id: Uuid,
+    pub asset_id: Uuid,
+    pub spent: Decimal,
+    pub rate_bps: i32,
+    pub amount: Decimal,
+    pub created_at: DateTime<Utc>,
+}
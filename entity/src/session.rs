@@ -0,0 +1,27 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Represents a login of an account, kept alive by rotating its refresh token.
+///
+/// # Fields
+///
+/// - `refresh_token_hash` - Hash of the current refresh token, the token itself is never stored.
+/// - `previous_refresh_token_hash` - Hash of the refresh token rotated last, presenting it again revokes the session.
+/// - `access_token_id` - `jti` of the last access token issued for the session.
+/// - `refreshed_at` - When the refresh token was last rotated.
+/// - `expires_at` - When the refresh token expires, extended on every rotation.
+/// - `revoked_at` - When the session was logged out or revoked.
+#[derive(Clone, Debug, PartialEq, Eq, FromRow, Serialize, Deserialize)]
+pub struct SessionModel {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub refresh_token_hash: String,
+    pub previous_refresh_token_hash: Option<String>,
+    pub access_token_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub refreshed_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
@@ -11,8 +11,19 @@ pub struct TicketModel {
     pub ticket_price: Decimal,
     pub ticket_asset: Uuid,
     pub amount: i32, // Amount of the tickets bought
+    pub first_number: i32, // First ticket number of the purchase (inclusive)
+    pub last_number: i32, // Last ticket number of the purchase (inclusive)
+    pub block_number: i64,
+    pub log_index: i32,
     pub purchased_at: DateTime<Utc>,
     pub transaction_hash: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
-}
\ No newline at end of file
+}
+
+impl TicketModel {
+    /// Check if provided ticket number belongs to this purchase
+    pub fn contains_number(&self, number: i32) -> bool {
+        self.first_number <= number && number <= self.last_number
+    }
+}
@@ -0,0 +1,31 @@
+use async_graphql::{Context, Guard};
+use entity::account::AccountRole;
+
+use crate::objects::GQLJWTData;
+
+/// Allow requests of accounts having at least the role, e.g.
+/// `#[graphql(guard = "RoleGuard::new(AccountRole::Admin)")]`
+pub struct RoleGuard {
+    role: AccountRole,
+}
+
+impl RoleGuard {
+    pub fn new(role: AccountRole) -> Self {
+        RoleGuard { role }
+    }
+}
+
+impl Guard for RoleGuard {
+    async fn check(&self, ctx: &Context<'_>) -> async_graphql::Result<()> {
+        let claims = ctx
+            .data_opt::<GQLJWTData>()
+            .and_then(|rd| rd.claims.as_ref())
+            .ok_or(async_graphql::Error::from("Unauthorized request"))?;
+
+        if claims.role < self.role {
+            return Err("Forbidden request".into());
+        }
+
+        Ok(())
+    }
+}
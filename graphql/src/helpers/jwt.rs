@@ -1,15 +1,24 @@
 use std::sync::Arc;
 
 use chrono::{Duration, Utc};
+use entity::account::AccountRole;
 use jsonwebtoken::{
     errors::Error, Algorithm, DecodingKey, EncodingKey, Header, TokenData, Validation,
 };
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
     pub exp: i64,
+    /// Token id, checked against the revocation list
+    pub jti: Uuid,
+    /// Session the token was issued for
+    pub sid: Uuid,
+    /// Role of the account when the token was issued
+    #[serde(default)]
+    pub role: AccountRole,
 }
 
 #[derive(Clone)]
@@ -31,11 +40,22 @@ impl JWT {
         })))
     }
 
-    pub fn encode(&self, address: String, ttl: Option<i64>) -> Result<String, Error> {
-        let exp = Utc::now() + Duration::days(ttl.unwrap_or(90));
+    /// Encode an access token valid for `ttl` seconds
+    pub fn encode(
+        &self,
+        address: String,
+        role: AccountRole,
+        session_id: Uuid,
+        token_id: Uuid,
+        ttl: i64,
+    ) -> Result<String, Error> {
+        let exp = Utc::now() + Duration::seconds(ttl);
         let claim = Claims {
             sub: address,
             exp: exp.timestamp(),
+            jti: token_id,
+            sid: session_id,
+            role,
         };
 
         self.encode_with_claims(&claim)
@@ -12,10 +12,11 @@ use crate::server::Server;
 use error_stack::Result;
 use futures::future;
 use lib::error::Error;
+use std::sync::atomic::Ordering;
 use service::{
-     cache::service::CacheService, common::shutdown::ShutdownFlag, config::service::ConfigService, message_broker::MessageBrokerService, services::ServiceProvider, store::service::StoreService
+     cache::{invalidation::invalidate_on_events, service::CacheService}, common::shutdown::{await_shutdown_signal, spawn_ctrl_c_listener}, config::service::ConfigService, message_broker::{relay, MessageBrokerService}, services::ServiceProvider, store::service::StoreService
 };
-use tracing::{info, warn, Instrument};
+use tracing::{error, info, warn, Instrument};
 
 pub async fn start(config: ConfigService) -> Result<(), Error> {
     info!(version = %env!("CARGO_PKG_VERSION"), "Starting GraphQL");
@@ -28,6 +29,18 @@ pub async fn start(config: ConfigService) -> Result<(), Error> {
     services.warm_up::<CacheService>().await;
     services.warm_up::<MessageBrokerService>().await;
 
+    let cache = services.get_service_unchecked::<CacheService>().await;
+    let broker = services.get_service_unchecked::<MessageBrokerService>().await;
+    tokio::spawn(async move {
+        if let Err(e) = invalidate_on_events(cache, broker).await {
+            error!("Failed to invalidate cache on broker events: {e:?}");
+        }
+    });
+
+    // Mutations write to the outbox too, their messages are published even while no indexer runs
+    let shutdown = spawn_ctrl_c_listener();
+    let relay = relay::spawn(services.clone(), shutdown.clone());
+
     info!("Service is listening at {}", config.graphql.listen);
     info!("GraphQL endpoint exposed at {}", config.graphql.endpoint);
     info!(
@@ -45,9 +58,20 @@ pub async fn start(config: ConfigService) -> Result<(), Error> {
 
     let server = Server::new(services);
     let server = server.start().in_current_span();
-    
-    if let Err(err) = server.await {
-        warn!("Error while server task: {}", err);
+
+    tokio::select! {
+        result = server => {
+            if let Err(err) = result {
+                warn!("Error while server task: {}", err);
+            }
+        }
+        _ = await_shutdown_signal(shutdown.clone()) => (),
+    }
+
+    // Let the relay finish publishing the messages it claimed
+    shutdown.store(true, Ordering::Release);
+    if let Err(e) = relay.await {
+        error!("Outbox relay failed: {e:?}");
     }
 
     Ok(())
@@ -1,7 +1,9 @@
 use async_graphql::dataloader::Loader;
 use entity::prelude::AccountModel;
+use error_stack::Report;
 use lib::error::Error;
 use service::account::store::AccountStore;
+use service::account::types::AccountStats;
 use service::store::service::StoreService;
 use std::{collections::HashMap, sync::Arc};
 use uuid::Uuid;
@@ -43,3 +45,28 @@ impl Loader<Uuid> for AccountLoader {
         Ok(map)
     }
 }
+
+/// Load the activity summary of accounts, keyed by account id
+pub struct AccountStatsLoader {
+    store: Arc<StoreService>,
+}
+
+impl AccountStatsLoader {
+    pub fn new(store: Arc<StoreService>) -> Self {
+        AccountStatsLoader { store }
+    }
+}
+
+impl Loader<Uuid> for AccountStatsLoader {
+    type Value = AccountStats;
+    type Error = Arc<Report<Error>>;
+
+    async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, Self::Value>, Self::Error> {
+        // Stats are loaded on cache misses, a replica may lag behind the invalidating event
+        let pool = self.store.write();
+
+        AccountStore::find_stats_by_account_ids(pool, keys.to_vec())
+            .await
+            .map_err(Arc::new)
+    }
+}
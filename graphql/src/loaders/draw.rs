@@ -0,0 +1,39 @@
+use async_graphql::dataloader::Loader;
+use entity::draw::DrawModel;
+use error_stack::Report;
+use lib::error::Error;
+use service::draw::store::DrawStore;
+use service::store::service::StoreService;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Load the draws of lotteries ordered by tier, keyed by lottery id
+pub struct LotteryDrawsLoader {
+    store: Arc<StoreService>,
+}
+
+impl LotteryDrawsLoader {
+    pub fn new(store: Arc<StoreService>) -> Self {
+        LotteryDrawsLoader { store }
+    }
+}
+
+impl Loader<Uuid> for LotteryDrawsLoader {
+    type Value = Vec<DrawModel>;
+    type Error = Arc<Report<Error>>;
+
+    async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, Self::Value>, Self::Error> {
+        let pool = self.store.read();
+        let results = DrawStore::find_all_by_lottery_ids(pool, keys.to_vec())
+            .await
+            .map_err(Arc::new)?;
+
+        let mut map: HashMap<Uuid, Self::Value> = HashMap::new();
+        for result in results {
+            map.entry(result.lottery_id).or_default().push(result);
+        }
+
+        Ok(map)
+    }
+}
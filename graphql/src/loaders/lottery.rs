@@ -0,0 +1,100 @@
+use async_graphql::dataloader::Loader;
+use entity::prelude::{LotteryModel, LotteryStatusHistoryModel};
+use error_stack::Report;
+use lib::error::Error;
+use service::lottery::store::{LotteryStatusHistoryStore, LotteryStore};
+use service::store::service::StoreService;
+use service::ticket::store::TicketStore;
+use service::ticket::types::LotteryTicketStats;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub struct LotteryLoader {
+    store: Arc<StoreService>,
+}
+
+impl LotteryLoader {
+    pub fn new(store: Arc<StoreService>) -> Self {
+        LotteryLoader { store }
+    }
+}
+
+impl Loader<Uuid> for LotteryLoader {
+    type Value = LotteryModel;
+    type Error = Arc<Report<Error>>;
+
+    async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, Self::Value>, Self::Error> {
+        let pool = self.store.read();
+        let results = LotteryStore::find_all_by_ids(pool, keys.to_vec())
+            .await
+            .map_err(Arc::new)?;
+
+        let mut map = HashMap::new();
+        for result in results {
+            map.insert(result.id, result);
+        }
+
+        Ok(map)
+    }
+}
+
+/// Load the status history of lotteries, keyed by lottery id
+pub struct LotteryStatusHistoryLoader {
+    store: Arc<StoreService>,
+}
+
+impl LotteryStatusHistoryLoader {
+    pub fn new(store: Arc<StoreService>) -> Self {
+        LotteryStatusHistoryLoader { store }
+    }
+}
+
+impl Loader<Uuid> for LotteryStatusHistoryLoader {
+    type Value = Vec<LotteryStatusHistoryModel>;
+    type Error = Arc<Report<Error>>;
+
+    async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, Self::Value>, Self::Error> {
+        let pool = self.store.read();
+        let results = LotteryStatusHistoryStore::find_by_lottery_ids(pool, keys.to_vec())
+            .await
+            .map_err(Arc::new)?;
+
+        let mut map: HashMap<Uuid, Self::Value> = HashMap::new();
+        for result in results {
+            map.entry(result.lottery_id).or_default().push(result);
+        }
+
+        Ok(map)
+    }
+}
+
+/// Load the ticket sales of lotteries, keyed by lottery id
+pub struct LotteryTicketStatsLoader {
+    store: Arc<StoreService>,
+}
+
+impl LotteryTicketStatsLoader {
+    pub fn new(store: Arc<StoreService>) -> Self {
+        LotteryTicketStatsLoader { store }
+    }
+}
+
+impl Loader<Uuid> for LotteryTicketStatsLoader {
+    type Value = LotteryTicketStats;
+    type Error = Arc<Report<Error>>;
+
+    async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, Self::Value>, Self::Error> {
+        let pool = self.store.read();
+        let results = TicketStore::find_stats_by_lottery_ids(pool, keys.to_vec())
+            .await
+            .map_err(Arc::new)?;
+
+        let mut map = HashMap::new();
+        for result in results {
+            map.insert(result.lottery_id, result);
+        }
+
+        Ok(map)
+    }
+}
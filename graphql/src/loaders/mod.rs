@@ -1,5 +1,13 @@
 pub mod account;
 pub mod asset;
+pub mod draw;
+pub mod lottery;
+pub mod prize;
+pub mod ticket;
 
 pub use account::*;
 pub use asset::*;
+pub use draw::*;
+pub use lottery::*;
+pub use prize::*;
+pub use ticket::*;
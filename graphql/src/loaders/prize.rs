@@ -0,0 +1,39 @@
+use async_graphql::dataloader::Loader;
+use entity::prize::PrizeModel;
+use error_stack::Report;
+use lib::error::Error;
+use service::prize::store::PrizeStore;
+use service::store::service::StoreService;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Load every prize component of lotteries, keyed by lottery id
+pub struct LotteryPrizesLoader {
+    store: Arc<StoreService>,
+}
+
+impl LotteryPrizesLoader {
+    pub fn new(store: Arc<StoreService>) -> Self {
+        LotteryPrizesLoader { store }
+    }
+}
+
+impl Loader<Uuid> for LotteryPrizesLoader {
+    type Value = Vec<PrizeModel>;
+    type Error = Arc<Report<Error>>;
+
+    async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, Self::Value>, Self::Error> {
+        let pool = self.store.read();
+        let results = PrizeStore::find_all_by_lottery_ids(pool, keys.to_vec())
+            .await
+            .map_err(Arc::new)?;
+
+        let mut map: HashMap<Uuid, Self::Value> = HashMap::new();
+        for result in results {
+            map.entry(result.lottery_id).or_default().push(result);
+        }
+
+        Ok(map)
+    }
+}
@@ -0,0 +1,38 @@
+use async_graphql::dataloader::Loader;
+use entity::ticket::TicketModel;
+use error_stack::Report;
+use lib::error::Error;
+use service::store::service::StoreService;
+use service::ticket::store::TicketStore;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub struct TicketLoader {
+    store: Arc<StoreService>,
+}
+
+impl TicketLoader {
+    pub fn new(store: Arc<StoreService>) -> Self {
+        TicketLoader { store }
+    }
+}
+
+impl Loader<Uuid> for TicketLoader {
+    type Value = TicketModel;
+    type Error = Arc<Report<Error>>;
+
+    async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, Self::Value>, Self::Error> {
+        let pool = self.store.read();
+        let results = TicketStore::find_all_by_ids(pool, keys.to_vec())
+            .await
+            .map_err(Arc::new)?;
+
+        let mut map = HashMap::new();
+        for result in results {
+            map.insert(result.id, result);
+        }
+
+        Ok(map)
+    }
+}
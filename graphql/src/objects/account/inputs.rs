@@ -11,6 +11,25 @@ pub struct LoginSignatureInput {
     /// Signature for provided timestamp
     #[graphql(validator(min_length = 130, max_length = 130))]
     pub signature: String,
+
+    /// Referral code claimed by the account, only when it is created by this login
+    #[graphql(validator(max_length = 16))]
+    pub referral_code: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, InputObject)]
+pub struct LoginSiweInput {
+    /// EIP-4361 message, its nonce must come from `loginNonce`
+    #[graphql(validator(max_length = 4096))]
+    pub message: String,
+
+    /// Signature for provided message
+    #[graphql(validator(min_length = 130, max_length = 132))]
+    pub signature: String,
+
+    /// Referral code claimed by the account, only when it is created by this login
+    #[graphql(validator(max_length = 16))]
+    pub referral_code: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, InputObject)]
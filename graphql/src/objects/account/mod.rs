@@ -1,23 +1,33 @@
 pub mod inputs;
+pub mod subscriptions;
 pub mod types;
 
-use self::inputs::{LoginSignatureInput, UpdateAccountInput};
-use self::types::{AccountType, AuthType};
+use self::inputs::{LoginSignatureInput, LoginSiweInput, UpdateAccountInput};
+use self::types::{AccountType, AuthType, ReferralType, SessionType};
 use async_graphql::{Context, Object, Subscription};
 use chrono::Utc;
+use ethers::types::Address;
 use futures::{Stream, StreamExt};
 use lib::crypto::recover_address;
 use service::account::store::AccountStore;
 use service::account::types::{CreateAccount, UpdateAccount};
 use service::account::AccountService;
+use entity::account::{AccountModel, AccountRole};
+use service::auth::{AuthService, SessionTokens};
+use service::config::service::ConfigService;
+use service::referral::ReferralService;
 use service::{
     prelude::StoreService,
     services::ServiceProvider,
 };
+use std::str::FromStr;
+use uuid::Uuid;
+use lib::error::Error;
 use tracing::warn;
 
 use crate::guards::auth::AuthGuard;
-use crate::helpers::jwt::JWT;
+use crate::guards::role::RoleGuard;
+use crate::helpers::jwt::{Claims, JWT};
 use crate::objects::GQLJWTData;
 
 #[derive(Default)]
@@ -47,6 +57,46 @@ impl AccountQuery {
 
         Ok(account.into())
     }
+
+    /// Get a single-use nonce to include in the message signed for `loginWithSiwe`
+    async fn login_nonce(&self, ctx: &Context<'_>) -> async_graphql::Result<String> {
+        let services = ctx.data_unchecked::<ServiceProvider>();
+        let auth = services.get_service_unchecked::<AuthService>().await;
+
+        auth.issue_nonce().await.map_err(|e| {
+            warn!("Failed to issue login nonce: {e:?}");
+            async_graphql::Error::from("Internal error")
+        })
+    }
+
+    /// Referral program of the current account
+    #[graphql(guard = "AuthGuard::new()")]
+    async fn referral(&self, ctx: &Context<'_>) -> async_graphql::Result<ReferralType> {
+        let (account, _) = current_account(ctx).await?;
+
+        Ok(account.into())
+    }
+
+    /// Sessions of the current account that can still be refreshed
+    #[graphql(guard = "AuthGuard::new()")]
+    async fn sessions(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<SessionType>> {
+        let services = ctx.data_unchecked::<ServiceProvider>();
+        let auth = services.get_service_unchecked::<AuthService>().await;
+
+        let (account, claims) = current_account(ctx).await?;
+        let sessions = auth.sessions(account.id).await.map_err(|e| {
+            warn!("Failed to get sessions: {e:?}");
+            async_graphql::Error::from("Internal error")
+        })?;
+
+        Ok(sessions
+            .into_iter()
+            .map(|session| SessionType {
+                current: session.id == claims.sid,
+                session,
+            })
+            .collect())
+    }
 }
 
 #[derive(Default)]
@@ -55,65 +105,201 @@ pub struct AccountMutation;
 #[Object]
 impl AccountMutation {
     /// Exchange signature to token that can be used to authorize user
+    #[graphql(deprecation = "Signed timestamps can be replayed, use `loginWithSiwe`")]
     pub async fn login_with_signature(
         &self,
         ctx: &Context<'_>,
         input: LoginSignatureInput,
     ) -> async_graphql::Result<AuthType> {
         let services = ctx.data_unchecked::<ServiceProvider>();
-        let store = services.get_service_unchecked::<StoreService>().await;
+        let config = services.get_service_unchecked::<ConfigService>().await;
+
+        if !config.graphql.siwe.allow_signature_login {
+            return Err(async_graphql::Error::new("Signature login is disabled, use `loginWithSiwe`"));
+        }
 
         // Recover address of the signer
         let recovered_address =
             recover_address(&input.timestamp, &input.signature).map_err(|_| {
                 async_graphql::Error::new("Failed to recover address from provided signature")
             })?;
-        let recovered_address = format!("{recovered_address:?}");
-
-        // Try to get signer, if user is new, it won't exist yet
-        let account = {
-            let pool = store.read();
-            let account = AccountStore::try_find_by_address(pool, recovered_address.clone())
-                .await
-                .map_err(|e| {
-                    warn!("{e:?}");
+
+        authenticate(ctx, format!("{recovered_address:?}"), input.referral_code).await
+    }
+
+    /// Exchange a signed Sign-In with Ethereum (EIP-4361) message to token that can be used
+    /// to authorize user, the message nonce is burned
+    pub async fn login_with_siwe(
+        &self,
+        ctx: &Context<'_>,
+        input: LoginSiweInput,
+    ) -> async_graphql::Result<AuthType> {
+        let services = ctx.data_unchecked::<ServiceProvider>();
+        let auth = services.get_service_unchecked::<AuthService>().await;
+
+        let siwe = auth
+            .verify_siwe(&input.message, &input.signature)
+            .await
+            .map_err(|e| match e.current_context() {
+                Error::Redis => {
+                    warn!("Failed to verify SIWE message: {e:?}");
                     async_graphql::Error::from("Internal error")
-                })?;
-
-            if account.is_none() {
-                let service = services.get_service_unchecked::<AccountService>().await;
-
-                let mut db_tx = store.begin_transaction().await.map_err(|e| {
-                    warn!("Failed to start transaction: {e:?}");
-                    async_graphql::Error::new("Internal error")
-                })?;
-                
-                let create_account_dto = CreateAccount {
-                    address: recovered_address.clone(),
-                    created_at: Utc::now(),
-                };
-
-                let account = service
-                    .create_if_no_exists(create_account_dto, &mut db_tx)
-                    .await?;
-
-                store.commit_transaction(db_tx).await.map_err(|e| {
-                    warn!("Failed to commit transaction: {e:?}");
-                    async_graphql::Error::new("Internal error")
-                })?;
-
-                Some(account)
-            } else {
-                account
-            }
-        };
+                }
+                context => async_graphql::Error::new(context.to_string()),
+            })?;
 
-        let jwt = ctx.data_unchecked::<JWT>();
-        Ok(AuthType {
-            access_token: jwt.encode(recovered_address.clone(), None)?,
-            address: recovered_address,
-            account,
-        })
+        authenticate(ctx, format!("{:?}", siwe.address), input.referral_code).await
+    }
+
+    /// Exchange refresh token to new access and refresh tokens, the provided refresh token can't
+    /// be used again
+    pub async fn refresh_token(
+        &self,
+        ctx: &Context<'_>,
+        refresh_token: String,
+    ) -> async_graphql::Result<AuthType> {
+        let services = ctx.data_unchecked::<ServiceProvider>();
+        let store_service = services.get_service_unchecked::<StoreService>().await;
+        let auth = services.get_service_unchecked::<AuthService>().await;
+
+        let tokens = auth
+            .refresh_session(&refresh_token)
+            .await
+            .map_err(|e| match e.current_context() {
+                Error::SessionInvalid => {
+                    warn!("Refresh token rejected: {e:?}");
+                    async_graphql::Error::new(Error::SessionInvalid.to_string())
+                }
+                _ => {
+                    warn!("Failed to refresh session: {e:?}");
+                    async_graphql::Error::from("Internal error")
+                }
+            })?;
+
+        let account = AccountStore::find_by_id(store_service.read(), tokens.session.account_id)
+            .await
+            .map_err(|e| {
+                warn!("Failed to get account: {e:?}");
+                async_graphql::Error::from("Internal error")
+            })?;
+
+        issue_tokens(ctx, account, tokens).await
+    }
+
+    /// Revoke the session of the current token
+    #[graphql(guard = "AuthGuard::new()")]
+    pub async fn logout(&self, ctx: &Context<'_>) -> async_graphql::Result<bool> {
+        let services = ctx.data_unchecked::<ServiceProvider>();
+        let auth = services.get_service_unchecked::<AuthService>().await;
+
+        let (account, claims) = current_account(ctx).await?;
+        auth.revoke_session(claims.sid, Some(account.id))
+            .await
+            .map_err(|e| {
+                warn!("Failed to revoke session: {e:?}");
+                async_graphql::Error::from("Internal error")
+            })?;
+
+        Ok(true)
+    }
+
+    /// Revoke a session of the current account, e.g. one of a lost device
+    #[graphql(guard = "AuthGuard::new()")]
+    pub async fn revoke_session(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<bool> {
+        let services = ctx.data_unchecked::<ServiceProvider>();
+        let auth = services.get_service_unchecked::<AuthService>().await;
+
+        let (account, _) = current_account(ctx).await?;
+        auth.revoke_session(id, Some(account.id))
+            .await
+            .map_err(|e| {
+                warn!("Failed to revoke session: {e:?}");
+                async_graphql::Error::from("Internal error")
+            })?
+            .ok_or(async_graphql::Error::from("Session not found"))?;
+
+        Ok(true)
+    }
+
+    /// Grant the role to the account with provided address, it applies to its next access token
+    #[graphql(guard = "RoleGuard::new(AccountRole::Admin)")]
+    async fn grant_role(
+        &self,
+        ctx: &Context<'_>,
+        address: String,
+        role: AccountRole,
+    ) -> async_graphql::Result<AccountType> {
+        let services = ctx.data_unchecked::<ServiceProvider>();
+        let store_service = services.get_service_unchecked::<StoreService>().await;
+        let account_service = services.get_service_unchecked::<AccountService>().await;
+
+        // Addresses are stored checksum-less lowercase, as formatted by `Address`
+        let address = Address::from_str(&address)
+            .map_err(|_| async_graphql::Error::new(Error::InvalidAddress.to_string()))?;
+
+        let account = AccountStore::try_find_by_address(store_service.read(), format!("{address:?}"))
+            .await
+            .map_err(|e| {
+                warn!("Failed to get account: {e:?}");
+                async_graphql::Error::from("Internal error")
+            })?
+            .ok_or(async_graphql::Error::from("Account not found"))?;
+
+        let mut db_tx = store_service.begin_transaction().await.map_err(|e| {
+            warn!("Failed to start transaction: {e:?}");
+            async_graphql::Error::new("Internal error")
+        })?;
+
+        let account = account_service
+            .grant_role(account.id, role, &mut db_tx)
+            .await
+            .map_err(|e| match e.current_context() {
+                Error::AccountLastAdmin => async_graphql::Error::new(Error::AccountLastAdmin.to_string()),
+                _ => {
+                    warn!("Failed to grant role: {e:?}");
+                    async_graphql::Error::new("Failed to grant role")
+                }
+            })?;
+
+        store_service.commit_transaction(db_tx).await.map_err(|e| {
+            warn!("Failed to commit transaction: {e:?}");
+            async_graphql::Error::new("Internal error")
+        })?;
+
+        Ok(account.into())
+    }
+
+    /// Generate the referral code of the current account, the existing one is kept if any
+    #[graphql(guard = "AuthGuard::new()")]
+    async fn generate_referral_code(&self, ctx: &Context<'_>) -> async_graphql::Result<ReferralType> {
+        let services = ctx.data_unchecked::<ServiceProvider>();
+        let store_service = services.get_service_unchecked::<StoreService>().await;
+        let referral_service = services.get_service_unchecked::<ReferralService>().await;
+
+        let (account, _) = current_account(ctx).await?;
+        if account.referral_code.is_some() {
+            return Ok(account.into());
+        }
+
+        let mut db_tx = store_service.begin_transaction().await.map_err(|e| {
+            warn!("Failed to start transaction: {e:?}");
+            async_graphql::Error::new("Internal error")
+        })?;
+
+        let account = referral_service
+            .generate_code(account.id, &mut db_tx)
+            .await
+            .map_err(|e| {
+                warn!("Failed to generate referral code: {e:?}");
+                async_graphql::Error::new("Failed to generate referral code")
+            })?;
+
+        store_service.commit_transaction(db_tx).await.map_err(|e| {
+            warn!("Failed to commit transaction: {e:?}");
+            async_graphql::Error::new("Internal error")
+        })?;
+
+        Ok(account.into())
     }
 
     /// Update current account with provided data
@@ -158,6 +344,101 @@ impl AccountMutation {
     }
 }
 
+/// Open a session for the address, creating its account on first login, referred by the
+/// owner of the referral code if any
+async fn authenticate(
+    ctx: &Context<'_>,
+    address: String,
+    referral_code: Option<String>,
+) -> async_graphql::Result<AuthType> {
+    let services = ctx.data_unchecked::<ServiceProvider>();
+    let store = services.get_service_unchecked::<StoreService>().await;
+    let account_service = services.get_service_unchecked::<AccountService>().await;
+    let auth = services.get_service_unchecked::<AuthService>().await;
+
+    let mut db_tx = store.begin_transaction().await.map_err(|e| {
+        warn!("Failed to start transaction: {e:?}");
+        async_graphql::Error::new("Internal error")
+    })?;
+
+    // Signer won't have an account yet if user is new
+    let create_account_dto = CreateAccount {
+        address: address.clone(),
+        created_at: Utc::now(),
+        referral_code,
+    };
+
+    let account = account_service
+        .create_if_no_exists(create_account_dto, &mut db_tx)
+        .await
+        .map_err(|e| {
+            warn!("Failed to create account: {e:?}");
+            async_graphql::Error::new("Internal error")
+        })?;
+
+    let tokens = auth
+        .create_session(account.id, &mut db_tx)
+        .await
+        .map_err(|e| {
+            warn!("Failed to create session: {e:?}");
+            async_graphql::Error::new("Internal error")
+        })?;
+
+    store.commit_transaction(db_tx).await.map_err(|e| {
+        warn!("Failed to commit transaction: {e:?}");
+        async_graphql::Error::new("Internal error")
+    })?;
+
+    issue_tokens(ctx, account, tokens).await
+}
+
+/// Encode the access token of the session, alongside its refresh token
+async fn issue_tokens(
+    ctx: &Context<'_>,
+    account: AccountModel,
+    tokens: SessionTokens,
+) -> async_graphql::Result<AuthType> {
+    let services = ctx.data_unchecked::<ServiceProvider>();
+    let config = services.get_service_unchecked::<ConfigService>().await;
+    let jwt = ctx.data_unchecked::<JWT>();
+
+    let access_token = jwt.encode(
+        account.address.clone(),
+        account.role,
+        tokens.session.id,
+        tokens.session.access_token_id,
+        config.jwt.ttl.access,
+    )?;
+
+    Ok(AuthType {
+        access_token,
+        refresh_token: tokens.refresh_token,
+        address: account.address.clone(),
+        account: Some(account),
+    })
+}
+
+/// Account of the authorized request
+pub(crate) async fn current_account<'a>(ctx: &'a Context<'_>) -> async_graphql::Result<(AccountModel, &'a Claims)> {
+    let services = ctx.data_unchecked::<ServiceProvider>();
+    let store_service = services.get_service_unchecked::<StoreService>().await;
+
+    let claims = ctx
+        .data_opt::<GQLJWTData>()
+        .and_then(|rd| rd.claims.as_ref())
+        .ok_or(async_graphql::Error::from("Not authorized"))?;
+
+    let account = AccountStore::try_find_by_address(store_service.read(), claims.sub.clone())
+        .await
+        .map_err(|e| {
+            warn!("Failed to get account: {e:?}");
+            async_graphql::Error::from("Internal error")
+        })?
+        .ok_or(async_graphql::Error::from("Account not found"))?;
+
+    Ok((account, claims))
+}
+
 #[derive(Default)]
 pub struct AccountSubscription;
 
@@ -0,0 +1,39 @@
+use async_graphql::{Context, Subscription};
+use futures::Stream;
+use service::account::store::AccountStore;
+use service::message_broker::{channels, Event};
+use service::prelude::{ServiceProvider, StoreService};
+use tracing::warn;
+
+use super::types::AchievementType;
+use crate::objects::common::subscription::{subscribe, SubscriptionEvent};
+
+#[derive(Default)]
+pub struct AchievementSubscription;
+
+#[Subscription]
+impl AchievementSubscription {
+    /// Emits achievements as they get unlocked by the account with `address`
+    async fn achievement_unlocked(
+        &self,
+        ctx: &Context<'_>,
+        address: String,
+        after_event_id: Option<i64>,
+    ) -> async_graphql::Result<impl Stream<Item = async_graphql::Result<SubscriptionEvent<AchievementType>>>> {
+        let services = ctx.data_unchecked::<ServiceProvider>();
+        let store_service = services.get_service_unchecked::<StoreService>().await;
+
+        let account = AccountStore::try_find_by_address(store_service.read(), address)
+            .await
+            .map_err(|e| {
+                warn!("Failed to get account: {e:?}");
+                async_graphql::Error::from("Internal error")
+            })?
+            .ok_or(async_graphql::Error::from("Account not found"))?;
+
+        subscribe(ctx, channels::ACHIEVEMENT_UNLOCKED, after_event_id, move |event| match event {
+            Event::AchievementUnlocked(unlock) if unlock.account_id == account.id => Some(unlock.into()),
+            _ => None,
+        }).await
+    }
+}
@@ -1,5 +1,20 @@
+use async_graphql::dataloader::DataLoader;
 use async_graphql::{Context, Object};
-use entity::prelude::AccountModel;
+use error_stack::Report;
+use lib::error::Error;
+use entity::prelude::{AccountModel, AccountRole};
+use service::achievement::store::AchievementStore;
+use service::account::types::AccountStats;
+use service::cache::{service::{CacheKey, CacheService}, tags};
+use service::prelude::{ServiceProvider, StoreService};
+use std::time::Duration;
+use tracing::warn;
+
+use super::{AccountStatsType, AchievementType};
+use crate::loaders::AccountStatsLoader;
+
+/// Stats are invalidated on the ticket and draw events of the account
+const STATS_CACHE_TTL: Duration = Duration::from_secs(300);
 
 pub struct AccountType(AccountModel);
 
@@ -19,6 +34,10 @@ impl AccountType {
         &self.0.address
     }
 
+    async fn role(&self) -> AccountRole {
+        self.0.role
+    }
+
     async fn avatar(&self, ctx: &Context<'_>) -> Option<String> {
         if let Some(ref avatar) = self.0.avatar {
             // let services = ctx.data_unchecked::<ServiceProvider>();
@@ -40,6 +59,47 @@ impl AccountType {
         &self.0.twitter
     }
 
+    /// Activity summary over every lottery the account took part in
+    async fn stats(&self, ctx: &Context<'_>) -> async_graphql::Result<AccountStatsType> {
+        let services = ctx.data_unchecked::<ServiceProvider>();
+        let cache = services.get_service_unchecked::<CacheService>().await;
+        let loader = ctx.data_unchecked::<DataLoader<AccountStatsLoader>>();
+
+        let account_id = self.0.id;
+        let key = CacheKey::new("account_stats", account_id);
+        let stats = cache
+            .get_or_load(&key, STATS_CACHE_TTL, |_: &AccountStats| vec![tags::account(account_id)], || async move {
+                // Misses of the accounts listed together are loaded in one batch
+                loader
+                    .load_one(account_id)
+                    .await
+                    .map(Option::unwrap_or_default)
+                    .map_err(|e| Report::new(Error::Store).attach_printable(format!("{e:?}")))
+            })
+            .await
+            .map_err(|e| {
+                warn!("Failed to fetch account stats: {e:?}");
+                async_graphql::Error::from("Internal error")
+            })?;
+
+        Ok(stats.into())
+    }
+
+    /// Achievements unlocked by the account, in unlock order
+    async fn achievements(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<AchievementType>> {
+        let services = ctx.data_unchecked::<ServiceProvider>();
+        let store_service = services.get_service_unchecked::<StoreService>().await;
+
+        let achievements = AchievementStore::find_all_by_account_id(store_service.read(), self.0.id)
+            .await
+            .map_err(|e| {
+                warn!("Failed to fetch achievements: {e:?}");
+                async_graphql::Error::from("Internal error")
+            })?;
+
+        Ok(achievements.into_iter().map(Into::into).collect())
+    }
+
     async fn created_at(&self) -> String {
         self.0.created_at.to_rfc3339()
     }
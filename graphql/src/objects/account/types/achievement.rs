@@ -0,0 +1,62 @@
+use async_graphql::dataloader::DataLoader;
+use async_graphql::{Context, Object};
+use entity::account_achievement::AccountAchievementModel;
+use service::config::service::ConfigService;
+use service::prelude::ServiceProvider;
+
+use crate::loaders::AccountLoader;
+
+use super::AccountType;
+
+pub struct AchievementType(AccountAchievementModel);
+
+impl From<AccountAchievementModel> for AchievementType {
+    fn from(item: AccountAchievementModel) -> Self {
+        AchievementType(item)
+    }
+}
+
+#[Object]
+impl AchievementType {
+    /// Represent the key of the achievement rule, e.g. `first_ticket`
+    async fn key(&self) -> &str {
+        &self.0.achievement
+    }
+
+    /// Represent the name of the achievement, unless its rule was removed
+    async fn name(&self, ctx: &Context<'_>) -> Option<String> {
+        let services = ctx.data_unchecked::<ServiceProvider>();
+        let config = services.get_service_unchecked::<ConfigService>().await;
+
+        config.achievements.rule(&self.0.achievement).map(|rule| rule.name.clone())
+    }
+
+    async fn description(&self, ctx: &Context<'_>) -> Option<String> {
+        let services = ctx.data_unchecked::<ServiceProvider>();
+        let config = services.get_service_unchecked::<ConfigService>().await;
+
+        config.achievements.rule(&self.0.achievement).map(|rule| rule.description.clone())
+    }
+
+    async fn account(&self, ctx: &Context<'_>) -> async_graphql::Result<AccountType> {
+        let loader = ctx.data_unchecked::<DataLoader<AccountLoader>>();
+
+        match loader.load_one(self.0.account_id).await {
+            Ok(Some(account)) => Ok(account.into()),
+            _ => Err(async_graphql::Error::new("Unable to find account")),
+        }
+    }
+
+    /// Represent the hash of the transaction which unlocked the achievement, if any
+    async fn transaction_hash(&self) -> Option<String> {
+        self.0
+            .context
+            .as_ref()
+            .and_then(|context| context.get("transaction_hash")?.as_str())
+            .map(str::to_string)
+    }
+
+    async fn unlocked_at(&self) -> String {
+        self.0.unlocked_at.to_rfc3339()
+    }
+}
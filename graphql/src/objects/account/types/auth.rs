@@ -4,8 +4,7 @@ use entity::prelude::AccountModel;
 
 pub struct AuthType {
     pub access_token: String,
-    // TODO: Implement refresh token
-    // pub refresh_token: String,
+    pub refresh_token: String,
     pub address: String,
     pub account: Option<AccountModel>,
 }
@@ -16,6 +15,11 @@ impl AuthType {
         &self.access_token
     }
 
+    /// Exchange it with `refreshToken` once the access token expires, it can be used once
+    async fn refresh_token(&self) -> &str {
+        &self.refresh_token
+    }
+
     async fn address(&self) -> &str {
         &self.address
     }
@@ -1,5 +1,13 @@
 mod account;
+mod achievement;
 mod auth;
+mod referral;
+mod session;
+mod stats;
 
 pub use account::*;
+pub use achievement::*;
 pub use auth::*;
+pub use referral::*;
+pub use session::*;
+pub use stats::*;
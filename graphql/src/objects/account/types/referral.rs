@@ -0,0 +1,62 @@
+use async_graphql::{Context, Object};
+use entity::account::AccountModel;
+use service::account::store::AccountStore;
+use service::prelude::{ServiceProvider, StoreService};
+use service::referral::{store::ReferralStore, ReferralService};
+use tracing::warn;
+
+use super::{AccountType, AssetAmountType};
+
+/// Referral program of an account
+pub struct ReferralType(AccountModel);
+
+impl From<AccountModel> for ReferralType {
+    fn from(item: AccountModel) -> Self {
+        ReferralType(item)
+    }
+}
+
+#[Object]
+impl ReferralType {
+    /// Represent the code new accounts claim at login, until generated there is none
+    async fn code(&self) -> &Option<String> {
+        &self.0.referral_code
+    }
+
+    async fn link(&self, ctx: &Context<'_>) -> Option<String> {
+        let services = ctx.data_unchecked::<ServiceProvider>();
+        let referral_service = services.get_service_unchecked::<ReferralService>().await;
+
+        self.0.referral_code.as_deref().map(|code| referral_service.link(code))
+    }
+
+    /// Represent the accounts referred, the most recent first
+    async fn referred_accounts(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<AccountType>> {
+        let services = ctx.data_unchecked::<ServiceProvider>();
+        let store_service = services.get_service_unchecked::<StoreService>().await;
+
+        let accounts = AccountStore::find_all_by_referrer_id(store_service.read(), self.0.id)
+            .await
+            .map_err(|e| {
+                warn!("Failed to fetch referred accounts: {e:?}");
+                async_graphql::Error::from("Internal error")
+            })?;
+
+        Ok(accounts.into_iter().map(Into::into).collect())
+    }
+
+    /// Represent the rewards accrued on the purchases of referred accounts, per asset
+    async fn rewards(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<AssetAmountType>> {
+        let services = ctx.data_unchecked::<ServiceProvider>();
+        let store_service = services.get_service_unchecked::<StoreService>().await;
+
+        let rewards = ReferralStore::find_totals_by_referrer_id(store_service.read(), self.0.id)
+            .await
+            .map_err(|e| {
+                warn!("Failed to fetch referral rewards: {e:?}");
+                async_graphql::Error::from("Internal error")
+            })?;
+
+        Ok(rewards.into_iter().map(Into::into).collect())
+    }
+}
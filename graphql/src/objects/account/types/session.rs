@@ -0,0 +1,33 @@
+use async_graphql::Object;
+use chrono::{DateTime, Utc};
+use entity::session::SessionModel;
+use uuid::Uuid;
+
+pub struct SessionType {
+    pub session: SessionModel,
+    /// Whether the request was authorized by this session
+    pub current: bool,
+}
+
+#[Object]
+impl SessionType {
+    async fn id(&self) -> Uuid {
+        self.session.id
+    }
+
+    async fn current(&self) -> bool {
+        self.current
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.session.created_at
+    }
+
+    async fn refreshed_at(&self) -> DateTime<Utc> {
+        self.session.refreshed_at
+    }
+
+    async fn expires_at(&self) -> DateTime<Utc> {
+        self.session.expires_at
+    }
+}
@@ -0,0 +1,109 @@
+use async_graphql::dataloader::DataLoader;
+use async_graphql::{Context, Object};
+use service::account::types::{AccountStats, AssetAmount, AssetDraw};
+use sqlx::types::Decimal;
+
+use crate::loaders::AssetLoader;
+use crate::objects::asset::types::AssetType;
+use crate::objects::lottery::types::DrawType;
+
+pub struct AccountStatsType(AccountStats);
+
+impl From<AccountStats> for AccountStatsType {
+    fn from(item: AccountStats) -> Self {
+        AccountStatsType(item)
+    }
+}
+
+#[Object]
+impl AccountStatsType {
+    /// Represent the number of distinct lotteries the account bought tickets for
+    async fn lotteries_entered(&self) -> i64 {
+        self.0.lotteries_entered
+    }
+
+    async fn total_tickets(&self) -> i64 {
+        self.0.total_tickets
+    }
+
+    /// Represent the amount spent on tickets, per asset
+    async fn total_spent(&self) -> Vec<AssetAmountType> {
+        self.0.spent.iter().cloned().map(Into::into).collect()
+    }
+
+    /// Represent the number of completed draws won
+    async fn wins(&self) -> i64 {
+        self.0.wins
+    }
+
+    /// Represent the amount won, per asset
+    async fn total_winnings(&self) -> Vec<AssetAmountType> {
+        self.0.winnings.iter().cloned().map(Into::into).collect()
+    }
+
+    /// Represent the winnings minus the amount spent, per asset
+    async fn net_profit(&self) -> Vec<AssetAmountType> {
+        self.0.net_profit().into_iter().map(Into::into).collect()
+    }
+
+    /// Represent the largest completed draw won, per asset
+    async fn biggest_wins(&self) -> Vec<AssetDrawType> {
+        self.0.biggest_wins.iter().cloned().map(Into::into).collect()
+    }
+
+    async fn first_activity(&self) -> Option<String> {
+        self.0.first_activity.map(|date| date.to_rfc3339())
+    }
+
+    async fn last_activity(&self) -> Option<String> {
+        self.0.last_activity.map(|date| date.to_rfc3339())
+    }
+}
+
+pub struct AssetAmountType(AssetAmount);
+
+impl From<AssetAmount> for AssetAmountType {
+    fn from(item: AssetAmount) -> Self {
+        AssetAmountType(item)
+    }
+}
+
+#[Object]
+impl AssetAmountType {
+    async fn asset(&self, ctx: &Context<'_>) -> async_graphql::Result<AssetType> {
+        let loader = ctx.data_unchecked::<DataLoader<AssetLoader>>();
+
+        match loader.load_one(self.0.asset_id).await {
+            Ok(Some(asset)) => Ok(asset.into()),
+            _ => Err(async_graphql::Error::new("Unable to find asset")),
+        }
+    }
+
+    async fn amount(&self) -> Decimal {
+        self.0.amount
+    }
+}
+
+pub struct AssetDrawType(AssetDraw);
+
+impl From<AssetDraw> for AssetDrawType {
+    fn from(item: AssetDraw) -> Self {
+        AssetDrawType(item)
+    }
+}
+
+#[Object]
+impl AssetDrawType {
+    async fn asset(&self, ctx: &Context<'_>) -> async_graphql::Result<AssetType> {
+        let loader = ctx.data_unchecked::<DataLoader<AssetLoader>>();
+
+        match loader.load_one(self.0.asset_id).await {
+            Ok(Some(asset)) => Ok(asset.into()),
+            _ => Err(async_graphql::Error::new("Unable to find asset")),
+        }
+    }
+
+    async fn draw(&self) -> DrawType {
+        self.0.draw.clone().into()
+    }
+}
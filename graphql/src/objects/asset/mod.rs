@@ -1,31 +1,68 @@
 pub mod types;
 
 use self::types::{AssetType};
+use async_graphql::connection::{Connection, EmptyFields};
 use async_graphql::{Context, Object, Subscription};
 use futures::{Stream, StreamExt};
+use entity::asset::AssetModel;
 use service::asset::store::AssetStore;
+use service::cache::{service::{CacheKey, CacheService}, tags};
+use service::common::types::KeysetCursor;
 use service::services::ServiceProvider;
 use service::{prelude::StoreService};
+use std::time::Duration;
+use lib::error::Error;
 use tracing::warn;
 
+use crate::objects::common::connection::{build_connection, resolve_connection_args, Cursor};
+
+
+/// Assets are seldom added, no event announces them
+const ASSETS_CACHE_TTL: Duration = Duration::from_secs(60);
 
 #[derive(Default)]
 pub struct AssetQuery;
 
 #[Object]
 impl AssetQuery {
-    /// Get list of all supported assets
-    async fn assets(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<AssetType>> {
+    /// Get supported assets, in the order they were added
+    async fn assets(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> async_graphql::Result<Connection<Cursor, AssetType>> {
         let services = ctx.data_unchecked::<ServiceProvider>();
         let store_service = services.get_service_unchecked::<StoreService>().await;
 
-        let pool = store_service.read();
-        let assets = AssetStore::find_all(pool).await.map_err(|e| {
-            warn!("Failed to assets: {e:?}");
-            async_graphql::Error::from("Internal error")
-        })?;
-
-        Ok(assets.into_iter().map(Into::into).collect())
+        let cache = services.get_service_unchecked::<CacheService>().await;
+
+        // Cache misses load from the primary, a replica may lag behind the invalidating event
+        let pool = store_service.write();
+        let (after, first) = resolve_connection_args(after, first)?;
+        let has_previous_page = after.is_some();
+
+        let page = match &after {
+            Some(cursor) => format!("{}|{}|{}:{first}", cursor.sort, cursor.key, cursor.id),
+            None => format!("start:{first}"),
+        };
+        let key = CacheKey::new("assets", page);
+        let assets = cache
+            .get_or_load(&key, ASSETS_CACHE_TTL, |_: &Vec<AssetModel>| vec![tags::ASSETS.to_string()], || {
+                AssetStore::find_page(pool, after, first as i64 + 1)
+            })
+            .await
+            .map_err(|e| match e.current_context() {
+                Error::InvalidCursor => async_graphql::Error::new(Error::InvalidCursor.to_string()),
+                _ => {
+                    warn!("Failed to assets: {e:?}");
+                    async_graphql::Error::from("Internal error")
+                }
+            })?;
+
+        Ok(build_connection(assets, first, has_previous_page, EmptyFields, |asset| {
+            (KeysetCursor::from_timestamp(AssetStore::PAGE_SORT, asset.created_at, asset.id), asset.into())
+        }))
     }
 }
 
@@ -0,0 +1,121 @@
+use async_graphql::connection::{Connection, CursorType, Edge, EmptyFields};
+use async_graphql::{ObjectType, OutputType};
+use lib::error::Error;
+use service::common::types::KeysetCursor;
+use uuid::Uuid;
+
+use super::inputs::PageInput;
+
+/// Opaque cursor of a keyset paginated connection
+pub struct Cursor(pub KeysetCursor);
+
+impl CursorType for Cursor {
+    type Error = Error;
+
+    fn decode_cursor(s: &str) -> Result<Self, Self::Error> {
+        let bytes = hex::decode(s).map_err(|_| Error::InvalidCursor)?;
+        let value = String::from_utf8(bytes).map_err(|_| Error::InvalidCursor)?;
+
+        let Some((sort, position)) = value.split_once('|') else {
+            return Err(Error::InvalidCursor);
+        };
+        let Some((key, id)) = position.rsplit_once('|') else {
+            return Err(Error::InvalidCursor);
+        };
+        let id = Uuid::parse_str(id).map_err(|_| Error::InvalidCursor)?;
+
+        Ok(Cursor(KeysetCursor {
+            sort: sort.to_string(),
+            key: key.to_string(),
+            id,
+        }))
+    }
+
+    fn encode_cursor(&self) -> String {
+        hex::encode(format!("{}|{}|{}", self.0.sort, self.0.key, self.0.id))
+    }
+}
+
+/// Decode the `after` argument, rejecting cursors which were not issued by the server
+pub fn decode_after(after: Option<String>) -> async_graphql::Result<Option<KeysetCursor>> {
+    after
+        .map(|after| Cursor::decode_cursor(&after).map(|cursor| cursor.0))
+        .transpose()
+        .map_err(|e| async_graphql::Error::new(e.to_string()))
+}
+
+/// Resolve forward pagination arguments into the keyset position and the page size
+pub fn resolve_connection_args(
+    after: Option<String>,
+    first: Option<i32>,
+) -> async_graphql::Result<(Option<KeysetCursor>, usize)> {
+    let after = decode_after(after)?;
+
+    let page_size = match first {
+        Some(first) if first < 0 => return Err("`first` must be positive".into()),
+        Some(first) => Some(first as u64),
+        None => None,
+    };
+    let (_, first) = PageInput { page_size, ..Default::default() }.resolve();
+
+    Ok((after, first as usize))
+}
+
+/// Build a connection from a page fetched with one extra item, which tells whether a next page exists
+pub fn build_connection<Item, Node, Fields>(
+    mut items: Vec<Item>,
+    first: usize,
+    has_previous_page: bool,
+    additional_fields: Fields,
+    into_edge: impl Fn(Item) -> (KeysetCursor, Node),
+) -> Connection<Cursor, Node, Fields, EmptyFields>
+where
+    Node: OutputType,
+    Fields: ObjectType,
+{
+    let has_next_page = items.len() > first;
+    items.truncate(first);
+
+    let mut connection = Connection::with_additional_fields(has_previous_page, has_next_page, additional_fields);
+    connection.edges.extend(items.into_iter().map(|item| {
+        let (cursor, node) = into_edge(item);
+        Edge::new(Cursor(cursor), node)
+    }));
+
+    connection
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = KeysetCursor {
+            sort: "newest".to_string(),
+            key: "2025-03-01T10:00:00.000000Z".to_string(),
+            id: Uuid::new_v4(),
+        };
+
+        let decoded = Cursor::decode_cursor(&Cursor(cursor.clone()).encode_cursor()).unwrap();
+        assert_eq!(decoded.0, cursor);
+    }
+
+    #[test]
+    fn test_cursor_rejects_garbage() {
+        assert!(Cursor::decode_cursor("not hex").is_err());
+        assert!(Cursor::decode_cursor(&hex::encode("newest|no id")).is_err());
+        assert!(decode_after(Some(hex::encode(format!("{}", Uuid::nil())))).is_err());
+    }
+
+    #[test]
+    fn test_resolve_connection_args_caps_page_size() {
+        let (_, first) = resolve_connection_args(None, Some(10_000)).unwrap();
+        assert_eq!(first as u64, PageInput::MAX_PAGE_SIZE);
+
+        let (_, first) = resolve_connection_args(None, None).unwrap();
+        assert_eq!(first as u64, PageInput::DEFAULT_PAGE_SIZE);
+
+        assert!(resolve_connection_args(None, Some(-1)).is_err());
+    }
+}
@@ -5,4 +5,19 @@ use serde::Serialize;
 pub struct PageInput {
     pub page: Option<u64>,
     pub page_size: Option<u64>,
-}
\ No newline at end of file
+}
+
+impl PageInput {
+    pub const DEFAULT_PAGE_SIZE: u64 = 20;
+    pub const MAX_PAGE_SIZE: u64 = service::common::types::MAX_PAGE_SIZE;
+
+    /// Requested page and page size, the page size is capped to keep queries bounded
+    pub fn resolve(&self) -> (u64, u64) {
+        let page_size = self
+            .page_size
+            .unwrap_or(Self::DEFAULT_PAGE_SIZE)
+            .clamp(1, Self::MAX_PAGE_SIZE);
+
+        (self.page.unwrap_or(0), page_size)
+    }
+}
@@ -1,2 +1,4 @@
+pub mod connection;
+pub mod subscription;
 pub mod inputs;
 pub mod types;
@@ -0,0 +1,68 @@
+use async_graphql::{Context, ErrorExtensions, OutputType, SimpleObject};
+use futures::{Stream, StreamExt};
+use service::{message_broker::{Channel, Delivery, Event, MessageBrokerService}, prelude::ServiceProvider};
+use tracing::warn;
+
+use crate::objects::account::types::AchievementType;
+use crate::objects::lottery::types::{DrawType, LotteryType, PrizeType, TicketType};
+
+/// Event delivered by a subscription, along with its id
+#[derive(SimpleObject)]
+#[graphql(concrete(name = "TicketEvent", params(TicketType)))]
+#[graphql(concrete(name = "LotteryEvent", params(LotteryType)))]
+#[graphql(concrete(name = "DrawEvent", params(DrawType)))]
+#[graphql(concrete(name = "PrizeEvent", params(PrizeType)))]
+#[graphql(concrete(name = "AchievementEvent", params(AchievementType)))]
+pub struct SubscriptionEvent<T: OutputType> {
+    /// Pass it as `afterEventId` when subscribing again to receive the events missed since
+    pub event_id: i64,
+    pub data: T,
+}
+
+/// Subscribe to the broker channel, mapping its events with `map`.
+///
+/// When the subscriber lagged behind, a `RESYNC_REQUIRED` error is emitted and the stream ends,
+/// the client must subscribe again with the last `eventId` it received.
+pub async fn subscribe<P, T, F>(
+    ctx: &Context<'_>,
+    channel: Channel<P>,
+    after_event_id: Option<i64>,
+    mut map: F,
+) -> async_graphql::Result<impl Stream<Item = async_graphql::Result<SubscriptionEvent<T>>>>
+where
+    P: 'static,
+    T: OutputType,
+    F: FnMut(Event) -> Option<T> + Send + 'static,
+{
+    let services = ctx.data_unchecked::<ServiceProvider>();
+    let broker = services.get_service_unchecked::<MessageBrokerService>().await;
+
+    let stream = broker.subscribe(channel, after_event_id).await.map_err(|e| {
+        warn!("Failed to subscribe to {}: {e:?}", channel.name());
+        async_graphql::Error::from("Internal error")
+    })?;
+
+    Ok(stream
+        .scan(false, move |lagged, delivery| {
+            if *lagged {
+                return futures::future::ready(None);
+            }
+
+            let item = match delivery {
+                Delivery::Event(event) => map(event.event).map(|data| {
+                    Ok(SubscriptionEvent {
+                        event_id: event.id,
+                        data,
+                    })
+                }),
+                Delivery::Lagged => {
+                    *lagged = true;
+                    Some(Err(async_graphql::Error::new("Subscription lagged behind, resync required")
+                        .extend_with(|_, e| e.set("code", "RESYNC_REQUIRED"))))
+                }
+            };
+
+            futures::future::ready(Some(item))
+        })
+        .filter_map(futures::future::ready))
+}
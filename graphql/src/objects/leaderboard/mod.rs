@@ -0,0 +1,46 @@
+use async_graphql::{Context, Object};
+use lib::error::Error;
+use service::leaderboard::{types::{LeaderboardKind, LeaderboardPeriod}, LeaderboardService};
+use service::prelude::ServiceProvider;
+use tracing::warn;
+use types::LeaderboardEntryType;
+use uuid::Uuid;
+
+pub mod types;
+
+/// Maximum number of accounts returned for a board
+const MAX_LEADERBOARD_SIZE: u32 = 100;
+
+#[derive(Default)]
+pub struct LeaderboardQuery;
+
+#[Object]
+impl LeaderboardQuery {
+    /// Get the top accounts of the current period.
+    ///
+    /// Amounts are ranked within the asset, which is only optional for the most tickets board.
+    async fn leaderboard(
+        &self,
+        ctx: &Context<'_>,
+        kind: LeaderboardKind,
+        period: LeaderboardPeriod,
+        asset: Option<Uuid>,
+        #[graphql(default = 10)] first: u32,
+    ) -> async_graphql::Result<Vec<LeaderboardEntryType>> {
+        let services = ctx.data_unchecked::<ServiceProvider>();
+        let leaderboard_service = services.get_service_unchecked::<LeaderboardService>().await;
+
+        let entries = leaderboard_service
+            .top(kind, period, asset, first.min(MAX_LEADERBOARD_SIZE))
+            .await
+            .map_err(|e| match e.current_context() {
+                Error::LeaderboardAssetRequired => async_graphql::Error::new(Error::LeaderboardAssetRequired.to_string()),
+                _ => {
+                    warn!("Failed to fetch leaderboard: {e:?}");
+                    async_graphql::Error::from("Internal error")
+                }
+            })?;
+
+        Ok(entries.into_iter().map(Into::into).collect())
+    }
+}
@@ -0,0 +1,37 @@
+use async_graphql::dataloader::DataLoader;
+use async_graphql::{Context, Object};
+use service::leaderboard::types::LeaderboardEntry;
+use sqlx::types::Decimal;
+
+use crate::loaders::AccountLoader;
+use crate::objects::account::types::AccountType;
+
+pub struct LeaderboardEntryType(LeaderboardEntry);
+
+impl From<LeaderboardEntry> for LeaderboardEntryType {
+    fn from(item: LeaderboardEntry) -> Self {
+        LeaderboardEntryType(item)
+    }
+}
+
+#[Object]
+impl LeaderboardEntryType {
+    /// Represent the position on the board, starting at 1
+    async fn rank(&self) -> u32 {
+        self.0.rank
+    }
+
+    async fn account(&self, ctx: &Context<'_>) -> async_graphql::Result<AccountType> {
+        let loader = ctx.data_unchecked::<DataLoader<AccountLoader>>();
+
+        match loader.load_one(self.0.account_id).await {
+            Ok(Some(account)) => Ok(account.into()),
+            _ => Err(async_graphql::Error::new("Unable to find account")),
+        }
+    }
+
+    /// Represent the amount in whole units of the asset, or number of tickets, the account is ranked by
+    async fn score(&self) -> Decimal {
+        self.0.score
+    }
+}
@@ -0,0 +1,357 @@
+use std::str::FromStr;
+
+use async_graphql::{Context, InputObject, MaybeUndefined, Object};
+use chrono::Utc;
+use entity::{account::AccountRole, lottery::LotteryCategory};
+use ethers::types::Address;
+use lib::error::Error;
+use rust_decimal::Decimal;
+use service::{
+    account::{types::CreateAccount, AccountService},
+    audit::{store::AuditLogStore, types::{AuditAction, CreateAuditLog}},
+    cache::{service::CacheService, tags},
+    lottery::{store::LotteryStore, types::UpdateLottery, LotteryService},
+    prelude::{ServiceProvider, StoreService},
+    prize::PrizeService,
+};
+use tracing::warn;
+use uuid::Uuid;
+
+use super::types::{AuditLogType, LotteryType, PrizeType};
+use crate::guards::role::RoleGuard;
+use crate::objects::account::current_account;
+
+/// Content shown on the lottery page, `null` clears a field and an omitted field is left as is
+#[derive(InputObject)]
+pub struct LotteryContentInput {
+    #[graphql(validator(max_length = 2000))]
+    pub description: MaybeUndefined<String>,
+    #[graphql(validator(url, max_length = 500))]
+    pub cover_image_url: MaybeUndefined<String>,
+    pub category: MaybeUndefined<LotteryCategory>,
+    /// Replace the tags, they are stored lowercase
+    pub tags: Option<Vec<String>>,
+}
+
+/// Contribution of a sponsor to the jackpot of a lottery
+#[derive(InputObject)]
+pub struct SponsorLotteryInput {
+    /// Address of the sponsor, its account is created if needed
+    pub sponsor: String,
+    /// Asset of the contribution, it may differ from the ticket asset
+    pub asset: Uuid,
+    pub value: Decimal,
+}
+
+fn maybe_undefined<T>(value: MaybeUndefined<T>) -> Option<Option<T>> {
+    match value {
+        MaybeUndefined::Undefined => None,
+        MaybeUndefined::Null => Some(None),
+        MaybeUndefined::Value(value) => Some(Some(value)),
+    }
+}
+
+/// Apply the change to the lottery with provided uid on behalf of the current account
+async fn curate(
+    ctx: &Context<'_>,
+    uid: String,
+    action: AuditAction,
+    dto: UpdateLottery,
+) -> async_graphql::Result<LotteryType> {
+    let services = ctx.data_unchecked::<ServiceProvider>();
+    let store_service = services.get_service_unchecked::<StoreService>().await;
+    let lottery_service = services.get_service_unchecked::<LotteryService>().await;
+
+    let (account, _) = current_account(ctx).await?;
+    let lottery = LotteryStore::try_find_by_uid(store_service.read(), uid)
+        .await
+        .map_err(|e| {
+            warn!("Failed to get lottery: {e:?}");
+            async_graphql::Error::from("Internal error")
+        })?
+        .ok_or(async_graphql::Error::from("Lottery not found"))?;
+
+    let mut db_tx = store_service.begin_transaction().await.map_err(|e| {
+        warn!("Failed to start transaction: {e:?}");
+        async_graphql::Error::new("Internal error")
+    })?;
+
+    let lottery = lottery_service
+        .curate(lottery.id, action, dto, account.id, &mut db_tx)
+        .await
+        .map_err(|e| match e.current_context() {
+            Error::LotteryInvalidTag => async_graphql::Error::new(Error::LotteryInvalidTag.to_string()),
+            _ => {
+                warn!("Failed to update lottery: {e:?}");
+                async_graphql::Error::new("Failed to update lottery")
+            }
+        })?;
+
+    store_service.commit_transaction(db_tx).await.map_err(|e| {
+        warn!("Failed to commit transaction: {e:?}");
+        async_graphql::Error::new("Internal error")
+    })?;
+
+    invalidate_lottery_cache(ctx, [lottery.id]).await;
+
+    Ok(lottery.into())
+}
+
+/// Drop the cached lotteries, curation changes are not announced by broker events
+async fn invalidate_lottery_cache(ctx: &Context<'_>, lottery_ids: impl IntoIterator<Item = Uuid>) {
+    let services = ctx.data_unchecked::<ServiceProvider>();
+    let cache = services.get_service_unchecked::<CacheService>().await;
+
+    for lottery_id in lottery_ids {
+        if let Err(e) = cache.invalidate_tag(&tags::lottery(lottery_id)).await {
+            warn!("Failed to invalidate lottery cache: {e:?}");
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct LotteryAdminQuery;
+
+#[Object]
+impl LotteryAdminQuery {
+    /// Changes made to the lottery through the admin API, last first
+    #[graphql(guard = "RoleGuard::new(AccountRole::Admin)")]
+    async fn lottery_audit_log(
+        &self,
+        ctx: &Context<'_>,
+        uid: String,
+    ) -> async_graphql::Result<Vec<AuditLogType>> {
+        let services = ctx.data_unchecked::<ServiceProvider>();
+        let store_service = services.get_service_unchecked::<StoreService>().await;
+
+        let pool = store_service.read();
+        let lottery = LotteryStore::try_find_by_uid(pool, uid)
+            .await
+            .map_err(|e| {
+                warn!("Failed to get lottery: {e:?}");
+                async_graphql::Error::from("Internal error")
+            })?
+            .ok_or(async_graphql::Error::from("Lottery not found"))?;
+
+        let logs = AuditLogStore::find_by_entity_id(pool, lottery.id).await.map_err(|e| {
+            warn!("Failed to get audit log: {e:?}");
+            async_graphql::Error::from("Internal error")
+        })?;
+
+        Ok(logs.into_iter().map(Into::into).collect())
+    }
+}
+
+#[derive(Default)]
+pub struct LotteryAdminMutation;
+
+#[Object]
+impl LotteryAdminMutation {
+    /// Feature the lottery, optionally in the provided slot
+    #[graphql(guard = "RoleGuard::new(AccountRole::Admin)")]
+    async fn feature_lottery(
+        &self,
+        ctx: &Context<'_>,
+        uid: String,
+        #[graphql(validator(minimum = 1))] position: Option<i32>,
+    ) -> async_graphql::Result<LotteryType> {
+        let dto = UpdateLottery {
+            featured: Some(true),
+            featured_position: position.map(Some),
+            ..Default::default()
+        };
+
+        curate(ctx, uid, AuditAction::LotteryFeatured, dto).await
+    }
+
+    /// Stop featuring the lottery, freeing its slot
+    #[graphql(guard = "RoleGuard::new(AccountRole::Admin)")]
+    async fn unfeature_lottery(&self, ctx: &Context<'_>, uid: String) -> async_graphql::Result<LotteryType> {
+        let dto = UpdateLottery {
+            featured: Some(false),
+            featured_position: Some(None),
+            ..Default::default()
+        };
+
+        curate(ctx, uid, AuditAction::LotteryUnfeatured, dto).await
+    }
+
+    #[graphql(guard = "RoleGuard::new(AccountRole::Admin)")]
+    async fn rename_lottery(
+        &self,
+        ctx: &Context<'_>,
+        uid: String,
+        #[graphql(validator(min_length = 1, max_length = 100))] name: String,
+    ) -> async_graphql::Result<LotteryType> {
+        let dto = UpdateLottery {
+            name: Some(name),
+            ..Default::default()
+        };
+
+        curate(ctx, uid, AuditAction::LotteryRenamed, dto).await
+    }
+
+    /// Give the featured lotteries with provided uids their slots, in the provided order
+    #[graphql(guard = "RoleGuard::new(AccountRole::Admin)")]
+    async fn reorder_featured_lotteries(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(validator(min_items = 1, max_items = 50))] uids: Vec<String>,
+    ) -> async_graphql::Result<Vec<LotteryType>> {
+        let services = ctx.data_unchecked::<ServiceProvider>();
+        let store_service = services.get_service_unchecked::<StoreService>().await;
+        let lottery_service = services.get_service_unchecked::<LotteryService>().await;
+
+        let (account, _) = current_account(ctx).await?;
+
+        let mut lottery_ids = Vec::with_capacity(uids.len());
+        for uid in uids {
+            let lottery = LotteryStore::try_find_by_uid(store_service.read(), uid.clone())
+                .await
+                .map_err(|e| {
+                    warn!("Failed to get lottery: {e:?}");
+                    async_graphql::Error::from("Internal error")
+                })?
+                .ok_or(async_graphql::Error::new(format!("Lottery {uid} not found")))?;
+            lottery_ids.push(lottery.id);
+        }
+
+        let mut db_tx = store_service.begin_transaction().await.map_err(|e| {
+            warn!("Failed to start transaction: {e:?}");
+            async_graphql::Error::new("Internal error")
+        })?;
+
+        let lotteries = lottery_service
+            .reorder_featured(lottery_ids, account.id, &mut db_tx)
+            .await
+            .map_err(|e| {
+                warn!("Failed to reorder featured lotteries: {e:?}");
+                async_graphql::Error::new(e.current_context().to_string())
+            })?;
+
+        store_service.commit_transaction(db_tx).await.map_err(|e| {
+            warn!("Failed to commit transaction: {e:?}");
+            async_graphql::Error::new("Internal error")
+        })?;
+
+        let lottery_ids = lotteries.iter().map(|lottery| lottery.id).collect::<Vec<_>>();
+        invalidate_lottery_cache(ctx, lottery_ids).await;
+
+        Ok(lotteries.into_iter().map(Into::into).collect())
+    }
+
+    /// Leave the lottery out of the public listings, it stays reachable by uid
+    #[graphql(guard = "RoleGuard::new(AccountRole::Admin)")]
+    async fn hide_lottery(&self, ctx: &Context<'_>, uid: String) -> async_graphql::Result<LotteryType> {
+        let dto = UpdateLottery {
+            hidden: Some(true),
+            ..Default::default()
+        };
+
+        curate(ctx, uid, AuditAction::LotteryHidden, dto).await
+    }
+
+    #[graphql(guard = "RoleGuard::new(AccountRole::Admin)")]
+    async fn unhide_lottery(&self, ctx: &Context<'_>, uid: String) -> async_graphql::Result<LotteryType> {
+        let dto = UpdateLottery {
+            hidden: Some(false),
+            ..Default::default()
+        };
+
+        curate(ctx, uid, AuditAction::LotteryUnhidden, dto).await
+    }
+
+    /// Set the description, cover image, category and tags of the lottery
+    #[graphql(guard = "RoleGuard::new(AccountRole::Admin)")]
+    async fn update_lottery_content(
+        &self,
+        ctx: &Context<'_>,
+        uid: String,
+        input: LotteryContentInput,
+    ) -> async_graphql::Result<LotteryType> {
+        let dto = UpdateLottery {
+            description: maybe_undefined(input.description),
+            cover_image_url: maybe_undefined(input.cover_image_url),
+            category: maybe_undefined(input.category),
+            tags: input.tags,
+            ..Default::default()
+        };
+
+        curate(ctx, uid, AuditAction::LotteryContentUpdated, dto).await
+    }
+
+    /// Add a sponsor contribution to the jackpot of the lottery, in any known asset
+    #[graphql(guard = "RoleGuard::new(AccountRole::Admin)")]
+    async fn sponsor_lottery(
+        &self,
+        ctx: &Context<'_>,
+        uid: String,
+        input: SponsorLotteryInput,
+    ) -> async_graphql::Result<PrizeType> {
+        let services = ctx.data_unchecked::<ServiceProvider>();
+        let store_service = services.get_service_unchecked::<StoreService>().await;
+        let account_service = services.get_service_unchecked::<AccountService>().await;
+        let prize_service = services.get_service_unchecked::<PrizeService>().await;
+
+        let sponsor = Address::from_str(&input.sponsor)
+            .map_err(|_| async_graphql::Error::new(Error::InvalidAddress.to_string()))?;
+
+        let (account, _) = current_account(ctx).await?;
+        let lottery = LotteryStore::try_find_by_uid(store_service.read(), uid)
+            .await
+            .map_err(|e| {
+                warn!("Failed to get lottery: {e:?}");
+                async_graphql::Error::from("Internal error")
+            })?
+            .ok_or(async_graphql::Error::from("Lottery not found"))?;
+
+        let mut db_tx = store_service.begin_transaction().await.map_err(|e| {
+            warn!("Failed to start transaction: {e:?}");
+            async_graphql::Error::new("Internal error")
+        })?;
+
+        let dto = CreateAccount {
+            address: format!("{sponsor:?}"),
+            created_at: Utc::now(),
+            referral_code: None,
+        };
+        let sponsor = account_service.create_if_no_exists(dto, &mut db_tx).await.map_err(|e| {
+            warn!("Failed to create sponsor account: {e:?}");
+            async_graphql::Error::new("Internal error")
+        })?;
+
+        let prize = prize_service
+            .sponsor(lottery.id, sponsor.id, input.asset, input.value, None, &mut db_tx)
+            .await
+            .map_err(|e| match e.current_context() {
+                Error::PrizeServiceInvalidValue | Error::PrizeServiceUnknownAsset => {
+                    async_graphql::Error::new(e.current_context().to_string())
+                }
+                _ => {
+                    warn!("Failed to sponsor lottery: {e:?}");
+                    async_graphql::Error::new("Failed to sponsor lottery")
+                }
+            })?;
+
+        let audit_dto = CreateAuditLog {
+            account_id: account.id,
+            action: AuditAction::LotterySponsored,
+            entity_type: "lottery".to_string(),
+            entity_id: lottery.id,
+            changes: serde_json::json!({ "after": prize }),
+        };
+        AuditLogStore::create(db_tx.as_mut(), audit_dto).await.map_err(|e| {
+            warn!("Failed to write audit log: {e:?}");
+            async_graphql::Error::new("Internal error")
+        })?;
+
+        store_service.commit_transaction(db_tx).await.map_err(|e| {
+            warn!("Failed to commit transaction: {e:?}");
+            async_graphql::Error::new("Internal error")
+        })?;
+
+        invalidate_lottery_cache(ctx, [lottery.id]).await;
+
+        Ok(prize.into())
+    }
+}
@@ -1,20 +1,86 @@
-use async_graphql::InputObject;
-use entity::prelude::LotteryStatus;
-use service::lottery::store::LotteryFilter;
+use async_graphql::{Enum, InputObject};
+use chrono::{DateTime, Utc};
+use entity::prelude::{LotteryCategory, LotteryStatus};
+use service::lottery::store::{LotteryFilter, LotterySort};
+use sqlx::types::Decimal;
+use uuid::Uuid;
 
-#[derive(InputObject)]
+#[derive(InputObject, Default)]
 pub struct LotteryFilterInput {
     pub uid: Option<String>,
     pub featured: Option<bool>,
-    pub status: Option<LotteryStatus>,
+    /// Lotteries in any of the provided statuses
+    pub statuses: Option<Vec<LotteryStatus>>,
+    pub ticket_asset: Option<Uuid>,
+    pub start_date_from: Option<DateTime<Utc>>,
+    pub start_date_to: Option<DateTime<Utc>>,
+    pub end_date_from: Option<DateTime<Utc>>,
+    pub end_date_to: Option<DateTime<Utc>>,
+    /// Asset of the prizes, prize values are compared within this asset when provided
+    pub prize_asset: Option<Uuid>,
+    /// Prize value, compared against the largest pool of a single asset when `prizeAsset` is not provided
+    pub min_prize_value: Option<Decimal>,
+    pub max_prize_value: Option<Decimal>,
+    /// Address of an account holding tickets of the lottery
+    #[graphql(validator(min_length = 42, max_length = 42))]
+    pub participant: Option<String>,
+    /// Case insensitive search on the lottery name
+    #[graphql(validator(min_length = 1, max_length = 50))]
+    pub search: Option<String>,
+    /// Also list hidden lotteries, admins only
+    pub include_hidden: Option<bool>,
+    pub category: Option<LotteryCategory>,
+    /// Lotteries having any of the provided tags
+    pub tags: Option<Vec<String>>,
 }
 
 impl From<LotteryFilterInput> for LotteryFilter {
     fn from(value: LotteryFilterInput) -> Self {
         Self {
             featured: value.featured,
-            status: value.status,
             uid: value.uid,
+            statuses: value.statuses.unwrap_or_default(),
+            ticket_asset: value.ticket_asset,
+            start_date_from: value.start_date_from,
+            start_date_to: value.start_date_to,
+            end_date_from: value.end_date_from,
+            end_date_to: value.end_date_to,
+            prize_asset: value.prize_asset,
+            min_prize_value: value.min_prize_value,
+            max_prize_value: value.max_prize_value,
+            participant: value.participant,
+            search: value.search,
+            include_hidden: value.include_hidden.unwrap_or_default(),
+            category: value.category,
+            tags: value
+                .tags
+                .unwrap_or_default()
+                .into_iter()
+                .map(|tag| tag.trim().to_lowercase())
+                .collect(),
         }
     }
-}
\ No newline at end of file
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub enum LotterySortInput {
+    /// Earliest end date first, lotteries which already ended are only left out by the filter,
+    /// e.g. with `endDateFrom`
+    EndingSoon,
+    LargestPool,
+    #[default]
+    Newest,
+    /// Featured slot order, lotteries without slot last
+    FeaturedPosition,
+}
+
+impl From<LotterySortInput> for LotterySort {
+    fn from(value: LotterySortInput) -> Self {
+        match value {
+            LotterySortInput::EndingSoon => LotterySort::EndingSoon,
+            LotterySortInput::LargestPool => LotterySort::LargestPool,
+            LotterySortInput::Newest => LotterySort::Newest,
+            LotterySortInput::FeaturedPosition => LotterySort::FeaturedPosition,
+        }
+    }
+}
@@ -1,41 +1,98 @@
-use async_graphql::{Context, Object};
-use inputs::LotteryFilterInput;
-use service::{draw::store::DrawStore, lottery::store::LotteryStore, prelude::{ServiceProvider, StoreService}};
+use async_graphql::connection::Connection;
+use async_graphql::{Context, Guard, Object, SimpleObject};
+use inputs::{LotteryFilterInput, LotterySortInput};
+use entity::{account::AccountRole, lottery::LotteryModel};
+use service::{cache::{service::{CacheKey, CacheService}, tags}, common::types::KeysetCursor, draw::store::DrawStore, lottery::store::{LotteryStore, LotterySort}, prelude::{ServiceProvider, StoreService}};
+use std::time::Duration;
+use lib::error::Error;
 use tracing::warn;
 use types::{DrawType, LotteryType};
 
+use crate::guards::role::RoleGuard;
+use crate::objects::common::connection::{build_connection, resolve_connection_args, Cursor};
+
 pub mod types;
 pub mod inputs;
 pub mod tickets;
+pub mod subscriptions;
+pub mod admin;
+
+/// Lotteries only change on broker events, which invalidate them
+const LOTTERY_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// Fields of the lotteries connection besides its edges
+#[derive(SimpleObject)]
+pub struct LotteryConnectionFields {
+    /// Number of lotteries matching the filter, on every page
+    pub total_count: i64,
+}
 
 #[derive(Default)]
 pub struct LotteryQuery;
 
 #[Object]
 impl LotteryQuery {
-    /// Get list of all supported assets
-    async fn lotteries(&self, ctx: &Context<'_>, input: LotteryFilterInput) -> async_graphql::Result<Vec<LotteryType>> {
+    /// Get the lotteries matching the filter, in the requested order
+    async fn lotteries(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] input: LotteryFilterInput,
+        #[graphql(default)] sort: LotterySortInput,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> async_graphql::Result<Connection<Cursor, LotteryType, LotteryConnectionFields>> {
+        if input.include_hidden.unwrap_or_default() {
+            RoleGuard::new(AccountRole::Admin).check(ctx).await?;
+        }
+
         let services = ctx.data_unchecked::<ServiceProvider>();
         let store_service = services.get_service_unchecked::<StoreService>().await;
 
         let pool = store_service.read();
-        let lotteries = LotteryStore::find_by_filter(pool, input.into()).await.map_err(|e| {
-            warn!("Failed to lotteries: {e:?}");
-            async_graphql::Error::from("Internal error")
-        })?;
-        
-        Ok(lotteries.into_iter().map(Into::into).collect())
+        let sort = LotterySort::from(sort);
+        let (after, first) = resolve_connection_args(after, first)?;
+        let has_previous_page = after.is_some();
+
+        let (lotteries, total_count) =
+            LotteryStore::find_by_filter(pool, input.into(), sort, after, first as i64 + 1)
+                .await
+                .map_err(|e| match e.current_context() {
+                    Error::InvalidCursor => async_graphql::Error::new(Error::InvalidCursor.to_string()),
+                    _ => {
+                        warn!("Failed to lotteries: {e:?}");
+                        async_graphql::Error::from("Internal error")
+                    }
+                })?;
+
+        let fields = LotteryConnectionFields { total_count };
+        Ok(build_connection(lotteries, first, has_previous_page, fields, |item| {
+            let cursor = KeysetCursor {
+                sort: sort.name().to_string(),
+                key: item.cursor_key,
+                id: item.lottery.id,
+            };
+            (cursor, item.lottery.into())
+        }))
     }
     
     async fn lottery(&self, ctx: &Context<'_>, uid: String) -> async_graphql::Result<LotteryType> {
         let services = ctx.data_unchecked::<ServiceProvider>();
         let store_service = services.get_service_unchecked::<StoreService>().await;
 
-        let pool = store_service.read();
-        let lottery = LotteryStore::find_by_uid(pool, uid).await.map_err(|e| {
-            warn!("Failed to lottery: {e:?}");
-            async_graphql::Error::from("Internal error")
-        })?;
+        let cache = services.get_service_unchecked::<CacheService>().await;
+
+        // Cache misses load from the primary, a replica may lag behind the invalidating event
+        let pool = store_service.write();
+        let key = CacheKey::new("lottery", &uid);
+        let lottery = cache
+            .get_or_load(&key, LOTTERY_CACHE_TTL, |lottery: &LotteryModel| vec![tags::lottery(lottery.id)], || {
+                LotteryStore::find_by_uid(pool, uid)
+            })
+            .await
+            .map_err(|e| {
+                warn!("Failed to lottery: {e:?}");
+                async_graphql::Error::from("Internal error")
+            })?;
         
         Ok(lottery.into())
     }
@@ -0,0 +1,170 @@
+use async_graphql::{Context, Subscription};
+use entity::lottery::LotteryModel;
+use ethers::types::H256;
+use futures::Stream;
+use service::{chain::traits::string::ToHexString, lottery::store::LotteryStore, message_broker::{channels, Event}, prelude::{ServiceProvider, StoreService}};
+use std::str::FromStr;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::types::{DrawType, LotteryType, PrizeType};
+use crate::objects::common::subscription::{subscribe, SubscriptionEvent};
+
+/// Lottery a subscription is narrowed to by its `lotteryUid` argument
+pub(crate) struct SubscribedLottery {
+    uid: String,
+    id: Option<Uuid>, // Lotteries which did not open yet are not stored
+}
+
+impl SubscribedLottery {
+    /// Whether the lottery of the event is the subscribed one
+    pub(crate) fn matches(&self, lottery: &LotteryModel) -> bool {
+        self.id.map_or(self.uid == lottery.uid, |id| id == lottery.id)
+    }
+
+    /// Whether the event of the lottery with `lottery_id` belongs to the subscribed lottery
+    pub(crate) fn matches_id(&self, lottery_id: Uuid) -> bool {
+        self.id == Some(lottery_id)
+    }
+}
+
+/// Parse an onchain lottery uid into the form lotteries are stored with
+pub(crate) fn parse_lottery_uid(uid: &str) -> Option<String> {
+    H256::from_str(uid).ok().map(|uid| uid.to_hex_string())
+}
+
+/// Resolve the optional `lotteryUid` argument of a subscription, looking the lottery up once
+pub(crate) async fn resolve_lottery(ctx: &Context<'_>, lottery_uid: Option<String>) -> async_graphql::Result<Option<SubscribedLottery>> {
+    let Some(uid) = lottery_uid else {
+        return Ok(None);
+    };
+    let uid = parse_lottery_uid(&uid).ok_or(async_graphql::Error::from("Invalid lottery uid"))?;
+
+    let services = ctx.data_unchecked::<ServiceProvider>();
+    let store_service = services.get_service_unchecked::<StoreService>().await;
+
+    let lottery = LotteryStore::try_find_by_uid(store_service.read(), uid.clone()).await.map_err(|e| {
+        warn!("Failed to get lottery: {e:?}");
+        async_graphql::Error::from("Internal error")
+    })?;
+
+    Ok(Some(SubscribedLottery {
+        uid,
+        id: lottery.map(|lottery| lottery.id),
+    }))
+}
+
+/// Resolve the optional `lotteryUid` argument of a subscription to events only stored lotteries emit
+pub(crate) async fn resolve_stored_lottery(ctx: &Context<'_>, lottery_uid: Option<String>) -> async_graphql::Result<Option<SubscribedLottery>> {
+    match resolve_lottery(ctx, lottery_uid).await? {
+        Some(SubscribedLottery { id: None, .. }) => Err(async_graphql::Error::from("Lottery not found")),
+        lottery => Ok(lottery),
+    }
+}
+
+#[derive(Default)]
+pub struct LotterySubscription;
+
+#[Subscription]
+impl LotterySubscription {
+    /// Emits lotteries as they open, optionally only the one with `lotteryUid`
+    async fn lottery_opened(
+        &self,
+        ctx: &Context<'_>,
+        lottery_uid: Option<String>,
+        after_event_id: Option<i64>,
+    ) -> async_graphql::Result<impl Stream<Item = async_graphql::Result<SubscriptionEvent<LotteryType>>>> {
+        let subscribed = resolve_lottery(ctx, lottery_uid).await?;
+
+        subscribe(ctx, channels::LOTTERY_OPENED, after_event_id, move |event| match event {
+            Event::LotteryOpened(lottery) if subscribed.as_ref().is_none_or(|subscribed| subscribed.matches(&lottery)) => Some(lottery.into()),
+            _ => None,
+        }).await
+    }
+
+    /// Emits lotteries as they close, optionally only the one with `lotteryUid`
+    async fn lottery_closed(
+        &self,
+        ctx: &Context<'_>,
+        lottery_uid: Option<String>,
+        after_event_id: Option<i64>,
+    ) -> async_graphql::Result<impl Stream<Item = async_graphql::Result<SubscriptionEvent<LotteryType>>>> {
+        let subscribed = resolve_lottery(ctx, lottery_uid).await?;
+
+        subscribe(ctx, channels::LOTTERY_CLOSED, after_event_id, move |event| match event {
+            Event::LotteryClosed(lottery) if subscribed.as_ref().is_none_or(|subscribed| subscribed.matches(&lottery)) => Some(lottery.into()),
+            _ => None,
+        }).await
+    }
+
+    /// Emits lotteries as they get canceled, optionally only the one with `lotteryUid`
+    async fn lottery_canceled(
+        &self,
+        ctx: &Context<'_>,
+        lottery_uid: Option<String>,
+        after_event_id: Option<i64>,
+    ) -> async_graphql::Result<impl Stream<Item = async_graphql::Result<SubscriptionEvent<LotteryType>>>> {
+        let subscribed = resolve_lottery(ctx, lottery_uid).await?;
+
+        subscribe(ctx, channels::LOTTERY_CANCELED, after_event_id, move |event| match event {
+            Event::LotteryCanceled(lottery) if subscribed.as_ref().is_none_or(|subscribed| subscribed.matches(&lottery)) => Some(lottery.into()),
+            _ => None,
+        }).await
+    }
+
+    /// Emits draws as their winner is drawn, optionally only for the lottery with `lotteryUid`
+    async fn winner_drawn(
+        &self,
+        ctx: &Context<'_>,
+        lottery_uid: Option<String>,
+        after_event_id: Option<i64>,
+    ) -> async_graphql::Result<impl Stream<Item = async_graphql::Result<SubscriptionEvent<DrawType>>>> {
+        let subscribed = resolve_stored_lottery(ctx, lottery_uid).await?;
+
+        subscribe(ctx, channels::WINNER_DRAWN, after_event_id, move |event| match event {
+            Event::WinnerDrawn(draw) if subscribed.as_ref().is_none_or(|subscribed| subscribed.matches_id(draw.lottery_id)) => Some(draw.into()),
+            _ => None,
+        }).await
+    }
+
+    /// Emits prize pools as their value changes, optionally only for the lottery with `lotteryUid`
+    async fn prize_pool_updated(
+        &self,
+        ctx: &Context<'_>,
+        lottery_uid: Option<String>,
+        after_event_id: Option<i64>,
+    ) -> async_graphql::Result<impl Stream<Item = async_graphql::Result<SubscriptionEvent<PrizeType>>>> {
+        let subscribed = resolve_stored_lottery(ctx, lottery_uid).await?;
+
+        subscribe(ctx, channels::PRIZE_POOL_UPDATED, after_event_id, move |event| match event {
+            Event::PrizePoolUpdated(prize) if subscribed.as_ref().is_none_or(|subscribed| subscribed.matches_id(prize.lottery_id)) => Some(prize.into()),
+            _ => None,
+        }).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UID: &str = "0x00000000000000000000000000000000000000000000000000000000000000ab";
+
+    #[test]
+    fn test_parse_lottery_uid() {
+        assert_eq!(parse_lottery_uid(UID).as_deref(), Some(UID));
+        assert_eq!(parse_lottery_uid(&UID.to_uppercase().replace("0X", "0x")).as_deref(), Some(UID));
+        assert_eq!(parse_lottery_uid("0xab"), None);
+        assert_eq!(parse_lottery_uid("lottery"), None);
+    }
+
+    #[test]
+    fn test_subscribed_lottery_matches_by_id_once_stored() {
+        let id = Uuid::new_v4();
+        let stored = SubscribedLottery { uid: UID.to_string(), id: Some(id) };
+        let pending = SubscribedLottery { uid: UID.to_string(), id: None };
+
+        assert!(stored.matches_id(id));
+        assert!(!stored.matches_id(Uuid::new_v4()));
+        assert!(!pending.matches_id(id));
+    }
+}
@@ -1,9 +1,12 @@
+use async_graphql::connection::{Connection, EmptyFields};
 use async_graphql::{Context, Object, Subscription};
-use futures::{Stream, StreamExt};
-use service::{message_broker::{Event, MessageBrokerService}, prelude::{ServiceProvider, StoreService}, ticket::store::TicketStore};
+use futures::Stream;
+use service::{common::types::KeysetCursor, message_broker::{channels, Event}, prelude::{ServiceProvider, StoreService}, ticket::store::TicketStore};
+use lib::error::Error;
 use tracing::{info, warn};
 
-use super::types::TicketType;
+use super::{subscriptions::resolve_stored_lottery, types::TicketType};
+use crate::objects::common::{connection::{build_connection, resolve_connection_args, Cursor}, subscription::{subscribe, SubscriptionEvent}};
 
 #[derive(Default)]
 pub struct TicketQuery;
@@ -24,17 +27,31 @@ impl TicketQuery {
         Ok(tickets.into_iter().map(Into::into).collect())
     }
     
-    async fn get_user_tickets(&self, ctx: &Context<'_>, address: String) -> async_graphql::Result<Vec<TicketType>> {
+    /// Fetches the tickets of the account, last bought first
+    async fn get_user_tickets(
+        &self,
+        ctx: &Context<'_>,
+        address: String,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> async_graphql::Result<Connection<Cursor, TicketType>> {
         let services = ctx.data_unchecked::<ServiceProvider>();
         let store_service = services.get_service_unchecked::<StoreService>().await;
 
         let pool = store_service.read();
-        let tickets = TicketStore::find_by_address(pool, address).await.map_err(|e| {
-            warn!("Failed to get user tickets: {e:?}");
-            async_graphql::Error::from("Internal error")
+        let (after, first) = resolve_connection_args(after, first)?;
+        let has_previous_page = after.is_some();
+        let tickets = TicketStore::find_by_address(pool, address, after, first as i64 + 1).await.map_err(|e| match e.current_context() {
+            Error::InvalidCursor => async_graphql::Error::new(Error::InvalidCursor.to_string()),
+            _ => {
+                warn!("Failed to get user tickets: {e:?}");
+                async_graphql::Error::from("Internal error")
+            }
         })?;
         
-        Ok(tickets.into_iter().map(Into::into).collect())
+        Ok(build_connection(tickets, first, has_previous_page, EmptyFields, |ticket| {
+            (KeysetCursor::from_timestamp(TicketStore::PAGE_SORT, ticket.purchased_at, ticket.id), ticket.into())
+        }))
     }
 }
 
@@ -43,18 +60,20 @@ pub struct TicketSubscription;
 
 #[Subscription]
 impl TicketSubscription {
-    async fn ticket_bought(&self, ctx: &Context<'_>) -> async_graphql::Result<impl Stream<Item = TicketType>> {
-        let services = ctx.data_unchecked::<ServiceProvider>();
-        let broker = services.get_service_unchecked::<MessageBrokerService>().await;
-
-        Ok(broker.subscribe().await.filter_map(|event| {
-            futures::future::ready(match event {
-                Event::TicketBought(ticket) => {
-                    info!("Ticket bought: {ticket:?}");
-                    Some(ticket.into())
-                },
-                _ => None,
-            })
-        }))
+    async fn ticket_bought(
+        &self,
+        ctx: &Context<'_>,
+        lottery_uid: Option<String>,
+        after_event_id: Option<i64>,
+    ) -> async_graphql::Result<impl Stream<Item = async_graphql::Result<SubscriptionEvent<TicketType>>>> {
+        let subscribed = resolve_stored_lottery(ctx, lottery_uid).await?;
+
+        subscribe(ctx, channels::TICKET_BOUGHT, after_event_id, move |event| match event {
+            Event::TicketBought(ticket) if subscribed.as_ref().is_none_or(|subscribed| subscribed.matches_id(ticket.lottery_id)) => {
+                info!("Ticket bought: {ticket:?}");
+                Some(ticket.into())
+            },
+            _ => None,
+        }).await
     }
 }
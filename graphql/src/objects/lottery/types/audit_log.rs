@@ -0,0 +1,32 @@
+use async_graphql::{Json, Object};
+use chrono::{DateTime, Utc};
+use entity::prelude::AuditLogModel;
+
+pub struct AuditLogType(pub AuditLogModel);
+
+impl From<AuditLogModel> for AuditLogType {
+    fn from(value: AuditLogModel) -> Self {
+        AuditLogType(value)
+    }
+}
+
+/// Represent a change made through the admin API
+#[Object]
+impl AuditLogType {
+    async fn id(&self) -> String {
+        format!("{:#x}", self.0.id)
+    }
+
+    async fn action(&self) -> &String {
+        &self.0.action
+    }
+
+    /// Changed fields, as `{"before": {..}, "after": {..}}`
+    async fn changes(&self) -> Json<&serde_json::Value> {
+        Json(&self.0.changes)
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+}
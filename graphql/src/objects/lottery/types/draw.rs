@@ -1,17 +1,17 @@
+use async_graphql::dataloader::DataLoader;
 use async_graphql::{Context, Enum, Object};
 use chrono::{Days, Utc};
 use entity::draw::{DrawModel, DrawStatus};
-use service::account::store::AccountStore;
 use sqlx::types::Decimal;
 
 use entity::prelude::{AssetModel, LotteryModel};
-use service::{
-    prelude::{ConfigService, StoreService},
-    services::ServiceProvider,
-};
+use service::prelude::ConfigService;
 
+use crate::loaders::{AccountLoader, TicketLoader};
 use crate::objects::account::types::AccountType;
 
+use super::TicketType;
+
 pub struct DrawType(pub DrawModel);
 
 impl From<DrawModel> for DrawType {
@@ -31,18 +31,40 @@ impl DrawType {
     }
 
     async fn winner(&self, ctx: &Context<'_>) -> Option<AccountType> {
-        if let Some(winner) = self.0.winner {
-            let services = ctx.data_unchecked::<ServiceProvider>();
-            let store_service = services.get_service_unchecked::<StoreService>().await;
-            let pool = store_service.read();
-            
-            match AccountStore::try_find_by_id(pool, winner).await.ok()? {
-                Some(account) => Some(account.into()),
-                None => None,
-            }
-        } else {
-            None
-        }
+        let winner = self.0.winner?;
+        let loader = ctx.data_unchecked::<DataLoader<AccountLoader>>();
+        
+        let account = loader.load_one(winner).await.ok()??;
+        Some(account.into())
+    }
+
+    /// Represent the ticket number picked by the draw
+    async fn winning_number(&self) -> Option<i32> {
+        self.0.winning_number
+    }
+    
+    /// Represent the purchase which holds the winning ticket number
+    async fn winning_ticket(&self, ctx: &Context<'_>) -> Option<TicketType> {
+        let winning_ticket = self.0.winning_ticket?;
+        let loader = ctx.data_unchecked::<DataLoader<TicketLoader>>();
+        
+        let ticket = loader.load_one(winning_ticket).await.ok()??;
+        Some(ticket.into())
+    }
+
+    /// Represent the place paid by the draw, 1 being the first place
+    async fn tier(&self) -> i32 {
+        self.0.tier
+    }
+    
+    /// Represent the amount paid to the winner
+    async fn amount(&self) -> Option<Decimal> {
+        self.0.amount
+    }
+    
+    /// Represent the percentage of the prize pool paid to the winner
+    async fn share(&self) -> Option<Decimal> {
+        self.0.share
     }
 
     async fn transaction_hash(&self) -> Option<String> {
@@ -1,21 +1,27 @@
+use async_graphql::connection::{Connection, EmptyFields};
+use async_graphql::dataloader::DataLoader;
 use async_graphql::{Context, Object};
 use chrono::{Days, Utc};
 use entity::draw::DrawStatus;
-use service::draw::store::DrawStore;
-use service::prize::store::PrizeStore;
+use entity::prize::PrizeSource;
+use service::common::types::KeysetCursor;
 use service::ticket::store::TicketStore;
+use service::ticket::types::LotteryTicketStats;
 use sqlx::types::Decimal;
+use lib::error::Error;
+use tracing::warn;
 
-use entity::prelude::{AssetModel, LotteryModel, LotteryStatus};
-use service::asset::store::AssetStore;
+use entity::prelude::{AssetModel, LotteryCategory, LotteryModel, LotteryStatus};
 use service::{
     prelude::{ConfigService, StoreService},
     services::ServiceProvider,
 };
 
+use crate::loaders::{AssetLoader, LotteryDrawsLoader, LotteryPrizesLoader, LotteryStatusHistoryLoader, LotteryTicketStatsLoader};
 use crate::objects::asset::types::AssetType;
+use crate::objects::common::connection::{build_connection, resolve_connection_args, Cursor};
 
-use super::{DrawType, PrizeType, TicketType};
+use super::{DrawType, LotteryStatusHistoryType, PrizeType, TicketType};
 
 pub struct LotteryType(LotteryModel);
 
@@ -31,27 +37,40 @@ impl LotteryType {
         format!("{:#x}", self.0.id)
     }
     
-    async fn tickets(&self, ctx: &Context<'_>) -> Vec<TicketType> {
+    /// Represent the tickets of the lottery, last bought first
+    async fn tickets(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> async_graphql::Result<Connection<Cursor, TicketType>> {
         let services = ctx.data_unchecked::<ServiceProvider>();
         let store_service = services.get_service_unchecked::<StoreService>().await;
         let pool = store_service.read();
         
-        let tickets = TicketStore::find_last_bought_tickets_by_lottery_id(pool, self.0.id).await.unwrap();
-        tickets.into_iter().map(|ticket| ticket.into()).collect()
+        let (after, first) = resolve_connection_args(after, first)?;
+        let has_previous_page = after.is_some();
+        let tickets = TicketStore::find_last_bought_tickets_by_lottery_id(pool, self.0.id, after, first as i64 + 1)
+            .await
+            .map_err(|e| match e.current_context() {
+                Error::InvalidCursor => async_graphql::Error::new(Error::InvalidCursor.to_string()),
+                _ => {
+                    warn!("Failed to fetch lottery tickets: {e:?}");
+                    async_graphql::Error::from("Internal error")
+                }
+            })?;
+        
+        Ok(build_connection(tickets, first, has_previous_page, EmptyFields, |ticket| {
+            (KeysetCursor::from_timestamp(TicketStore::PAGE_SORT, ticket.purchased_at, ticket.id), ticket.into())
+        }))
     }
     
     async fn draw(&self, ctx: &Context<'_>) -> Option<DrawType> {
-        let services = ctx.data_unchecked::<ServiceProvider>();
-        let store_service = services.get_service_unchecked::<StoreService>().await;
-        let pool = store_service.read();
-        
-        let draw = DrawStore::find_by_lottery_id(pool, self.0.id).await;
+        let loader = ctx.data_unchecked::<DataLoader<LotteryDrawsLoader>>();
         
-        if draw.is_err() {
-            return None;
-        }
+        let draws = loader.load_one(self.0.id).await.ok()??;
+        let draw = draws.into_iter().next()?;
         
-        let draw = draw.unwrap();
         if draw.status.eq(&DrawStatus::Pending) {
             return None;
         }
@@ -59,17 +78,61 @@ impl LotteryType {
         Some(draw.into())
     }
     
+    /// Represent the paid draws of the lottery, ordered by place
+    async fn winners(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<DrawType>> {
+        let loader = ctx.data_unchecked::<DataLoader<LotteryDrawsLoader>>();
+        
+        let draws = loader.load_one(self.0.id).await.map_err(|e| {
+            warn!("Failed to fetch winners: {e:?}");
+            async_graphql::Error::from("Internal error")
+        })?;
+        
+        Ok(draws
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|draw| draw.status == DrawStatus::Completed)
+            .map(|draw| draw.into())
+            .collect())
+    }
+    
+    /// Represent the status transitions of the lottery, oldest first
+    async fn status_history(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<LotteryStatusHistoryType>> {
+        let loader = ctx.data_unchecked::<DataLoader<LotteryStatusHistoryLoader>>();
+        
+        let history = loader.load_one(self.0.id).await.map_err(|e| {
+            warn!("Failed to fetch lottery status history: {e:?}");
+            async_graphql::Error::from("Internal error")
+        })?;
+        
+        Ok(history.unwrap_or_default().into_iter().map(|entry| entry.into()).collect())
+    }
+    
     async fn prize(&self, ctx: &Context<'_>) -> async_graphql::Result<PrizeType> {
-        let services = ctx.data_unchecked::<ServiceProvider>();
-        let store_service = services.get_service_unchecked::<StoreService>().await;
-        let pool = store_service.read();
+        let loader = ctx.data_unchecked::<DataLoader<LotteryPrizesLoader>>();
         
-        match PrizeStore::find_by_lottery_id(pool, self.0.id).await {
-            Ok(prize) => Ok(prize.into()),
-            Err(_) => Err(async_graphql::Error::new("No prize found"))
+        let prizes = loader.load_one(self.0.id).await.map_err(|e| {
+            warn!("Failed to fetch prize: {e:?}");
+            async_graphql::Error::from("Internal error")
+        })?;
+        
+        match prizes.unwrap_or_default().into_iter().find(|prize| prize.source == PrizeSource::Tickets) {
+            Some(prize) => Ok(prize.into()),
+            None => Err(async_graphql::Error::new("No prize found"))
         }
     }
     
+    /// Represent the number of tickets sold
+    async fn tickets_sold(&self, ctx: &Context<'_>) -> async_graphql::Result<i64> {
+        let stats = self.ticket_stats(ctx).await?;
+        Ok(stats.tickets_sold)
+    }
+    
+    /// Represent the number of accounts holding tickets
+    async fn participants_count(&self, ctx: &Context<'_>) -> async_graphql::Result<i64> {
+        let stats = self.ticket_stats(ctx).await?;
+        Ok(stats.participants)
+    }
+    
     async fn uid(&self) -> String {
         self.0.uid.clone()
     }
@@ -78,6 +141,36 @@ impl LotteryType {
         &self.0.name
     }
 
+    async fn featured(&self) -> bool {
+        self.0.featured
+    }
+
+    /// Slot among the featured lotteries, lowest first
+    async fn featured_position(&self) -> Option<i32> {
+        self.0.featured_position
+    }
+
+    /// Hidden lotteries are left out of the public listings
+    async fn hidden(&self) -> bool {
+        self.0.hidden
+    }
+
+    async fn description(&self) -> Option<&String> {
+        self.0.description.as_ref()
+    }
+
+    async fn cover_image_url(&self) -> Option<&String> {
+        self.0.cover_image_url.as_ref()
+    }
+
+    async fn category(&self) -> Option<LotteryCategory> {
+        self.0.category
+    }
+
+    async fn tags(&self) -> &Vec<String> {
+        &self.0.tags
+    }
+
     async fn start_date(&self) -> String {
         self.0.start_date.to_rfc3339()
     }
@@ -95,11 +188,9 @@ impl LotteryType {
     }
 
     async fn ticket_asset(&self, ctx: &Context<'_>) -> Option<AssetType> {
-        let services = ctx.data_unchecked::<ServiceProvider>();
-        let store_service = services.get_service_unchecked::<StoreService>().await;
-        let pool = store_service.read();
+        let loader = ctx.data_unchecked::<DataLoader<AssetLoader>>();
         
-        let asset = AssetStore::find_by_id(pool, self.0.ticket_asset).await.ok()?;
+        let asset = loader.load_one(self.0.ticket_asset).await.ok()??;
         Some(asset.into())
     }
     
@@ -119,3 +210,16 @@ impl LotteryType {
         self.0.created_at.to_rfc3339()
     }
 }
+
+impl LotteryType {
+    async fn ticket_stats(&self, ctx: &Context<'_>) -> async_graphql::Result<LotteryTicketStats> {
+        let loader = ctx.data_unchecked::<DataLoader<LotteryTicketStatsLoader>>();
+        
+        let stats = loader.load_one(self.0.id).await.map_err(|e| {
+            warn!("Failed to fetch ticket stats: {e:?}");
+            async_graphql::Error::from("Internal error")
+        })?;
+        
+        Ok(stats.unwrap_or_default())
+    }
+}
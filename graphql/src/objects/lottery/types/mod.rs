@@ -2,8 +2,12 @@ pub mod lottery;
 pub mod prize;
 pub mod draw;
 pub mod ticket;
+pub mod status_history;
+pub mod audit_log;
 
 pub use lottery::*;
 pub use prize::*;
 pub use draw::*;
-pub use ticket::*;
\ No newline at end of file
+pub use ticket::*;
+pub use status_history::*;
+pub use audit_log::*;
@@ -1,16 +1,16 @@
+use async_graphql::dataloader::DataLoader;
 use async_graphql::{Context, Object};
 use chrono::{Days, Utc};
-use entity::prize::PrizeStatus;
+use entity::prize::{PrizeSource, PrizeStatus};
 use sqlx::types::Decimal;
 
 use entity::prelude::{AssetModel, PrizeModel};
-use service::asset::store::AssetStore;
-use service::{
-    prelude::{ConfigService, StoreService},
-    services::ServiceProvider,
-};
+use tracing::warn;
+use service::prelude::ConfigService;
+use service::prize::prize_pool;
 
-use crate::objects::asset::types::AssetType;
+use crate::loaders::{AccountLoader, AssetLoader, LotteryPrizesLoader};
+use crate::objects::{account::types::AccountType, asset::types::AssetType};
 
 pub struct PrizeType(PrizeModel);
 
@@ -30,17 +30,52 @@ impl PrizeType {
         format!("{:#x}", self.0.lottery_id)
     }
     
-    async fn prize_asset(&self, ctx: &Context<'_>) -> AssetType {
-        let services = ctx.data_unchecked::<ServiceProvider>();
-        let store_service = services.get_service_unchecked::<StoreService>().await;
-        let pool = store_service.read();
+    async fn prize_asset(&self, ctx: &Context<'_>) -> async_graphql::Result<AssetType> {
+        let loader = ctx.data_unchecked::<DataLoader<AssetLoader>>();
         
-        let asset = AssetStore::find_by_id(pool, self.0.prize_asset).await.unwrap();
-        asset.into()
+        match loader.load_one(self.0.prize_asset).await {
+            Ok(Some(asset)) => Ok(asset.into()),
+            _ => Err(async_graphql::Error::new("Unable to find asset")),
+        }
     }
     
-    async fn total_prize_pool(&self) -> Decimal {
-        self.0.value
+    /// Represent the value of every prize of the lottery in the asset of this prize, sponsor
+    /// contributions included
+    async fn total_prize_pool(&self, ctx: &Context<'_>) -> async_graphql::Result<Decimal> {
+        let loader = ctx.data_unchecked::<DataLoader<LotteryPrizesLoader>>();
+        
+        let prizes = loader.load_one(self.0.lottery_id).await.map_err(|e| {
+            warn!("Failed to fetch lottery prizes: {e:?}");
+            async_graphql::Error::from("Internal error")
+        })?;
+        
+        Ok(prize_pool(&prizes.unwrap_or_default(), self.0.prize_asset))
+    }
+    
+    /// Represent who funds the prize, ticket sales or a sponsor
+    async fn source(&self) -> PrizeSource {
+        self.0.source
+    }
+    
+    /// Represent the sponsor of the prize, only set for sponsor prizes
+    async fn sponsor(&self, ctx: &Context<'_>) -> Option<AccountType> {
+        let sponsor = self.0.sponsor?;
+        let loader = ctx.data_unchecked::<DataLoader<AccountLoader>>();
+        
+        let account = loader.load_one(sponsor).await.ok()??;
+        Some(account.into())
+    }
+    
+    /// Represent every prize of the lottery, the ticket-funded pool followed by sponsor contributions
+    async fn breakdown(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<PrizeType>> {
+        let loader = ctx.data_unchecked::<DataLoader<LotteryPrizesLoader>>();
+        
+        let prizes = loader.load_one(self.0.lottery_id).await.map_err(|e| {
+            warn!("Failed to fetch prize breakdown: {e:?}");
+            async_graphql::Error::from("Internal error")
+        })?;
+        
+        Ok(prizes.unwrap_or_default().into_iter().map(|prize| prize.into()).collect())
     }
 
     async fn status(&self) -> PrizeStatus {
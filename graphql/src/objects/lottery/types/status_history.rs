@@ -0,0 +1,46 @@
+use async_graphql::Object;
+use entity::prelude::{LotteryStatus, LotteryStatusHistoryModel};
+
+pub struct LotteryStatusHistoryType(pub LotteryStatusHistoryModel);
+
+impl From<LotteryStatusHistoryModel> for LotteryStatusHistoryType {
+    fn from(value: LotteryStatusHistoryModel) -> Self {
+        LotteryStatusHistoryType(value)
+    }
+}
+
+/// Represent a status transition of a lottery, and the event which caused it
+#[Object]
+impl LotteryStatusHistoryType {
+    async fn id(&self) -> String {
+        format!("{:#x}", self.0.id)
+    }
+
+    async fn from_status(&self) -> Option<LotteryStatus> {
+        self.0.from_status
+    }
+
+    async fn to_status(&self) -> LotteryStatus {
+        self.0.to_status
+    }
+
+    async fn chain(&self) -> Option<&String> {
+        self.0.chain.as_ref()
+    }
+
+    async fn block_number(&self) -> Option<i64> {
+        self.0.block_number
+    }
+
+    async fn transaction_hash(&self) -> Option<&String> {
+        self.0.transaction_hash.as_ref()
+    }
+
+    async fn log_index(&self) -> Option<i32> {
+        self.0.log_index
+    }
+
+    async fn created_at(&self) -> String {
+        self.0.created_at.to_rfc3339()
+    }
+}
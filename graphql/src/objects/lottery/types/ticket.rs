@@ -1,16 +1,12 @@
+use async_graphql::dataloader::DataLoader;
 use async_graphql::{Context, Object};
 use chrono::{Days, Utc};
-use service::account::store::AccountStore;
-use service::lottery::store::LotteryStore;
 use sqlx::types::Decimal;
 
 use entity::prelude::{AssetModel, TicketModel};
-use service::asset::store::AssetStore;
-use service::{
-    prelude::{ConfigService, StoreService},
-    services::ServiceProvider,
-};
+use service::prelude::ConfigService;
 
+use crate::loaders::{AccountLoader, AssetLoader, LotteryLoader};
 use crate::objects::account::types::AccountType;
 use crate::objects::asset::types::AssetType;
 
@@ -33,36 +29,32 @@ impl TicketType {
     }
     
     async fn lottery_uid(&self, ctx: &Context<'_>) -> async_graphql::Result<String> {
-        let services = ctx.data_unchecked::<ServiceProvider>();
-            let store_service = services.get_service_unchecked::<StoreService>().await;
-            let pool = store_service.read();
-            
-            match LotteryStore::find_by_id(pool, self.0.lottery_id).await {
-                Ok(lottery) => Ok(lottery.uid),
-                Err(_) => Err(async_graphql::Error::new("Unable to find lottery")),
-            }
+        let loader = ctx.data_unchecked::<DataLoader<LotteryLoader>>();
+        
+        match loader.load_one(self.0.lottery_id).await {
+            Ok(Some(lottery)) => Ok(lottery.uid),
+            _ => Err(async_graphql::Error::new("Unable to find lottery")),
+        }
     }
     
     async fn asset(&self, ctx: &Context<'_>) -> async_graphql::Result<AssetType> {
-        let services = ctx.data_unchecked::<ServiceProvider>();
-        let store_service = services.get_service_unchecked::<StoreService>().await;
-        let pool = store_service.read();
+        let loader = ctx.data_unchecked::<DataLoader<AssetLoader>>();
         
-        let asset = match AssetStore::find_by_id(pool, self.0.ticket_asset).await {
-            Ok(asset) => Ok(asset),
-            Err(_) => Err(async_graphql::Error::new("Unable to find asset"))
+        let asset = match loader.load_one(self.0.ticket_asset).await {
+            Ok(Some(asset)) => Ok(asset),
+            _ => Err(async_graphql::Error::new("Unable to find asset"))
         }?;
         
         Ok(asset.into())
     }
     
-    async fn account(&self, ctx: &Context<'_>) -> AccountType {
-        let services = ctx.data_unchecked::<ServiceProvider>();
-        let store_service = services.get_service_unchecked::<StoreService>().await;
-        let pool = store_service.read();
+    async fn account(&self, ctx: &Context<'_>) -> async_graphql::Result<AccountType> {
+        let loader = ctx.data_unchecked::<DataLoader<AccountLoader>>();
         
-        let account = AccountStore::find_by_id(pool, self.0.account_id).await.unwrap();
-        account.into()
+        match loader.load_one(self.0.account_id).await {
+            Ok(Some(account)) => Ok(account.into()),
+            _ => Err(async_graphql::Error::new("Unable to find account")),
+        }
     }
     
     /// Represent the number of tickets the user has bought on this transaction
@@ -70,6 +62,16 @@ impl TicketType {
         self.0.amount as u32
     }
     
+    /// Represent the first ticket number assigned to this purchase
+    async fn first_number(&self) -> i32 {
+        self.0.first_number
+    }
+    
+    /// Represent the last ticket number assigned to this purchase
+    async fn last_number(&self) -> i32 {
+        self.0.last_number
+    }
+    
     /// Represent the price of each ticket
     async fn individual_ticket_price(&self) -> Decimal {
         self.0.ticket_price.into()
@@ -2,15 +2,17 @@ pub mod account;
 pub mod asset;
 pub mod common;
 pub mod image;
+pub mod leaderboard;
 pub mod system;
 pub mod twitter;
 pub mod lottery;
 
 use async_graphql::{MergedObject, MergedSubscription};
-use lottery::{tickets::{TicketQuery, TicketSubscription}, LotteryQuery};
+use leaderboard::LeaderboardQuery;
+use lottery::{admin::{LotteryAdminMutation, LotteryAdminQuery}, subscriptions::LotterySubscription, tickets::{TicketQuery, TicketSubscription}, LotteryQuery};
 
 use self::{
-    account::{AccountMutation, AccountQuery, AccountSubscription},
+    account::{subscriptions::AchievementSubscription, AccountMutation, AccountQuery, AccountSubscription},
     asset::{AssetQuery, AssetSubscription},
     // image::ImageMutation,
     twitter::{TwitterMutation, TwitterQuery},
@@ -23,19 +25,24 @@ pub struct Query(
     AssetQuery,
     TwitterQuery,
     LotteryQuery,
-    TicketQuery
+    LotteryAdminQuery,
+    TicketQuery,
+    LeaderboardQuery
 );
 
 #[derive(MergedObject, Default)]
 pub struct Mutation(
     TwitterMutation,
     AccountMutation,
+    LotteryAdminMutation,
     // ImageMutation,
 );
 
 #[derive(MergedSubscription, Default)]
 pub struct Subscription(
-    TicketSubscription
+    TicketSubscription,
+    LotterySubscription,
+    AchievementSubscription
 );
 
 pub struct GQLJWTData {
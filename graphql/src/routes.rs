@@ -5,10 +5,14 @@ use axum::{
     http::HeaderMap,
     response::{Html, IntoResponse, Response},
 };
+use jsonwebtoken::TokenData;
+use lib::error::Error;
 use serde::Deserialize;
+use service::auth::AuthService;
 use service::config::service::ConfigService;
 use tracing::{debug, warn};
 
+use crate::helpers::jwt::Claims;
 use crate::ide::altair::AltairGraphQL;
 use crate::objects::GQLJWTData;
 use crate::server::AppState;
@@ -38,23 +42,10 @@ pub async fn graphql_ws_handler(
                             }
                             let token = token.unwrap();
 
-                            match state.jwt.decode(token) {
-                                Ok(token) => Ok(Some(token.claims)),
-                                Err(err) => match *err.kind() {
-                                    jsonwebtoken::errors::ErrorKind::InvalidToken => {
-                                        Err(async_graphql::Error::new("Token is invalid"))
-                                    }
-                                    jsonwebtoken::errors::ErrorKind::ExpiredSignature => {
-                                        Err(async_graphql::Error::new("Token has expired"))
-                                    }
-                                    _ => {
-                                        warn!("Token validation error: {}", err);
-                                        Err(async_graphql::Error::new(
-                                            "Unable to validate auth token",
-                                        ))
-                                    }
-                                },
-                            }?
+                            authorize(&state, token)
+                                .await
+                                .map(Some)
+                                .map_err(async_graphql::Error::new)?
                         } else {
                             None
                         }
@@ -81,24 +72,13 @@ pub async fn graphql_handler(
             async_graphql::Response::from_errors(vec![ServerError::new(msg, None)]).into()
         };
 
-        match state.jwt.decode(token) {
-            Ok(token) => {
+        match authorize(&state, token).await {
+            Ok(claims) => {
                 request = request.data(GQLJWTData {
-                    claims: Some(token.claims),
+                    claims: Some(claims),
                 })
             }
-            Err(err) => match *err.kind() {
-                jsonwebtoken::errors::ErrorKind::InvalidToken => {
-                    return err_msg_response("Token is invalid");
-                }
-                jsonwebtoken::errors::ErrorKind::ExpiredSignature => {
-                    return err_msg_response("Token has expired");
-                }
-                _ => {
-                    warn!("Token validation error: {}", err);
-                    return err_msg_response("Unable to validate auth token");
-                }
-            },
+            Err(msg) => return err_msg_response(msg),
         }
     }
 
@@ -124,6 +104,43 @@ pub async fn health() -> impl IntoResponse {
     Html("OK")
 }
 
+/// Decode the access token, rejecting expired and revoked ones
+async fn authorize(state: &AppState, token: String) -> Result<Claims, &'static str> {
+    let claims = decoded_claims(state.jwt.decode(token))?;
+
+    let auth = state.services.get_service_unchecked::<AuthService>().await;
+    let revoked = auth.is_token_revoked(claims.jti).await;
+
+    unrevoked_claims(claims, revoked)
+}
+
+fn decoded_claims(decoded: jsonwebtoken::errors::Result<TokenData<Claims>>) -> Result<Claims, &'static str> {
+    match decoded {
+        Ok(token) => Ok(token.claims),
+        Err(err) => match *err.kind() {
+            jsonwebtoken::errors::ErrorKind::InvalidToken => Err("Token is invalid"),
+            jsonwebtoken::errors::ErrorKind::ExpiredSignature => Err("Token has expired"),
+            // Tokens issued before sessions carry no token id, they can't be revoked
+            jsonwebtoken::errors::ErrorKind::Json(_) => Err("Token is invalid, please log in again"),
+            _ => {
+                warn!("Token validation error: {}", err);
+                Err("Unable to validate auth token")
+            }
+        },
+    }
+}
+
+fn unrevoked_claims(claims: Claims, revoked: error_stack::Result<bool, Error>) -> Result<Claims, &'static str> {
+    match revoked {
+        Ok(false) => Ok(claims),
+        Ok(true) => Err("Token has been revoked"),
+        Err(err) => {
+            warn!("Failed to check token revocation: {err:?}");
+            Err("Unable to validate auth token")
+        }
+    }
+}
+
 fn extract_token_from_str(value: &str) -> Option<String> {
     value.split_once(' ').map(|s| s.1).map(|s| s.to_owned())
 }
@@ -134,3 +151,75 @@ fn get_auth_token_from_headers(headers: &HeaderMap) -> Option<String> {
         .and_then(|value| value.to_str().ok())
         .and_then(extract_token_from_str)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use entity::account::AccountRole;
+    use error_stack::Report;
+    use jsonwebtoken::errors::ErrorKind;
+    use jsonwebtoken::Header;
+    use uuid::Uuid;
+
+    fn claims(jti: Uuid) -> Claims {
+        Claims {
+            sub: "0x0000000000000000000000000000000000000001".to_string(),
+            exp: 0,
+            jti,
+            sid: Uuid::new_v4(),
+            role: AccountRole::default(),
+        }
+    }
+
+    fn decoded(claims: Claims) -> jsonwebtoken::errors::Result<TokenData<Claims>> {
+        Ok(TokenData { header: Header::default(), claims })
+    }
+
+    #[test]
+    fn decoded_token_is_accepted() {
+        let jti = Uuid::new_v4();
+
+        assert_eq!(decoded_claims(decoded(claims(jti))).unwrap().jti, jti);
+    }
+
+    #[test]
+    fn invalid_and_expired_tokens_are_rejected() {
+        assert_eq!(decoded_claims(Err(ErrorKind::InvalidToken.into())).unwrap_err(), "Token is invalid");
+        assert_eq!(decoded_claims(Err(ErrorKind::ExpiredSignature.into())).unwrap_err(), "Token has expired");
+        assert_eq!(
+            decoded_claims(Err(ErrorKind::InvalidSignature.into())).unwrap_err(),
+            "Unable to validate auth token"
+        );
+    }
+
+    #[test]
+    fn token_of_rotated_session_is_rejected() {
+        let result = unrevoked_claims(claims(Uuid::new_v4()), Ok(true));
+
+        assert_eq!(result.unwrap_err(), "Token has been revoked");
+    }
+
+    #[test]
+    fn token_of_active_session_is_accepted() {
+        assert!(unrevoked_claims(claims(Uuid::new_v4()), Ok(false)).is_ok());
+    }
+
+    #[test]
+    fn token_is_rejected_when_revocation_is_unknown() {
+        let result = unrevoked_claims(claims(Uuid::new_v4()), Err(Report::new(Error::Redis)));
+
+        assert_eq!(result.unwrap_err(), "Unable to validate auth token");
+    }
+
+    #[test]
+    fn token_issued_before_sessions_is_rejected() {
+        let legacy = serde_json::from_value::<Claims>(serde_json::json!({
+            "sub": "0x0000000000000000000000000000000000000001",
+            "exp": 0,
+        }))
+        .map_err(jsonwebtoken::errors::Error::from)
+        .map(|claims| TokenData { header: Header::default(), claims });
+
+        assert_eq!(decoded_claims(legacy).unwrap_err(), "Token is invalid, please log in again");
+    }
+}
@@ -4,7 +4,9 @@ use crate::objects::{Mutation, Query, Subscription as SubscriptionRoot};
 use crate::{
     helpers::jwt::JWT,
     loaders::{
-        account::AccountLoader, asset::AssetLoader
+        account::{AccountLoader, AccountStatsLoader}, asset::AssetLoader, draw::LotteryDrawsLoader,
+        lottery::{LotteryLoader, LotteryStatusHistoryLoader, LotteryTicketStatsLoader},
+        prize::LotteryPrizesLoader, ticket::TicketLoader,
     },
 };
 use async_graphql::extensions::{Tracing, OpenTelemetry};
@@ -59,10 +61,38 @@ pub async fn new(ctx: GQLGlobalData) -> ServiceSchema {
         AccountLoader::new(store.clone()),
         tokio::spawn,
     ))
+    .data(DataLoader::new(
+        AccountStatsLoader::new(store.clone()),
+        tokio::spawn,
+    ))
     .data(DataLoader::new(
         AssetLoader::new(store.clone()),
         tokio::spawn,
     ))
+    .data(DataLoader::new(
+        LotteryLoader::new(store.clone()),
+        tokio::spawn,
+    ))
+    .data(DataLoader::new(
+        LotteryStatusHistoryLoader::new(store.clone()),
+        tokio::spawn,
+    ))
+    .data(DataLoader::new(
+        LotteryTicketStatsLoader::new(store.clone()),
+        tokio::spawn,
+    ))
+    .data(DataLoader::new(
+        LotteryPrizesLoader::new(store.clone()),
+        tokio::spawn,
+    ))
+    .data(DataLoader::new(
+        LotteryDrawsLoader::new(store.clone()),
+        tokio::spawn,
+    ))
+    .data(DataLoader::new(
+        TicketLoader::new(store.clone()),
+        tokio::spawn,
+    ))
     .extension(Tracing)
     .extension(opentelemetry_extension)
     .finish()
@@ -6,17 +6,28 @@ use crate::chain::subscription::ChainSubscription;
 use crate::chain::transformer::EventTransformer;
 use crate::chain::validator::EventValidator;
 use crate::handler::{Handler, HandlerPayload};
+use crate::handlers::PaidWinner;
 use crate::state::StateManager;
-use crate::stream::{ChainEventKind, ChainStream, StreamProvider, StreamProviderResult};
+use crate::stream::{
+    ChainEvent, ChainEventKind, ChainStream, RejectedEvent, StreamProvider, StreamProviderResult,
+    Transformer,
+};
 use chrono::{TimeZone, Utc};
+use error_stack::{Report, Result, ResultExt};
+use entity::raw_log::RawLogModel;
+use ethers::types::{Address, Log, H256, U256, U64};
+use ethers::providers::Middleware;
 use futures::StreamExt;
+use lib::error::Error;
 use service::chain::provider::ChainProvider;
 use service::chain::traits::string::ToHexString;
 use service::chain::utils::get_block::get_timestamp_by_block;
-use service::chain::Chain;
+use service::chain::{Chain, ChainClient};
+use service::raw_log::{store::RawLogStore, types::CreateRawLog};
 use service::prelude::StoreService;
 use service::transaction::service::TransactionService;
 use service::transaction::store::TransactionStore;
+use std::str::FromStr;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use tracing::{debug, error, info, warn, Instrument};
@@ -52,123 +63,52 @@ impl StreamProvider for Chain {
                 // We cannot validate events on validator, since events validated there by DDBB, may result in
                 // dupplicated events since we send the event to the channel, and validate the next event. This will result
                 // in that the event is not fully processed and saved to database yet.
-                while let Some(mut event) = stream.next().await {
-                    let services = self.services.clone();
-                    let store_service = services.get_service_unchecked::<StoreService>().await;
-                    let pool = store_service.read();
-                    
-                    if let Some(transaction_log) =
-                    TransactionStore::try_find_by_hash_and_log_index(
-                        pool,
-                        event.transaction_hash,
-                        event.log_index,
-                    )
-                    .await?
-                    {
-                        let hash = transaction_log.transaction_hash;
-                        let log_index = transaction_log.log_index;
-                        info!("Event with hash {} already processed with log_index {}", hash, log_index);
-                        continue;
-                    }
-                    
-                    let event_context = HandlerPayload::from((event.clone(), event.kind.clone()))
-                        .get_context(&self);
-                    
-                    let mut db_tx = store_service.begin_transaction().await?;
-                    let timestamp =
-                        get_timestamp_by_block(event.block_number, client.clone()).await?;
-                    
-                    event.triggered_at = Utc.timestamp_opt(timestamp as i64, 0).unwrap(); // Safe to unwrap
-                    let transaction_service =
-                        services.get_service_unchecked::<TransactionService>().await;
-                    
-                    let event_block_number = event.block_number.as_u64();
-                    let event_handler_result = match event.kind.clone() {
-                        ChainEventKind::LotteryOpened(kind) => {
-                                self.handle(
-                                    HandlerPayload::from((event.clone(), kind)),
-                                    services,
-                                    state_manager.clone(),
-                                    &mut db_tx,
-                                )
-                                .await
-                            }
-                            ChainEventKind::LotteryClosed(kind) => {
-                                self.handle(
-                                    HandlerPayload::from((event.clone(), kind)),
-                                    services,
-                                    state_manager.clone(),
-                                    &mut db_tx,
-                                )
-                                .await
-                            }
-                            ChainEventKind::TicketBought(kind) => {
-                                self.handle(
-                                    HandlerPayload::from((event.clone(), kind)),
-                                    services,
-                                    state_manager.clone(),
-                                    &mut db_tx,
-                                )
-                                .await
-                            }
-                            ChainEventKind::WinnerPaid(kind) => {
-                                self.handle(
-                                    HandlerPayload::from((event.clone(), kind)),
-                                    services,
-                                    state_manager.clone(),
-                                    &mut db_tx,
-                                )
-                                .await
-                            }
-                            ChainEventKind::LotteryNumberGenerated(kind) => {
-                                self.handle(
-                                    HandlerPayload::from((event.clone(), kind)),
-                                    services,
-                                    state_manager.clone(),
-                                    &mut db_tx,
-                                )
-                                .await
-                            }
-                            ChainEventKind::LotteryCanceled(kind) => {
-                                self.handle(
-                                    HandlerPayload::from((event.clone(), kind)),
-                                    services,
-                                    state_manager.clone(),
-                                    &mut db_tx,
-                                )
-                                .await
-                            }
-                            ChainEventKind::FeeCollected(kind) => {
-                                self.handle(
-                                    HandlerPayload::from((event.clone(), kind)),
-                                    services,
-                                    state_manager.clone(),
-                                    &mut db_tx,
-                                )
-                                .await
+                while let Some(item) = stream.next().await {
+                    let event = match item {
+                        Ok(event) => event,
+                        Err(rejected) => {
+                            // Keep the log around, so it can be decoded again once bindings are updated.
+                            // A log failing to be archived must not stop the chain
+                            if let Err(e) = archive_rejected_log(&self, rejected).await {
+                                error!(reason = ?e, "Failed to archive rejected log");
                             }
+                            continue;
+                        }
                     };
-                    
-                    if let Err(e) = event_handler_result {
-                        error!(
-                            tx_hash = event.transaction_hash.to_hex_string(),
-                            block_number = event.block_number.to_string(),
-                            log_index = event.log_index.to_string(),
-                            "Failed to handle chain event. Error: {:?}", e
-                        );
-                        break;
+
+                    let event_block_number = event.block_number.as_u64();
+                    let event_position = (event.transaction_hash, event.log_index);
+                    let tx_hash = event.transaction_hash.to_hex_string();
+                    let log_index = event.log_index.to_string();
+
+                    match process_event(&self, event, client.clone(), state_manager.clone()).await {
+                        Ok(true) => {}
+                        Ok(false) => continue,
+                        // Events breaking the lottery lifecycle are parked with the rejected logs,
+                        // so they can be replayed once the lottery is fixed
+                        Err(e) if matches!(e.current_context(), Error::LotteryInvalidStatusTransition) => {
+                            warn!(tx_hash, log_index, reason = ?e, "Parking chain event");
+
+                            let (transaction_hash, log_index) = event_position;
+                            if let Err(e) = park_event(&self, &client, transaction_hash, log_index, format!("{e:#}")).await {
+                                error!(tx_hash, reason = ?e, "Failed to park chain event");
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            error!(
+                                tx_hash,
+                                block_number = event_block_number.to_string(),
+                                log_index,
+                                "Failed to handle chain event. Error: {:?}", e
+                            );
+                            break;
+                        }
                     }
-                    
-                    let transaction = transaction_service
-                        .create_without_side_effects(event_context, &mut db_tx)
-                        .await?;
-                    
-                    debug!("Transaction log created: {:?}", transaction);
-                    store_service.commit_transaction(db_tx).await?;
-                    
+
                     state_manager.set_block_number(event_block_number).await?;
                     state_manager.save().await?;
-                    
+
                     // Restart stream when state has changed
                     if let Some(state) = state_manager.next().await {
                         stream.stop().await;
@@ -189,6 +129,323 @@ impl StreamProvider for Chain {
     }
 }
 
+/// Handle the event and record it in the transaction log within a single database transaction.
+///
+/// Returns `false` when the event has already been processed.
+pub(crate) async fn process_event(
+    chain: &Chain,
+    mut event: ChainEvent,
+    client: Arc<ChainClient>,
+    state_manager: StateManager,
+) -> Result<bool, Error> {
+    let services = chain.services.clone();
+    let store_service = services.get_service_unchecked::<StoreService>().await;
+    let pool = store_service.read();
+
+    if let Some(transaction_log) =
+        TransactionStore::try_find_by_hash_and_log_index(pool, event.transaction_hash, event.log_index)
+            .await?
+    {
+        let hash = transaction_log.transaction_hash;
+        let log_index = transaction_log.log_index;
+        info!("Event with hash {} already processed with log_index {}", hash, log_index);
+        return Ok(false);
+    }
+
+    // The chain is read before the transaction begins, so no connection is held while waiting on the node
+    let timestamp =
+        get_timestamp_by_block(event.block_number, client.clone()).await?;
+    let paid_winner = match &event.kind {
+        ChainEventKind::WinnerPaid(kind) => Some(PaidWinner::fetch(chain, kind.clone(), event.transaction_hash).await),
+        _ => None,
+    };
+
+    let mut db_tx = store_service.begin_transaction().await?;
+
+    event.triggered_at = Utc.timestamp_opt(timestamp as i64, 0).unwrap(); // Safe to unwrap
+
+    let event_context = HandlerPayload::from((event.clone(), event.kind.clone()))
+        .get_context(chain);
+    let transaction_service =
+        services.get_service_unchecked::<TransactionService>().await;
+
+    match event.kind.clone() {
+        ChainEventKind::LotteryOpened(kind) => {
+            chain
+                .handle(
+                    HandlerPayload::from((event.clone(), kind)),
+                    services,
+                    state_manager,
+                    &mut db_tx,
+                )
+                .await?
+        }
+        ChainEventKind::LotteryClosed(kind) => {
+            chain
+                .handle(
+                    HandlerPayload::from((event.clone(), kind)),
+                    services,
+                    state_manager,
+                    &mut db_tx,
+                )
+                .await?
+        }
+        ChainEventKind::TicketBought(kind) => {
+            chain
+                .handle(
+                    HandlerPayload::from((event.clone(), kind)),
+                    services,
+                    state_manager,
+                    &mut db_tx,
+                )
+                .await?
+        }
+        ChainEventKind::WinnerPaid(kind) => {
+            let kind = paid_winner.unwrap_or(PaidWinner { event: kind, random_number: None });
+            chain
+                .handle(
+                    HandlerPayload::from((event.clone(), kind)),
+                    services,
+                    state_manager,
+                    &mut db_tx,
+                )
+                .await?
+        }
+        ChainEventKind::LotteryNumberGenerated(kind) => {
+            chain
+                .handle(
+                    HandlerPayload::from((event.clone(), kind)),
+                    services,
+                    state_manager,
+                    &mut db_tx,
+                )
+                .await?
+        }
+        ChainEventKind::LotteryCanceled(kind) => {
+            chain
+                .handle(
+                    HandlerPayload::from((event.clone(), kind)),
+                    services,
+                    state_manager,
+                    &mut db_tx,
+                )
+                .await?
+        }
+        ChainEventKind::FeeCollected(kind) => {
+            chain
+                .handle(
+                    HandlerPayload::from((event.clone(), kind)),
+                    services,
+                    state_manager,
+                    &mut db_tx,
+                )
+                .await?
+        }
+    };
+
+    let transaction = transaction_service
+        .find_or_create(event_context, &mut db_tx)
+        .await?;
+
+    debug!("Transaction log created: {:?}", transaction);
+    store_service.commit_transaction(db_tx).await?;
+
+    Ok(true)
+}
+
+/// Store a log the transformer rejected in the raw log archive
+async fn archive_rejected_log(chain: &Chain, rejected: RejectedEvent<Log>) -> Result<(), Error> {
+    let store_service = chain.services.get_service_unchecked::<StoreService>().await;
+    let log = rejected.item;
+
+    let dto = CreateRawLog {
+        chain: chain.name(),
+        address: log.address.to_hex_string(),
+        topics: log.topics.iter().map(|topic| topic.to_hex_string()).collect(),
+        data: format!("0x{}", hex::encode(&log.data)),
+        block_number: log.block_number.map(|block_number| block_number.as_u64() as i64),
+        transaction_hash: log.transaction_hash.map(|hash| hash.to_hex_string()),
+        log_index: log.log_index.map(|log_index| log_index.as_u32() as i32),
+        reason: rejected.reason,
+    };
+
+    let raw_log = RawLogStore::create(store_service.write(), dto).await?;
+
+    warn!(
+        raw_log_id = raw_log.id.to_string(),
+        tx_hash = raw_log.transaction_hash,
+        log_index = raw_log.log_index,
+        "Archived rejected log",
+    );
+
+    Ok(())
+}
+
+/// Store the log of an event its handler rejected in the raw log archive.
+///
+/// The log is fetched again from the receipt of its transaction, as events don't keep it.
+async fn park_event(
+    chain: &Chain,
+    client: &ChainClient,
+    transaction_hash: H256,
+    log_index: U256,
+    reason: String,
+) -> Result<(), Error> {
+    let receipt = client
+        .get_transaction_receipt(transaction_hash)
+        .await
+        .change_context(Error::ContractQuery)?
+        .ok_or(Report::new(Error::NotFound))?;
+
+    let log = receipt
+        .logs
+        .into_iter()
+        .find(|log| log.log_index == Some(log_index))
+        .ok_or(Report::new(Error::NotFound))?;
+
+    archive_rejected_log(chain, RejectedEvent { item: log, reason }).await
+}
+
+/// Decode again the pending raw logs of the chain, handling the ones the transformer now accepts.
+///
+/// Returns the number of logs reprocessed, logs still rejected or failing to be handled get
+/// their reason refreshed. The indexer cursor is left untouched, archived logs are older
+/// than the block it already processed.
+pub(crate) async fn reprocess_raw_logs(chain: &Chain) -> Result<usize, Error> {
+    let store_service = chain.services.get_service_unchecked::<StoreService>().await;
+    let client = chain.get_client()?;
+    let state_manager = StateManager::new(&chain.config, chain.services.clone()).await?;
+
+    let raw_logs = RawLogStore::find_pending_by_chain(store_service.read(), chain.name()).await?;
+    let mut reprocessed = 0;
+
+    for raw_log in raw_logs {
+        let event = raw_log_to_log(&raw_log)
+            .and_then(|log| EventTransformer::transform(log).map_err(|e| format!("{e:#}")));
+
+        let event = match event {
+            Ok(event) => event,
+            Err(reason) => {
+                RawLogStore::update_reason(store_service.write(), raw_log.id, reason).await?;
+                continue;
+            }
+        };
+
+        if let Err(e) = process_event(chain, event, client.clone(), state_manager.clone()).await {
+            error!(raw_log_id = raw_log.id.to_string(), reason = ?e, "Failed to reprocess raw log");
+            RawLogStore::update_reason(store_service.write(), raw_log.id, format!("{e:?}")).await?;
+            continue;
+        }
+        RawLogStore::mark_reprocessed(store_service.write(), raw_log.id).await?;
+
+        info!(raw_log_id = raw_log.id.to_string(), "Raw log reprocessed");
+        reprocessed += 1;
+    }
+
+    Ok(reprocessed)
+}
+
+/// Rebuild the chain log from its archived representation
+fn raw_log_to_log(raw_log: &RawLogModel) -> std::result::Result<Log, String> {
+    let address = Address::from_str(&raw_log.address).map_err(|e| format!("Invalid address: {e}"))?;
+
+    let topics = raw_log
+        .topics
+        .iter()
+        .map(|topic| H256::from_str(topic))
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| format!("Invalid topic: {e}"))?;
+
+    let data = hex::decode(raw_log.data.trim_start_matches("0x"))
+        .map_err(|e| format!("Invalid data: {e}"))?;
+
+    let transaction_hash = raw_log
+        .transaction_hash
+        .as_deref()
+        .map(H256::from_str)
+        .transpose()
+        .map_err(|e| format!("Invalid transaction hash: {e}"))?;
+
+    Ok(Log {
+        address,
+        topics,
+        data: data.into(),
+        block_number: raw_log.block_number.map(|block_number| U64::from(block_number as u64)),
+        transaction_hash,
+        log_index: raw_log.log_index.map(|log_index| U256::from(log_index as u64)),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::types::Uuid;
+
+    fn raw_log(topics: Vec<String>, data: &str) -> RawLogModel {
+        RawLogModel {
+            id: Uuid::new_v4(),
+            chain: "test".to_string(),
+            address: format!("{:?}", Address::from_low_u64_be(1)),
+            topics,
+            data: data.to_string(),
+            block_number: Some(42),
+            transaction_hash: Some(format!("{:?}", H256::from_low_u64_be(2))),
+            log_index: Some(3),
+            reason: String::new(),
+            reprocessed_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_raw_log_to_log() {
+        let topic = H256::from_low_u64_be(7);
+        let log = raw_log_to_log(&raw_log(vec![topic.to_hex_string()], "0x0102")).unwrap();
+
+        assert_eq!(log.address, Address::from_low_u64_be(1));
+        assert_eq!(log.topics, vec![topic]);
+        assert_eq!(log.data.to_vec(), vec![1, 2]);
+        assert_eq!(log.block_number, Some(U64::from(42)));
+        assert_eq!(log.transaction_hash, Some(H256::from_low_u64_be(2)));
+        assert_eq!(log.log_index, Some(U256::from(3)));
+    }
+
+    #[test]
+    fn test_raw_log_to_log_without_position() {
+        let mut raw_log = raw_log(Vec::new(), "");
+        raw_log.block_number = None;
+        raw_log.transaction_hash = None;
+        raw_log.log_index = None;
+
+        let log = raw_log_to_log(&raw_log).unwrap();
+
+        assert!(log.topics.is_empty());
+        assert!(log.data.is_empty());
+        assert_eq!(log.block_number, None);
+        assert_eq!(log.transaction_hash, None);
+        assert_eq!(log.log_index, None);
+    }
+
+    #[test]
+    fn test_raw_log_to_log_rejects_malformed_fields() {
+        let mut invalid_address = raw_log(Vec::new(), "0x");
+        invalid_address.address = "0xnope".to_string();
+        assert!(raw_log_to_log(&invalid_address).unwrap_err().starts_with("Invalid address"));
+
+        let invalid_topic = raw_log(vec!["0x12".to_string()], "0x");
+        assert!(raw_log_to_log(&invalid_topic).unwrap_err().starts_with("Invalid topic"));
+
+        let invalid_data = raw_log(Vec::new(), "0xzz");
+        assert!(raw_log_to_log(&invalid_data).unwrap_err().starts_with("Invalid data"));
+
+        let mut invalid_hash = raw_log(Vec::new(), "0x");
+        invalid_hash.transaction_hash = Some("0x12".to_string());
+        assert!(raw_log_to_log(&invalid_hash).unwrap_err().starts_with("Invalid transaction hash"));
+    }
+}
+
 // OLD CODE
 
 // impl Chain {
@@ -6,9 +6,9 @@ use crate::{
 use async_trait::async_trait;
 use error_stack::{Report, Result};
 use lib::error::Error;
-use service::{chain::provider::ChainProvider, store::service::{DatabaseTransaction, StoreService}};
+use service::{chain::{provider::ChainProvider, traits::string::ToHexString}, lottery::{store::LotteryStore, LotteryService}, store::service::{DatabaseTransaction, StoreService}};
 use service::services::ServiceProvider;
-use tracing::{info, warn};
+use tracing::info;
 
 #[async_trait]
 impl<Provider> Handler<LotteryCanceled> for Provider
@@ -27,8 +27,22 @@ where
             "Received a new LotteryCanceled event",
         );
         
-        // TODO
-
+        let lottery = LotteryStore::find_by_uid(
+            db_tx.as_mut(), 
+            payload.kind.lottery_id.to_hex_string(),
+        ).await?;
+        
+        let lottery_service = services.get_service_unchecked::<LotteryService>().await;
+        let context = payload.get_context(self);
+        
+        // A late event must not rewrite a final status, the rejection parks the event
+        lottery_service.cancel_lottery(lottery.id, Some(context), db_tx).await?;
+        
+        info!(
+            lottery_id = lottery.uid,
+            "Lottery cancelled",
+        );
+        
         Ok(())
     }
 }
\ No newline at end of file
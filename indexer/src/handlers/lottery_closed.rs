@@ -4,12 +4,11 @@ use crate::{
     state::StateManager,
 };
 use async_trait::async_trait;
-use entity::prelude::LotteryStatus;
 use error_stack::{Report, Result};
 use lib::error::Error;
-use service::{chain::{provider::ChainProvider, traits::string::ToHexString}, lottery::{store::LotteryStore, types::UpdateLottery, LotteryService}, store::service::{DatabaseTransaction, StoreService}};
+use service::{chain::{provider::ChainProvider, traits::string::ToHexString}, lottery::{store::LotteryStore, LotteryService}, store::service::{DatabaseTransaction, StoreService}};
 use service::services::ServiceProvider;
-use tracing::{info, warn};
+use tracing::info;
 
 #[async_trait]
 impl<Provider> Handler<LotteryClosed> for Provider
@@ -28,11 +27,6 @@ where
             "Received a new LotteryClosed event",
         );
         
-        let dto = UpdateLottery {
-            status: Some(LotteryStatus::Completed),
-            ..Default::default()
-        };
-        
         let lottery = LotteryStore::find_by_uid(
             db_tx.as_mut(), 
             payload.kind.lottery_id.to_hex_string(),
@@ -41,7 +35,8 @@ where
         let lottery_service = services.get_service_unchecked::<LotteryService>().await;
         let context = payload.get_context(self);
         
-        lottery_service.close_lottery(lottery.id, dto, Some(context), db_tx).await?;
+        // A late event must not rewrite a final status, the rejection parks the event
+        lottery_service.close_lottery(lottery.id, Some(context), db_tx).await?;
         
         info!(
             lottery_id = lottery.uid,
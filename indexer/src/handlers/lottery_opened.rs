@@ -37,7 +37,7 @@ where
         let config = self.get_config();
         let lottery_provider_address = config.contracts.get("provider").unwrap();
         
-        let lottery_data = get_lottery_data(self, *lottery_provider_address, payload.kind.lottery_id).await?;
+        let lottery_data = get_lottery_data(self, *lottery_provider_address, payload.kind.lottery_id, None).await?;
         let token_address = lottery_data.entrance_token_address;
         
         let asset = AssetStore::find_by_address(db_tx.as_mut(), token_address.to_hex_string()).await?;
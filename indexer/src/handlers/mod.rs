@@ -5,3 +5,5 @@ mod lottery_number_generated;
 mod lottery_opened;
 mod ticket_bought;
 mod winner_paid;
+
+pub(crate) use winner_paid::PaidWinner;
@@ -35,7 +35,8 @@ where
             None => {
                 let dto = CreateAccount {
                     address: payload.kind.buyer.to_hex_string(),
-                    created_at: payload.triggered_at
+                    created_at: payload.triggered_at,
+                    referral_code: None,
                 };
                 
                 let user = account_service.create_if_no_exists(dto, db_tx).await?;
@@ -66,7 +67,9 @@ where
            ticket_price: lottery.ticket_price,
            lottery_id: lottery.id,
            amount: payload.kind.tickets as i32,
-           transaction_hash: payload.transaction_hash.to_hex_string()
+           transaction_hash: payload.transaction_hash.to_hex_string(),
+           block_number: payload.block_number.as_u64() as i64,
+           log_index: payload.log_index.as_u32() as i32,
         };
         
         let ticket_service = services.get_service_unchecked::<TicketService>().await;
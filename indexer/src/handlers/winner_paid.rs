@@ -1,38 +1,69 @@
 use std::str::FromStr;
 
 use crate::{
-    events::WinnerPaid,
+    events::{LotteryNumberGenerated, WinnerPaid},
     handler::{Handler, HandlerPayload},
     state::StateManager,
 };
 use async_trait::async_trait;
 use chrono::Utc;
 use entity::{draw::DrawStatus, prize::PrizeStatus};
-use error_stack::{Report, Result};
+use error_stack::{Report, Result, ResultExt};
+use ethers::{contract::EthEvent, providers::Middleware, types::{H256, U256}};
 use lib::error::Error;
-use rust_decimal::Decimal;
+use rust_decimal::{prelude::FromPrimitive, Decimal};
 use service::{account::store::AccountStore, chain::{provider::ChainProvider, traits::string::ToHexString}, draw::{store::DrawStore, types::{CreateDraw, UpdateDraw}, DrawService}, lottery::store::LotteryStore, prize::{store::PrizeStore, types::CreatePrize, PrizeService}, store::service::{DatabaseTransaction, StoreService}, ticket::store::TicketStore};
 use service::services::ServiceProvider;
 use tracing::{info, warn};
 
+/// A `WinnerPaid` event along with the random number of its draw, read from the chain before
+/// the database transaction begins
+#[derive(Clone)]
+pub(crate) struct PaidWinner {
+    pub event: WinnerPaid,
+    pub random_number: Option<U256>,
+}
+
+impl PaidWinner {
+    /// Read the random number of the draw paid by the transaction, a failed read leaves it unknown
+    pub(crate) async fn fetch<Provider: ChainProvider>(
+        provider: &Provider,
+        event: WinnerPaid,
+        transaction_hash: H256,
+    ) -> Self {
+        let random_number = match find_random_number(provider, transaction_hash).await {
+            Ok(random_number) => random_number,
+            Err(e) => {
+                warn!(
+                    lottery_id = event.lottery_id.to_string(),
+                    "Failed to fetch random number of the draw: {e:?}",
+                );
+                None
+            }
+        };
+
+        Self { event, random_number }
+    }
+}
+
 #[async_trait]
-impl<Provider> Handler<WinnerPaid> for Provider
+impl<Provider> Handler<PaidWinner> for Provider
 where
     Provider: ChainProvider,
 {
     async fn handle(
         &self,
-        payload: HandlerPayload<WinnerPaid>,
+        payload: HandlerPayload<PaidWinner>,
         services: ServiceProvider,
         _state: StateManager,
         db_tx: &mut DatabaseTransaction<'_>,
     ) -> Result<(), Error> {
         info!(
-            lottery_id = payload.kind.lottery_id.to_string(),
-            winner = payload.kind.winner.to_string(),
+            lottery_id = payload.kind.event.lottery_id.to_string(),
+            winner = payload.kind.event.winner.to_string(),
             "Received a new WinnerPaid event",
         );
-        let winner_address = payload.kind.winner.to_hex_string();
+        let winner_address = payload.kind.event.winner.to_hex_string();
         
         let user = match AccountStore::try_find_by_address(
             db_tx.as_mut(), 
@@ -41,15 +72,15 @@ where
             Some(user) => user,
             None => {
                 warn!(
-                    lottery_id = payload.kind.lottery_id.to_string(),
-                    winner = payload.kind.winner.to_string(),
+                    lottery_id = payload.kind.event.lottery_id.to_string(),
+                    winner = payload.kind.event.winner.to_string(),
                     "Winner not found in the database",
                 );
                 return Ok(());
             }
         };
         
-        let lottery_uid = payload.kind.lottery_id.to_hex_string();
+        let lottery_uid = payload.kind.event.lottery_id.to_hex_string();
         let lottery = LotteryStore::find_by_uid(db_tx.as_mut(), lottery_uid).await?;
         
         let store_service = services.get_service_unchecked::<StoreService>().await;
@@ -60,8 +91,8 @@ where
                 Some(ticket) => ticket,
                 None => {
                     warn!(
-                        lottery_id = payload.kind.lottery_id.to_string(),
-                        winner = payload.kind.winner.to_string(),
+                        lottery_id = payload.kind.event.lottery_id.to_string(),
+                        winner = payload.kind.event.winner.to_string(),
                         "Winning ticket not found in the database",
                     );
                     return Ok(());
@@ -70,12 +101,21 @@ where
         
         let draw_service = services.get_service_unchecked::<DrawService>().await;
         
+        let sold_tickets = TicketStore::count_sold_by_lottery_id(db_tx.as_mut(), lottery.id).await?;
+        let winning_number = payload
+            .kind
+            .random_number
+            .and_then(|random_number| winning_number_from_random(random_number, sold_tickets));
+        
         let event_context = payload.get_context(self);
         let draw_dto = UpdateDraw {
             draw_date: Some(payload.triggered_at),
             status: Some(DrawStatus::Completed),
             winner: Some(winning_ticket.account_id),
-            transaction_hash: Some(payload.transaction_hash.to_hex_string())
+            transaction_hash: Some(payload.transaction_hash.to_hex_string()),
+            winning_number,
+            amount: u128::try_from(payload.kind.event.amount).ok().and_then(Decimal::from_u128),
+            ..Default::default()
         };
         
         let draw = draw_service.mark_winner_as_drawn(lottery.id, draw_dto, Some(event_context), db_tx).await?;
@@ -87,4 +127,69 @@ where
         );
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Find random number generated for the draw, which is emitted by the same transaction
+/// as a `LotteryNumberGenerated` event.
+async fn find_random_number<Provider: ChainProvider>(
+    provider: &Provider,
+    transaction_hash: H256,
+) -> Result<Option<U256>, Error> {
+    let client = provider.get_client()?;
+    let receipt = client
+        .get_transaction_receipt(transaction_hash)
+        .await
+        .change_context(Error::ContractQuery)?;
+
+    let Some(receipt) = receipt else {
+        return Ok(None);
+    };
+
+    let random_number = receipt
+        .logs
+        .into_iter()
+        .filter(|log| log.topics.first() == Some(&LotteryNumberGenerated::signature()))
+        .find_map(|log| LotteryNumberGenerated::decode_log(&log.into()).ok())
+        .map(|event| event.r_number);
+
+    Ok(random_number)
+}
+
+/// Map random number to a ticket number, tickets are numbered starting from 1.
+///
+/// Neither `WinnerPaid` nor the contract storage expose the winning number, and only the
+/// contract ABI is vendored, so this assumes the contract picks the ticket at index
+/// `random_number % sold_tickets`. The number is only kept when its ticket belongs to the
+/// winner paid by the contract, see `DrawService::mark_winner_as_drawn`.
+fn winning_number_from_random(random_number: U256, sold_tickets: i64) -> Option<i32> {
+    if sold_tickets <= 0 {
+        return None;
+    }
+
+    let index = random_number % U256::from(sold_tickets);
+    Some(index.as_u64() as i32 + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_winning_number_from_random() {
+        assert_eq!(winning_number_from_random(U256::from(0), 10), Some(1));
+        assert_eq!(winning_number_from_random(U256::from(9), 10), Some(10));
+        assert_eq!(winning_number_from_random(U256::from(25), 10), Some(6));
+        assert_eq!(winning_number_from_random(U256::MAX, 1), Some(1));
+        assert_eq!(winning_number_from_random(U256::from(5), 0), None);
+    }
+
+    #[test]
+    fn test_winning_number_from_random_stays_in_range() {
+        for sold_tickets in 1..50 {
+            for random_number in [0u64, 1, 7, 1_000, u64::MAX] {
+                let number = winning_number_from_random(U256::from(random_number), sold_tickets).unwrap();
+                assert!((1..=sold_tickets as i32).contains(&number));
+            }
+        }
+    }
+}
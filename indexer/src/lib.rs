@@ -4,6 +4,7 @@ mod handler;
 mod handlers;
 mod state;
 mod stream;
+mod tasks;
 
 use crate::stream::{StreamProvider, StreamProviderResult};
 use error_stack::Result;
@@ -13,6 +14,7 @@ use service::cache::service::CacheService;
 use service::chain::Chain;
 use service::common::shutdown::{spawn_ctrl_c_listener, ShutdownFlag};
 use service::config::service::{ChainConfig, ConfigService};
+use service::message_broker::relay;
 use service::services::ServiceProvider;
 use service::store::service::StoreService;
 use tracing::warn;
@@ -54,9 +56,16 @@ pub async fn start(
         return Ok(());
     }
 
-    if with_tasks {
+    let mut tasks = if with_tasks {
         info!("Starting tasks");
-    }
+        tasks::start_tasks(configs.clone(), services.clone(), shutdown.clone())
+    } else {
+        Vec::new()
+    };
+
+    // Messages written by the handlers are only published by the relay
+    tasks.push(relay::spawn(services.clone(), shutdown.clone()));
+    tasks.push(tasks::leaderboard::spawn(services.clone(), shutdown.clone()));
 
     let chain_tasks = start_chains(configs, services.clone(), shutdown.clone());
 
@@ -71,9 +80,45 @@ pub async fn start(
         Err(e) => error!(reason = ?e, "Error while starting chains"),
     }
 
+    // Tasks are only meaningful while chains are being indexed, pending outbox
+    // messages are published on the next start
+    for task in tasks {
+        task.abort();
+    }
+
     Ok(())
 }
 
+/// Reprocess the raw logs archived for the provided chains, or all chains if none provided.
+///
+/// Returns the number of logs that were successfully decoded and handled.
+pub async fn reprocess_raw_logs(
+    config: ConfigService,
+    chains: Option<Vec<String>>,
+) -> Result<usize, Error> {
+    let services = ServiceProvider::new();
+    let config = services.add_service(config).await;
+
+    services.warm_up::<StoreService>().await;
+    services.warm_up::<CacheService>().await;
+
+    let mut reprocessed = 0;
+    for chain_config in config.chains.iter().filter(|chain| {
+        chains
+            .as_ref()
+            .map(|i| i.contains(&chain.name))
+            .unwrap_or(true)
+    }) {
+        let chain = Chain::from((chain_config.clone(), services.clone()));
+        let count = chain::reprocess_raw_logs(&chain).await?;
+
+        info!(chain = chain_config.name, count, "Raw logs reprocessed");
+        reprocessed += count;
+    }
+
+    Ok(reprocessed)
+}
+
 /// Initialize chain for each provided config, and start them.
 fn start_chains(
     config: Vec<ChainConfig>,
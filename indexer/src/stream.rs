@@ -180,7 +180,7 @@ where
     T: Transformer<S>,
     V: Validator<S>,
 {
-    type Item = ChainEvent;
+    type Item = std::result::Result<ChainEvent, RejectedEvent<S::Item>>;
 
     fn poll_next(
         mut self: std::pin::Pin<&mut Self>,
@@ -207,10 +207,14 @@ where
 
             match input {
                 ChannelEvent::Event(event) => match T::transform(event.clone()) {
-                    Ok(event) => return std::task::Poll::Ready(Some(event)),
+                    Ok(event) => return std::task::Poll::Ready(Some(Ok(event))),
                     Err(e) => {
                         warn!(reason = %e, payload = ?event, "Failed to transform event");
-                        continue;
+                        let rejected = RejectedEvent {
+                            item: event,
+                            reason: format!("{e:#}"),
+                        };
+                        return std::task::Poll::Ready(Some(Err(rejected)));
                     }
                 },
                 // The `UnboundedReceiver` pauses in a `Poll::Pending` state, awaiting new messages.
@@ -289,6 +293,13 @@ pub struct ChainEvent {
     pub triggered_at: DateTime<Utc>,
 }
 
+/// Subscription item the transformer could not turn into a [`ChainEvent`]
+#[derive(Debug, Clone)]
+pub struct RejectedEvent<T> {
+    pub item: T,
+    pub reason: String,
+}
+
 // TODO: Provide more details about each event
 #[derive(Debug, Clone)]
 pub enum ChainEventKind {
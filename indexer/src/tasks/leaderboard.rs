@@ -0,0 +1,83 @@
+use error_stack::Result;
+use futures::stream::{select_all, StreamExt};
+use lib::error::Error;
+use service::common::shutdown::{await_shutdown_signal, ShutdownFlag};
+use service::leaderboard::LeaderboardService;
+use service::message_broker::{channels, Delivery, MessageBrokerService};
+use service::services::ServiceProvider;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn, Instrument};
+
+/// Delay before subscribing again once the broker failed
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Consumer group of the leaderboard updates, every event is applied by one indexer
+const CONSUMER_GROUP: &str = "leaderboard";
+
+/// Apply the ticket and draw events to the leaderboards until shutdown.
+pub fn spawn(services: ServiceProvider, shutdown: ShutdownFlag) -> JoinHandle<()> {
+    let span = tracing::info_span!("leaderboard");
+
+    tokio::spawn(
+        async move {
+            let leaderboard_service = services.get_service_unchecked::<LeaderboardService>().await;
+            let message_broker = services.get_service_unchecked::<MessageBrokerService>().await;
+
+            info!("Starting leaderboard updates");
+
+            loop {
+                tokio::select! {
+                    _ = await_shutdown_signal(shutdown.clone()) => break,
+                    result = follow_events(&leaderboard_service, &message_broker) => {
+                        match result {
+                            Ok(()) => info!("Resuming leaderboard updates from the last applied events"),
+                            Err(e) if matches!(e.current_context(), Error::LeaderboardRebuilding) => {
+                                info!("Leaderboards are being rebuilt, pausing updates");
+                                tokio::time::sleep(RETRY_DELAY).await;
+                            }
+                            Err(e) => {
+                                error!(reason = ?e, "Failed to update leaderboards");
+                                tokio::time::sleep(RETRY_DELAY).await;
+                            }
+                        }
+                    }
+                }
+            }
+
+            info!("Leaderboard updates stopped");
+        }
+        .instrument(span),
+    )
+}
+
+/// Apply the events published after the last applied ones, returns once lagging behind
+async fn follow_events(
+    leaderboard_service: &LeaderboardService,
+    message_broker: &MessageBrokerService,
+) -> Result<(), Error> {
+    let ticket_cursor = leaderboard_service.cursor(channels::TICKET_BOUGHT.name()).await?;
+    let draw_cursor = leaderboard_service.cursor(channels::WINNER_DRAWN.name()).await?;
+
+    let streams = vec![
+        message_broker.subscribe_group(CONSUMER_GROUP, channels::TICKET_BOUGHT, ticket_cursor).await?.boxed(),
+        message_broker.subscribe_group(CONSUMER_GROUP, channels::WINNER_DRAWN, draw_cursor).await?.boxed(),
+    ];
+
+    let mut deliveries = select_all(streams);
+
+    while let Some(delivery) = deliveries.next().await {
+        match delivery {
+            Delivery::Event(event) => {
+                leaderboard_service.apply(&event).await?;
+                message_broker.ack(CONSUMER_GROUP, &event).await;
+            }
+            Delivery::Lagged => {
+                warn!("Leaderboard updates lagged behind");
+                return Ok(());
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,23 @@
+pub(crate) mod leaderboard;
+mod reconciliation;
+
+use service::chain::Chain;
+use service::common::shutdown::ShutdownFlag;
+use service::config::service::ChainConfig;
+use service::services::ServiceProvider;
+use tokio::task::JoinHandle;
+
+/// Spawn periodic tasks for each provided chain config.
+pub fn start_tasks(
+    configs: Vec<ChainConfig>,
+    services: ServiceProvider,
+    shutdown: ShutdownFlag,
+) -> Vec<JoinHandle<()>> {
+    configs
+        .into_iter()
+        .map(|config| {
+            let chain = Chain::from((config, services.clone()));
+            reconciliation::spawn(chain, shutdown.clone())
+        })
+        .collect()
+}
@@ -0,0 +1,43 @@
+use service::chain::Chain;
+use service::common::shutdown::{await_shutdown_signal, ShutdownFlag};
+use service::reconciliation::ReconciliationService;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info, Instrument};
+
+/// Periodically reconcile indexed lotteries with the chain until shutdown.
+pub fn spawn(chain: Chain, shutdown: ShutdownFlag) -> JoinHandle<()> {
+    let span = tracing::info_span!("reconciliation", chain = chain.config.name.as_str());
+
+    tokio::spawn(
+        async move {
+            let config = chain.config.reconciliation.clone();
+            let reconciliation_service = chain
+                .services
+                .get_service_unchecked::<ReconciliationService>()
+                .await;
+
+            info!(
+                interval = config.interval,
+                auto_repair = config.auto_repair,
+                "Starting reconciliation task"
+            );
+
+            let mut interval = tokio::time::interval(Duration::from_secs(config.interval.max(1)));
+
+            loop {
+                tokio::select! {
+                    _ = await_shutdown_signal(shutdown.clone()) => break,
+                    _ = interval.tick() => {
+                        if let Err(e) = reconciliation_service.reconcile(&chain, &config).await {
+                            error!(reason = ?e, "Failed to reconcile lotteries");
+                        }
+                    }
+                }
+            }
+
+            info!("Reconciliation task stopped");
+        }
+        .instrument(span),
+    )
+}
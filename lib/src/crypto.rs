@@ -1,9 +1,12 @@
+use std::iter::Peekable;
 use std::str::FromStr;
 
-use error_stack::{Result, ResultExt};
+use chrono::{DateTime, FixedOffset, Utc};
+use error_stack::{Report, Result, ResultExt};
 use ethers::{
     abi::Address,
     types::{RecoveryMessage, Signature, H160},
+    utils::to_checksum,
 };
 use serde::Serialize;
 
@@ -26,3 +29,283 @@ where
     let recovered_address = recover_address(&message, signature)?;
     Ok(recovered_address.eq(address))
 }
+
+const SIWE_PREAMBLE: &str = " wants you to sign in with your Ethereum account:";
+const SIWE_VERSION: &str = "1";
+const SIWE_NONCE_MIN_LENGTH: usize = 8;
+
+/// Sign-In with Ethereum message, as specified by EIP-4361
+#[derive(Clone, Debug, PartialEq)]
+pub struct SiweMessage {
+    pub scheme: Option<String>,
+    pub domain: String,
+    pub address: Address,
+    pub statement: Option<String>,
+    pub uri: String,
+    pub version: String,
+    pub chain_id: u64,
+    pub nonce: String,
+    pub issued_at: DateTime<FixedOffset>,
+    pub expiration_time: Option<DateTime<FixedOffset>>,
+    pub not_before: Option<DateTime<FixedOffset>>,
+    pub request_id: Option<String>,
+    pub resources: Vec<String>,
+}
+
+/// Value of the `{name}: {value}` line, failing when the line holds another field
+fn siwe_field<'a>(line: Option<&'a str>, name: &str) -> Result<&'a str, Error> {
+    line.and_then(|line| line.strip_prefix(name))
+        .and_then(|line| line.strip_prefix(": "))
+        .ok_or(Report::from(Error::SiweInvalidMessage))
+        .attach_printable_lazy(|| format!("Missing `{name}` field"))
+}
+
+/// Value of the optional `{name}: {value}` line, consuming the line only when it holds the field
+fn siwe_optional_field<'a, I>(lines: &mut Peekable<I>, name: &str) -> Option<&'a str>
+where
+    I: Iterator<Item = &'a str>,
+{
+    let value = lines
+        .peek()
+        .and_then(|line| line.strip_prefix(name))
+        .and_then(|line| line.strip_prefix(": "))?;
+
+    lines.next();
+    Some(value)
+}
+
+fn siwe_datetime(value: &str, name: &str) -> Result<DateTime<FixedOffset>, Error> {
+    DateTime::parse_from_rfc3339(value)
+        .change_context(Error::SiweInvalidMessage)
+        .attach_printable_lazy(|| format!("Invalid `{name}` timestamp"))
+}
+
+impl FromStr for SiweMessage {
+    type Err = Report<Error>;
+
+    fn from_str(message: &str) -> std::result::Result<Self, Self::Err> {
+        let mut lines = message.split('\n').peekable();
+
+        let origin = lines
+            .next()
+            .and_then(|line| line.strip_suffix(SIWE_PREAMBLE))
+            .ok_or(Report::from(Error::SiweInvalidMessage))
+            .attach_printable("Missing preamble")?;
+        let (scheme, domain) = match origin.split_once("://") {
+            Some((scheme, domain)) => (Some(scheme.to_string()), domain.to_string()),
+            None => (None, origin.to_string()),
+        };
+        if domain.is_empty() {
+            return Err(Report::from(Error::SiweInvalidMessage).attach_printable("Missing domain"));
+        }
+
+        // Address must be EIP-55 checksummed
+        let address = lines.next().unwrap_or_default();
+        let parsed_address = Address::from_str(address)
+            .change_context(Error::SiweInvalidMessage)
+            .attach_printable("Invalid address")?;
+        if to_checksum(&parsed_address, None) != address {
+            return Err(Report::from(Error::SiweInvalidMessage)
+                .attach_printable("Address is not EIP-55 checksummed"));
+        }
+
+        if lines.next() != Some("") {
+            return Err(Report::from(Error::SiweInvalidMessage).attach_printable("Missing empty line after address"));
+        }
+
+        // Statement is followed by an empty line, without statement the empty line is alone
+        let statement = match lines.next() {
+            Some("") => None,
+            Some(statement) => {
+                if lines.next() != Some("") {
+                    return Err(Report::from(Error::SiweInvalidMessage)
+                        .attach_printable("Missing empty line after statement"));
+                }
+                Some(statement.to_string())
+            }
+            None => {
+                return Err(Report::from(Error::SiweInvalidMessage).attach_printable("Missing URI"))
+            }
+        };
+
+        let uri = siwe_field(lines.next(), "URI")?.to_string();
+        let version = siwe_field(lines.next(), "Version")?.to_string();
+        if version != SIWE_VERSION {
+            return Err(Report::from(Error::SiweInvalidMessage).attach_printable("Unsupported version"));
+        }
+
+        let chain_id = siwe_field(lines.next(), "Chain ID")?
+            .parse::<u64>()
+            .change_context(Error::SiweInvalidMessage)
+            .attach_printable("Invalid chain id")?;
+
+        let nonce = siwe_field(lines.next(), "Nonce")?.to_string();
+        if nonce.len() < SIWE_NONCE_MIN_LENGTH || !nonce.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(Report::from(Error::SiweInvalidMessage).attach_printable("Invalid nonce"));
+        }
+
+        let issued_at = siwe_datetime(siwe_field(lines.next(), "Issued At")?, "Issued At")?;
+        let expiration_time = siwe_optional_field(&mut lines, "Expiration Time")
+            .map(|value| siwe_datetime(value, "Expiration Time"))
+            .transpose()?;
+        let not_before = siwe_optional_field(&mut lines, "Not Before")
+            .map(|value| siwe_datetime(value, "Not Before"))
+            .transpose()?;
+        let request_id = siwe_optional_field(&mut lines, "Request ID").map(str::to_string);
+
+        let mut resources = Vec::new();
+        if lines.peek() == Some(&"Resources:") {
+            lines.next();
+            while let Some(resource) = lines.peek().and_then(|line| line.strip_prefix("- ")) {
+                resources.push(resource.to_string());
+                lines.next();
+            }
+        }
+
+        if lines.next().is_some() {
+            return Err(Report::from(Error::SiweInvalidMessage).attach_printable("Unexpected trailing content"));
+        }
+
+        Ok(SiweMessage {
+            scheme,
+            domain,
+            address: parsed_address,
+            statement,
+            uri,
+            version,
+            chain_id,
+            nonce,
+            issued_at,
+            expiration_time,
+            not_before,
+            request_id,
+            resources,
+        })
+    }
+}
+
+impl SiweMessage {
+    /// Check the message was issued for this service and is valid at `now`.
+    ///
+    /// The URI must be the expected one or one of its sub-paths.
+    pub fn validate(
+        &self,
+        domain: &str,
+        uri: &str,
+        chain_ids: &[u64],
+        now: DateTime<Utc>,
+    ) -> Result<(), Error> {
+        if self.domain != domain {
+            return Err(Report::from(Error::SiweInvalidDomain)
+                .attach_printable(format!("Expected {domain}, got {}", self.domain)));
+        }
+
+        let uri = uri.trim_end_matches('/');
+        let uri_matches = self
+            .uri
+            .strip_prefix(uri)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'));
+        if !uri_matches {
+            return Err(Report::from(Error::SiweInvalidUri)
+                .attach_printable(format!("Expected {uri}, got {}", self.uri)));
+        }
+
+        if !chain_ids.contains(&self.chain_id) {
+            return Err(Report::from(Error::SiweInvalidChain)
+                .attach_printable(format!("Chain {} is not supported", self.chain_id)));
+        }
+
+        if self.expiration_time.is_some_and(|expiration_time| now >= expiration_time) {
+            return Err(Report::from(Error::SiweExpired));
+        }
+
+        if self.not_before.is_some_and(|not_before| now < not_before) {
+            return Err(Report::from(Error::SiweNotYetValid));
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse the Sign-In with Ethereum message and check it was signed by its address.
+///
+/// The signature is checked against the message as sent, its fields are not re-serialized.
+pub fn verify_siwe_message(message: &str, signature: &str) -> Result<SiweMessage, Error> {
+    let siwe = SiweMessage::from_str(message)?;
+    let recovered_address = recover_address(message, signature)?;
+
+    if recovered_address != siwe.address {
+        return Err(Report::from(Error::InvalidSignature)
+            .attach_printable("Message was not signed by its address"));
+    }
+
+    Ok(siwe)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::signers::{LocalWallet, Signer};
+    use ethers::utils::hash_message;
+
+    fn message(address: Address, expiration_time: &str) -> String {
+        format!(
+            "app.example.com wants you to sign in with your Ethereum account:\n\
+             {}\n\
+             \n\
+             Sign in to the lottery.\n\
+             \n\
+             URI: https://app.example.com/login\n\
+             Version: 1\n\
+             Chain ID: 1\n\
+             Nonce: 32891756ab\n\
+             Issued At: 2021-09-30T16:25:24Z\n\
+             Expiration Time: {expiration_time}\n\
+             Resources:\n\
+             - ipfs://bafybeiemxf5abjwjbikoz4mc3a3dla6ual3jsgpdr4cjr3oz3evfyavhwq/",
+            to_checksum(&address, None)
+        )
+    }
+
+    #[test]
+    fn test_verify_siwe_message() {
+        let wallet = LocalWallet::from_str(
+            "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318",
+        )
+        .unwrap();
+        let message = message(wallet.address(), "2021-10-01T16:25:24.000Z");
+        let signature = wallet.sign_hash(hash_message(&message)).unwrap().to_string();
+
+        let siwe = verify_siwe_message(&message, &signature).unwrap();
+        assert_eq!(siwe.address, wallet.address());
+        assert_eq!(siwe.statement.as_deref(), Some("Sign in to the lottery."));
+        assert_eq!(siwe.nonce, "32891756ab");
+        assert_eq!(siwe.resources.len(), 1);
+
+        let now = DateTime::parse_from_rfc3339("2021-09-30T17:00:00Z").unwrap().to_utc();
+        siwe.validate("app.example.com", "https://app.example.com/", &[1], now).unwrap();
+
+        let later = DateTime::parse_from_rfc3339("2021-10-02T00:00:00Z").unwrap().to_utc();
+        assert!(siwe.validate("app.example.com", "https://app.example.com", &[1], later).is_err());
+        assert!(siwe.validate("evil.example.com", "https://app.example.com", &[1], now).is_err());
+        assert!(siwe.validate("app.example.com", "https://app.example", &[1], now).is_err());
+        assert!(siwe.validate("app.example.com", "https://app.example.com", &[137], now).is_err());
+
+        // Signature does not cover a tampered message
+        let tampered = message.replace("2021-10-01T16:25:24.000Z", "2031-10-01T16:25:24.000Z");
+        assert!(verify_siwe_message(&tampered, &signature).is_err());
+    }
+
+    #[test]
+    fn test_parse_siwe_message_without_statement() {
+        let message = message(Address::zero(), "2021-10-01T16:25:24Z")
+            .replace("Sign in to the lottery.\n", "");
+
+        let siwe = SiweMessage::from_str(&message).unwrap();
+        assert_eq!(siwe.statement, None);
+
+        assert!(SiweMessage::from_str(&message.to_lowercase()).is_err());
+        assert!(SiweMessage::from_str(&message.replace("Nonce: 32891756ab", "Nonce: 1")).is_err());
+        assert!(SiweMessage::from_str(&format!("{message}\n")).is_err());
+    }
+}
@@ -8,6 +8,24 @@ pub enum Error {
 
     #[error("Ticket invalid fee")]
     TicketServiceInvalidFee,
+
+    #[error("Prize value must be positive")]
+    PrizeServiceInvalidValue,
+
+    #[error("Prize asset is unknown")]
+    PrizeServiceUnknownAsset,
+
+    #[error("Lottery tags must be at most 10 labels made of letters, digits and dashes")]
+    LotteryInvalidTag,
+
+    #[error("Lottery is not featured")]
+    LotteryNotFeatured,
+
+    #[error("Lottery is listed more than once")]
+    LotteryDuplicated,
+
+    #[error("Lottery status transition is not allowed")]
+    LotteryInvalidStatusTransition,
     
     #[error("Stream error")]
     Stream,
@@ -69,6 +87,54 @@ pub enum Error {
     #[error("Invalid signature")]
     InvalidSignature,
 
+    #[error("Invalid Sign-In with Ethereum message")]
+    SiweInvalidMessage,
+
+    #[error("Sign-In with Ethereum message was issued for another domain")]
+    SiweInvalidDomain,
+
+    #[error("Sign-In with Ethereum message was issued for another URI")]
+    SiweInvalidUri,
+
+    #[error("Sign-In with Ethereum message was issued for an unsupported chain")]
+    SiweInvalidChain,
+
+    #[error("Sign-In with Ethereum message has expired")]
+    SiweExpired,
+
+    #[error("Sign-In with Ethereum message is not valid yet")]
+    SiweNotYetValid,
+
+    #[error("Sign-In with Ethereum nonce is unknown or already used")]
+    SiweInvalidNonce,
+
+    #[error("Session is invalid, revoked or expired")]
+    SessionInvalid,
+
+    #[error("Invalid address")]
+    InvalidAddress,
+
+    #[error("An admin account already exists")]
+    AccountAdminAlreadyExists,
+
+    #[error("The last admin account can't be demoted")]
+    AccountLastAdmin,
+
+    #[error("Referral code is already taken")]
+    ReferralCodeTaken,
+
+    #[error("Invalid cursor")]
+    InvalidCursor,
+
+    #[error("Leaderboard amounts can only be ranked within an asset")]
+    LeaderboardAssetRequired,
+
+    #[error("Leaderboards are being rebuilt")]
+    LeaderboardRebuilding,
+
+    #[error("Leaderboard events are still being published")]
+    LeaderboardEventsPending,
+
     #[error("Failed to serialize provided object")]
     SerdeSerialize,
 
@@ -0,0 +1,44 @@
+ALTER TABLE ticket ADD COLUMN block_number BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE ticket ADD COLUMN log_index INT NOT NULL DEFAULT 0;
+ALTER TABLE ticket ADD COLUMN first_number INT NOT NULL DEFAULT 0;
+ALTER TABLE ticket ADD COLUMN last_number INT NOT NULL DEFAULT 0;
+
+-- Recover on-chain position of already indexed purchases from the transaction log.
+-- Purchases of a transaction were indexed in log order, the n-th one is matched with the n-th log
+UPDATE ticket
+SET block_number = purchase_log.block_number,
+    log_index = purchase_log.log_index
+FROM (
+    SELECT purchase.id, log.block_number, log.log_index
+    FROM (
+        SELECT id, transaction_hash,
+            ROW_NUMBER() OVER (PARTITION BY transaction_hash ORDER BY created_at, id) AS position
+        FROM ticket
+    ) purchase
+    JOIN (
+        SELECT transaction_hash, block_number, log_index,
+            ROW_NUMBER() OVER (PARTITION BY transaction_hash ORDER BY log_index) AS position,
+            COUNT(*) OVER (PARTITION BY transaction_hash) AS logs
+        FROM transaction_log
+    ) log ON log.transaction_hash = purchase.transaction_hash
+        AND log.position = LEAST(purchase.position, log.logs)
+) purchase_log
+WHERE purchase_log.id = ticket.id;
+
+-- Assign contiguous ticket ranges per lottery in purchase order
+UPDATE ticket
+SET first_number = numbered.last_number - ticket.amount + 1,
+    last_number = numbered.last_number
+FROM (
+    SELECT id, SUM(amount) OVER (
+        PARTITION BY lottery_id
+        ORDER BY block_number, log_index, purchased_at, created_at, id
+    ) AS last_number
+    FROM ticket
+) numbered
+WHERE numbered.id = ticket.id;
+
+CREATE INDEX idx_ticket_lottery_id_numbers ON ticket(lottery_id, first_number, last_number);
+
+ALTER TABLE draw ADD COLUMN winning_number INT;
+ALTER TABLE draw ADD COLUMN winning_ticket UUID REFERENCES ticket(id);
@@ -0,0 +1,14 @@
+CREATE TABLE reconciliation_report (
+    id UUID PRIMARY KEY,
+    chain TEXT NOT NULL,
+    lottery_id UUID NOT NULL REFERENCES lottery(id),
+    account_id UUID REFERENCES account(id),
+    kind VARCHAR(40) NOT NULL,
+    expected TEXT NOT NULL,
+    actual TEXT NOT NULL,
+    repaired BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_reconciliation_report_lottery_id ON reconciliation_report(lottery_id);
+CREATE INDEX idx_reconciliation_report_created_at ON reconciliation_report(created_at);
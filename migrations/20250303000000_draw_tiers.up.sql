@@ -0,0 +1,9 @@
+-- A lottery may pay out several places, one draw row per winner
+DROP INDEX idx_draw_lottery_id;
+
+ALTER TABLE draw ADD COLUMN tier INT NOT NULL DEFAULT 1;
+ALTER TABLE draw ADD COLUMN amount DECIMAL;
+ALTER TABLE draw ADD COLUMN share DECIMAL;
+
+CREATE INDEX idx_draw_lottery_id ON draw(lottery_id);
+CREATE UNIQUE INDEX idx_draw_lottery_id_tier ON draw(lottery_id, tier);
@@ -0,0 +1,8 @@
+-- A lottery prize is made of the ticket-funded pool plus sponsor contributions
+DROP INDEX idx_prize_lottery_id;
+
+ALTER TABLE prize ADD COLUMN source VARCHAR(20) NOT NULL DEFAULT 'TICKETS';
+ALTER TABLE prize ADD COLUMN sponsor UUID REFERENCES account(id);
+
+CREATE INDEX idx_prize_lottery_id ON prize(lottery_id);
+CREATE UNIQUE INDEX idx_prize_lottery_id_tickets ON prize(lottery_id) WHERE source = 'TICKETS';
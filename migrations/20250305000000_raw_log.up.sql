@@ -0,0 +1,17 @@
+CREATE TABLE raw_log (
+    id UUID PRIMARY KEY,
+    chain TEXT NOT NULL,
+    address TEXT NOT NULL,
+    topics TEXT[] NOT NULL,
+    data TEXT NOT NULL,
+    block_number BIGINT,
+    transaction_hash TEXT,
+    log_index INT,
+    reason TEXT NOT NULL,
+    reprocessed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX idx_raw_log_unique ON raw_log (transaction_hash, log_index);
+CREATE INDEX idx_raw_log_pending ON raw_log (chain, block_number, log_index) WHERE reprocessed_at IS NULL;
@@ -0,0 +1,17 @@
+CREATE TABLE lottery_status_history (
+    id UUID PRIMARY KEY,
+    lottery_id UUID NOT NULL REFERENCES lottery(id),
+    from_status VARCHAR(20),
+    to_status VARCHAR(20) NOT NULL,
+    chain TEXT,
+    block_number BIGINT,
+    transaction_hash TEXT,
+    log_index INTEGER,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_lottery_status_history_lottery_id ON lottery_status_history(lottery_id, created_at);
+
+-- Lotteries indexed before the history existed start from their current status
+INSERT INTO lottery_status_history (id, lottery_id, from_status, to_status, created_at)
+SELECT gen_random_uuid(), id, NULL, status, created_at FROM lottery;
@@ -0,0 +1,8 @@
+CREATE TABLE broker_event (
+    id BIGSERIAL PRIMARY KEY,
+    channel TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_broker_event_channel ON broker_event (channel, id);
@@ -0,0 +1,17 @@
+CREATE TABLE outbox (
+    id UUID PRIMARY KEY,
+    channel TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    dedup_key TEXT NOT NULL,
+    attempts INT NOT NULL DEFAULT 0,
+    last_error TEXT,
+    published_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX idx_outbox_dedup_key ON outbox (dedup_key);
+CREATE INDEX idx_outbox_pending ON outbox (created_at) WHERE published_at IS NULL;
+
+ALTER TABLE broker_event ADD COLUMN dedup_key TEXT;
+
+CREATE UNIQUE INDEX idx_broker_event_dedup_key ON broker_event (dedup_key);
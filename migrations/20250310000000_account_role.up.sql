@@ -0,0 +1,3 @@
+ALTER TABLE account ADD COLUMN role VARCHAR(20) NOT NULL DEFAULT 'PLAYER';
+
+CREATE INDEX idx_account_role ON account (role) WHERE role <> 'PLAYER';
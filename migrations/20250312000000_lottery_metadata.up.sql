@@ -0,0 +1,6 @@
+ALTER TABLE lottery
+    ADD COLUMN category VARCHAR(20),
+    ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX idx_lottery_category ON lottery (category);
+CREATE INDEX idx_lottery_tags ON lottery USING GIN (tags);
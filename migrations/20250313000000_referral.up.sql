@@ -0,0 +1,20 @@
+ALTER TABLE account
+    ADD COLUMN referral_code VARCHAR(16) UNIQUE,
+    ADD COLUMN referrer_id UUID REFERENCES account (id);
+
+CREATE INDEX idx_account_referrer_id ON account (referrer_id);
+
+-- Reward accrued to the referrer of the buyer, once per purchase
+CREATE TABLE referral_reward (
+    id UUID PRIMARY KEY,
+    referrer_id UUID NOT NULL REFERENCES account (id),
+    referred_id UUID NOT NULL REFERENCES account (id),
+    ticket_id UUID NOT NULL UNIQUE REFERENCES ticket (id),
+    asset_id UUID NOT NULL REFERENCES asset (id),
+    spent DECIMAL NOT NULL,
+    rate_bps INT NOT NULL,
+    amount DECIMAL NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_referral_reward_referrer_id ON referral_reward (referrer_id);
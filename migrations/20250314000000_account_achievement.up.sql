@@ -0,0 +1,9 @@
+-- Achievements unlocked by accounts, rules are declared in the config
+CREATE TABLE account_achievement (
+    id UUID PRIMARY KEY,
+    account_id UUID NOT NULL REFERENCES account (id),
+    achievement VARCHAR(40) NOT NULL,
+    context JSONB,
+    unlocked_at TIMESTAMPTZ NOT NULL,
+    UNIQUE (account_id, achievement)
+);
@@ -0,0 +1,4 @@
+-- Payouts are recorded once per event, replaying one must not open another place
+ALTER TABLE draw ADD COLUMN log_index INT;
+
+CREATE UNIQUE INDEX idx_draw_lottery_id_transaction_hash_log_index ON draw(lottery_id, transaction_hash, log_index);
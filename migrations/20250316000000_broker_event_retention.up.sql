@@ -0,0 +1 @@
+CREATE INDEX idx_broker_event_created_at ON broker_event (created_at);
@@ -0,0 +1,3 @@
+ALTER TABLE outbox ADD COLUMN claimed_until TIMESTAMPTZ;
+
+CREATE INDEX idx_outbox_published_at ON outbox (published_at) WHERE published_at IS NOT NULL;
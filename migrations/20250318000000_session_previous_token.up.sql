@@ -0,0 +1 @@
+ALTER TABLE session ADD COLUMN previous_refresh_token_hash TEXT;
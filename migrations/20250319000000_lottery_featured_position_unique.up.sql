@@ -0,0 +1,10 @@
+UPDATE lottery
+SET featured_position = ranked.position
+FROM (
+    SELECT id, ROW_NUMBER() OVER (ORDER BY featured_position ASC, created_at ASC) AS position
+    FROM lottery
+    WHERE featured_position IS NOT NULL
+) ranked
+WHERE lottery.id = ranked.id;
+
+CREATE UNIQUE INDEX idx_lottery_featured_position ON lottery (featured_position) WHERE featured_position IS NOT NULL;
@@ -0,0 +1 @@
+CREATE INDEX idx_draw_winner ON draw(winner);
@@ -0,0 +1,7 @@
+CREATE TABLE broker_event_watermark (
+    id INT PRIMARY KEY CHECK (id = 1),
+    pruned_id BIGINT NOT NULL
+);
+
+INSERT INTO broker_event_watermark (id, pruned_id)
+SELECT 1, MIN(id) - 1 FROM broker_event HAVING MIN(id) > 1;
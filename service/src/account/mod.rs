@@ -8,13 +8,22 @@ use self::types::{CreateAccount, UpdateAccount};
 use crate::prelude::*;
 use crate::services::{ServiceFactory, ServiceProvider};
 use crate::store::service::DatabaseTransaction;
-use entity::account::{AccountModel};
-use error_stack::{Report, Result};
+use chrono::Utc;
+use entity::account::{AccountModel, AccountRole};
+use error_stack::{Report, Result, ResultExt};
+use ethers::types::Address;
 use lib::error::Error;
 use serenity::async_trait;
+use std::str::FromStr;
 use std::sync::Arc;
+use tracing::warn;
 use uuid::Uuid;
 
+/// Whether taking the admin role from the account leaves no admin
+fn demotes_last_admin(admin_ids: &[Uuid], id: Uuid) -> bool {
+    admin_ids == [id]
+}
+
 pub struct AccountService {
     store: Arc<StoreService>,
 }
@@ -24,6 +33,10 @@ impl AccountService {
         Self { store}
     }
 
+    /// Create the account on first sight of the address.
+    ///
+    /// Accounts created without a referral code, e.g. by an indexed purchase, are referred on
+    /// the first login claiming one.
     pub async fn create_if_no_exists(
         &self,
         dto: CreateAccount,
@@ -32,7 +45,16 @@ impl AccountService {
         let conn = self.store.read();
         let account = AccountStore::try_find_by_address(conn, dto.address.clone()).await?;
         if let Some(account) = account {
-            return Ok(account);
+            let Some(code) = dto.referral_code.filter(|_| account.referrer_id.is_none()) else {
+                return Ok(account);
+            };
+
+            return match self.find_referrer(&code, db_tx).await? {
+                Some(referrer_id) if referrer_id != account.id => {
+                    AccountStore::set_referrer(db_tx.as_mut(), account.id, referrer_id).await
+                }
+                _ => Ok(account),
+            };
         }
 
         let account = self.create(dto, db_tx).await?;
@@ -40,16 +62,36 @@ impl AccountService {
         Ok(account)
     }
 
+    /// Create the account, referred by the owner of the claimed referral code if any
     async fn create(
         &self,
         dto: CreateAccount,
         db_tx: &mut DatabaseTransaction<'_>,
     ) -> Result<AccountModel, Error> {
-        let account = AccountStore::create(db_tx.as_mut(), dto.clone()).await?;
+        let referrer_id = match dto.referral_code.as_deref() {
+            Some(code) => self.find_referrer(code, db_tx).await?,
+            None => None,
+        };
+
+        let account = AccountStore::create(db_tx.as_mut(), dto.clone(), referrer_id).await?;
 
         Ok(account)
     }
 
+    /// Owner of the referral code, an unknown code doesn't prevent logging in
+    async fn find_referrer(
+        &self,
+        code: &str,
+        db_tx: &mut DatabaseTransaction<'_>,
+    ) -> Result<Option<Uuid>, Error> {
+        let referrer = AccountStore::try_find_by_referral_code(db_tx.as_mut(), code.to_string()).await?;
+        if referrer.is_none() {
+            warn!("Ignoring unknown referral code {code}");
+        }
+
+        Ok(referrer.map(|referrer| referrer.id))
+    }
+
     pub async fn update(
         &self,
         id: Uuid,
@@ -61,6 +103,54 @@ impl AccountService {
         Ok(account_updated)
     }
 
+    /// Grant the role to the account, the last admin can't be demoted
+    pub async fn grant_role(
+        &self,
+        id: Uuid,
+        role: AccountRole,
+        db_tx: &mut DatabaseTransaction<'_>,
+    ) -> Result<AccountModel, Error> {
+        if role != AccountRole::Admin {
+            // Locking the admins serializes concurrent demotions
+            let admin_ids = AccountStore::lock_ids_by_role(db_tx.as_mut(), AccountRole::Admin).await?;
+            if demotes_last_admin(&admin_ids, id) {
+                return Err(Report::from(Error::AccountLastAdmin)
+                    .attach_printable(format!("Account {id} is the last admin")));
+            }
+        }
+
+        AccountStore::update_role(db_tx.as_mut(), id, role).await
+    }
+
+    /// Grant the admin role to the address, creating its account if needed.
+    ///
+    /// Only the first admin can be granted this way, later ones are granted by an admin.
+    pub async fn bootstrap_admin(&self, address: &str) -> Result<AccountModel, Error> {
+        let address = Address::from_str(address)
+            .change_context(Error::InvalidAddress)
+            .attach_printable_lazy(|| format!("Invalid address {address}"))?;
+
+        let admins = AccountStore::find_all_by_role(self.store.read(), AccountRole::Admin).await?;
+        if let Some(admin) = admins.first() {
+            return Err(Report::from(Error::AccountAdminAlreadyExists)
+                .attach_printable(format!("{} is already admin", admin.address)));
+        }
+
+        let mut db_tx = self.store.begin_transaction().await?;
+
+        let dto = CreateAccount {
+            address: format!("{address:?}"),
+            created_at: Utc::now(),
+            referral_code: None,
+        };
+        let account = self.create_if_no_exists(dto, &mut db_tx).await?;
+        let account = self.grant_role(account.id, AccountRole::Admin, &mut db_tx).await?;
+
+        self.store.commit_transaction(db_tx).await?;
+
+        Ok(account)
+    }
+
     pub fn is_default_avatar(&self, avatar: &str) -> bool {
         DEFAULT_AVATARS.contains(&avatar)
     }
@@ -74,3 +164,23 @@ impl ServiceFactory for AccountService {
         Ok(Self { store})
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_admin_is_not_demoted() {
+        let id = Uuid::new_v4();
+
+        assert!(demotes_last_admin(&[id], id));
+    }
+
+    #[test]
+    fn admin_is_demoted_while_another_remains() {
+        let id = Uuid::new_v4();
+
+        assert!(!demotes_last_admin(&[id, Uuid::new_v4()], id));
+        assert!(!demotes_last_admin(&[Uuid::new_v4()], id));
+    }
+}
@@ -1,21 +1,36 @@
 use crate::account::consts::DEFAULT_AVATARS;
-use crate::account::types::{CreateAccount, UpdateAccount};
+use crate::account::types::{AccountStats, AssetAmount, AssetDraw, CreateAccount, UpdateAccount};
 use crate::common::types::ChartDataset;
 use crate::{build_in_query, define_find_all_fns, define_find_optional_fns};
 use chrono::{DateTime, Utc};
-use entity::account::AccountModel;
-use error_stack::{Result, ResultExt};
+use entity::account::{AccountModel, AccountRole};
+use error_stack::{Report, Result, ResultExt};
 use lib::error::Error;
 use rand::prelude::SliceRandom;
 use rand::thread_rng;
 use rust_decimal::prelude::{ToPrimitive, Zero};
-use sqlx::{types::Decimal, Acquire, PgPool, Postgres, QueryBuilder};
+use sqlx::{types::Decimal, Acquire, FromRow, PgPool, Postgres, QueryBuilder};
+use std::collections::HashMap;
 use std::future::Future;
 use std::ops::{Div, Mul, Sub};
 use uuid::Uuid;
 
 pub struct AccountStore;
 
+/// Ticket or win activity of an account, the columns of the other kind default to zero
+#[derive(FromRow)]
+struct AccountActivity {
+    account_id: Uuid,
+    #[sqlx(default)]
+    lotteries_entered: i64,
+    #[sqlx(default)]
+    total_tickets: i64,
+    #[sqlx(default)]
+    wins: i64,
+    first_at: Option<DateTime<Utc>>,
+    last_at: Option<DateTime<Utc>>,
+}
+
 impl AccountStore {
     define_find_all_fns!(find_all, "SELECT * FROM account", AccountModel);
     define_find_optional_fns!(
@@ -32,6 +47,25 @@ impl AccountStore {
         Uuid,
         AccountModel
     );
+    define_find_optional_fns!(
+        find_by_referral_code,
+        try_find_by_referral_code,
+        "SELECT * FROM account WHERE referral_code = UPPER($1)",
+        String,
+        AccountModel
+    );
+    define_find_all_fns!(
+        find_all_by_referrer_id,
+        "SELECT * FROM account WHERE referrer_id = $1 ORDER BY created_at DESC",
+        Uuid,
+        AccountModel
+    );
+    define_find_all_fns!(
+        find_all_by_role,
+        "SELECT * FROM account WHERE role = $1 ORDER BY created_at ASC",
+        AccountRole,
+        AccountModel
+    );
 
     pub fn get_default_avatar() -> Option<String> {
         Vec::from(DEFAULT_AVATARS)
@@ -43,6 +77,7 @@ impl AccountStore {
     pub fn create<'a, 'c, Conn>(
         conn: Conn,
         input: CreateAccount,
+        referrer_id: Option<Uuid>,
     ) -> impl Future<Output = Result<AccountModel, Error>> + Send + 'a
     where
         Conn: Acquire<'c, Database = Postgres> + Send + 'a,
@@ -55,15 +90,16 @@ impl AccountStore {
     
             let query = r#"
                 INSERT INTO account (
-                    id, address, created_at, updated_at
+                    id, address, referrer_id, created_at, updated_at
                 )
-                VALUES ($1, $2, $3, $4)
+                VALUES ($1, $2, $3, $4, $5)
                 RETURNING *
             "#;
     
             let account = sqlx::query_as(query)
                 .bind(Uuid::new_v4()) // Generate a new UUID for the account
                 .bind(input.address.clone()) // Bind the address from the input
+                .bind(referrer_id) // Bind the optional referrer
                 .bind(input.created_at) // Bind the created_at from the input
                 .bind(input.created_at) // Bind the updated_at from the input
                 .fetch_one(conn.as_mut())
@@ -112,6 +148,137 @@ impl AccountStore {
         }
     }
     
+    #[allow(clippy::manual_async_fn)]
+    pub fn update_role<'a, 'c, Conn>(
+        conn: Conn,
+        id: Uuid,
+        role: AccountRole,
+    ) -> impl Future<Output = Result<AccountModel, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn
+                .acquire()
+                .await
+                .change_context(Error::StoreTransactionFailed)?;
+
+            let query = r#"
+                UPDATE account
+                SET role = $2, updated_at = NOW()
+                WHERE id = $1
+                RETURNING *
+            "#;
+
+            let account = sqlx::query_as(query)
+                .bind(id) // Bind the account ID to update
+                .bind(role) // Bind the granted role
+                .fetch_one(conn.as_mut())
+                .await
+                .change_context(Error::StoreUpdateFailed)?;
+
+            Ok(account)
+        }
+    }
+
+    /// Lock the accounts with the role until the end of the transaction, returning their IDs
+    #[allow(clippy::manual_async_fn)]
+    pub fn lock_ids_by_role<'a, 'c, Conn>(
+        conn: Conn,
+        role: AccountRole,
+    ) -> impl Future<Output = Result<Vec<Uuid>, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn.acquire().await.change_context(Error::Store)?;
+
+            let query = r#"
+                SELECT id FROM account
+                WHERE role = $1
+                FOR UPDATE
+            "#;
+
+            let ids = sqlx::query_scalar(query)
+                .bind(role) // Bind the role to lock the accounts of
+                .fetch_all(&mut *conn)
+                .await
+                .change_context(Error::Store)?;
+
+            Ok(ids)
+        }
+    }
+
+    /// Set the referral code of the account, unless it already has one
+    #[allow(clippy::manual_async_fn)]
+    pub fn set_referral_code<'a, 'c, Conn>(
+        conn: Conn,
+        id: Uuid,
+        code: String,
+    ) -> impl Future<Output = Result<AccountModel, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn
+                .acquire()
+                .await
+                .change_context(Error::StoreTransactionFailed)?;
+
+            let query = r#"
+                UPDATE account
+                SET referral_code = COALESCE(referral_code, $2), updated_at = NOW()
+                WHERE id = $1
+                RETURNING *
+            "#;
+
+            let account = sqlx::query_as(query)
+                .bind(id) // Bind the account ID to update
+                .bind(code) // Bind the generated referral code
+                .fetch_one(conn.as_mut())
+                .await
+                .map_err(|e| {
+                    let taken = e.as_database_error().is_some_and(|e| e.is_unique_violation());
+                    Report::new(e).change_context(if taken { Error::ReferralCodeTaken } else { Error::StoreUpdateFailed })
+                })?;
+
+            Ok(account)
+        }
+    }
+
+    /// Attach the referrer to the account, an existing referrer is kept
+    pub fn set_referrer<'a, 'c, Conn>(
+        conn: Conn,
+        id: Uuid,
+        referrer_id: Uuid,
+    ) -> impl Future<Output = Result<AccountModel, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn
+                .acquire()
+                .await
+                .change_context(Error::StoreTransactionFailed)?;
+
+            let query = r#"
+                UPDATE account
+                SET referrer_id = COALESCE(referrer_id, $2), updated_at = NOW()
+                WHERE id = $1
+                RETURNING *
+            "#;
+
+            let account = sqlx::query_as(query)
+                .bind(id) // Bind the account ID to update
+                .bind(referrer_id) // Bind the referrer to attach
+                .fetch_one(conn.as_mut())
+                .await
+                .change_context(Error::StoreUpdateFailed)?;
+
+            Ok(account)
+        }
+    }
+
     pub async fn find_all_by_ids(
         pool: &PgPool,
         ids: Vec<Uuid>,
@@ -140,4 +307,126 @@ impl AccountStore {
         
         Ok(accounts)
     }
+
+    /// Compute the activity summary of the accounts from their tickets and won draws, keyed by account ID.
+    ///
+    /// Accounts without activity get empty stats.
+    pub async fn find_stats_by_account_ids(
+        pool: &PgPool,
+        account_ids: Vec<Uuid>,
+    ) -> Result<HashMap<Uuid, AccountStats>, Error> {
+        let mut stats: HashMap<Uuid, AccountStats> =
+            account_ids.iter().map(|account_id| (*account_id, AccountStats::default())).collect();
+        let mut first_activity: HashMap<Uuid, Vec<DateTime<Utc>>> = HashMap::new();
+        let mut last_activity: HashMap<Uuid, Vec<DateTime<Utc>>> = HashMap::new();
+
+        let query = r#"
+            SELECT
+                account_id,
+                COUNT(DISTINCT lottery_id) AS lotteries_entered,
+                COALESCE(SUM(amount), 0)::BIGINT AS total_tickets,
+                MIN(purchased_at) AS first_at,
+                MAX(purchased_at) AS last_at
+            FROM ticket
+            WHERE account_id = ANY($1)
+            GROUP BY account_id
+        "#;
+
+        let tickets: Vec<AccountActivity> = sqlx::query_as(query)
+            .bind(&account_ids) // Bind the account IDs
+            .fetch_all(pool)
+            .await
+            .change_context(Error::Store)?;
+
+        for tickets in tickets {
+            let account = stats.entry(tickets.account_id).or_default();
+            account.lotteries_entered = tickets.lotteries_entered;
+            account.total_tickets = tickets.total_tickets;
+            first_activity.entry(tickets.account_id).or_default().extend(tickets.first_at);
+            last_activity.entry(tickets.account_id).or_default().extend(tickets.last_at);
+        }
+
+        let query = r#"
+            SELECT account_id, ticket_asset, SUM(ticket_price * amount)
+            FROM ticket
+            WHERE account_id = ANY($1)
+            GROUP BY account_id, ticket_asset
+        "#;
+
+        let spent: Vec<(Uuid, Uuid, Decimal)> = sqlx::query_as(query)
+            .bind(&account_ids) // Bind the account IDs
+            .fetch_all(pool)
+            .await
+            .change_context(Error::Store)?;
+
+        for (account_id, asset_id, amount) in spent {
+            stats.entry(account_id).or_default().spent.push(AssetAmount { asset_id, amount });
+        }
+
+        // Draws pay out of the ticket-funded prize, in its asset
+        let query = r#"
+            SELECT draw.winner, prize.prize_asset, COALESCE(SUM(draw.amount), 0)
+            FROM draw
+            JOIN prize ON prize.lottery_id = draw.lottery_id AND prize.source = 'TICKETS'
+            WHERE draw.winner = ANY($1) AND draw.status = 'COMPLETED'
+            GROUP BY draw.winner, prize.prize_asset
+        "#;
+
+        let winnings: Vec<(Uuid, Uuid, Decimal)> = sqlx::query_as(query)
+            .bind(&account_ids) // Bind the account IDs
+            .fetch_all(pool)
+            .await
+            .change_context(Error::Store)?;
+
+        for (account_id, asset_id, amount) in winnings {
+            stats.entry(account_id).or_default().winnings.push(AssetAmount { asset_id, amount });
+        }
+
+        let query = r#"
+            SELECT winner AS account_id, COUNT(*) AS wins, MIN(draw_date) AS first_at, MAX(draw_date) AS last_at
+            FROM draw
+            WHERE winner = ANY($1) AND status = 'COMPLETED'
+            GROUP BY winner
+        "#;
+
+        let wins: Vec<AccountActivity> = sqlx::query_as(query)
+            .bind(&account_ids) // Bind the account IDs
+            .fetch_all(pool)
+            .await
+            .change_context(Error::Store)?;
+
+        for wins in wins {
+            stats.entry(wins.account_id).or_default().wins = wins.wins;
+            first_activity.entry(wins.account_id).or_default().extend(wins.first_at);
+            last_activity.entry(wins.account_id).or_default().extend(wins.last_at);
+        }
+
+        // Amounts only compare within an asset, so the biggest win is kept per prize asset
+        let query = r#"
+            SELECT DISTINCT ON (draw.winner, prize.prize_asset) prize.prize_asset AS asset_id, draw.*
+            FROM draw
+            JOIN prize ON prize.lottery_id = draw.lottery_id AND prize.source = 'TICKETS'
+            WHERE draw.winner = ANY($1) AND draw.status = 'COMPLETED' AND draw.amount IS NOT NULL
+            ORDER BY draw.winner, prize.prize_asset, draw.amount DESC, draw.draw_date ASC
+        "#;
+
+        let biggest_wins: Vec<AssetDraw> = sqlx::query_as(query)
+            .bind(&account_ids) // Bind the account IDs
+            .fetch_all(pool)
+            .await
+            .change_context(Error::Store)?;
+
+        for win in biggest_wins {
+            if let Some(account_id) = win.draw.winner {
+                stats.entry(account_id).or_default().biggest_wins.push(win);
+            }
+        }
+
+        for (account_id, account) in stats.iter_mut() {
+            account.first_activity = first_activity.remove(account_id).and_then(|dates| dates.into_iter().min());
+            account.last_activity = last_activity.remove(account_id).and_then(|dates| dates.into_iter().max());
+        }
+
+        Ok(stats)
+    }
 }
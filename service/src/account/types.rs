@@ -1,9 +1,15 @@
 use chrono::{DateTime, Utc};
+use entity::draw::DrawModel;
+use serde::{Deserialize, Serialize};
+use sqlx::{prelude::FromRow, types::Decimal};
+use std::collections::BTreeMap;
+use uuid::Uuid;
 
 #[derive(Clone, Debug)]
 pub struct CreateAccount {
     pub address: String,
     pub created_at: DateTime<Utc>,
+    pub referral_code: Option<String>, // Code of the referrer claimed by the new account
 }
 
 #[derive(Clone, Debug)]
@@ -28,3 +34,89 @@ impl Default for UpdateAccount {
 pub struct LinkTwitter {
     pub access_token: String,
 }
+
+#[derive(Clone, Debug, PartialEq, FromRow, Serialize, Deserialize)]
+pub struct AssetAmount {
+    pub asset_id: Uuid,
+    pub amount: Decimal,
+}
+
+/// Draw along with the asset it paid out in
+#[derive(Clone, Debug, PartialEq, FromRow, Serialize, Deserialize)]
+pub struct AssetDraw {
+    pub asset_id: Uuid,
+    #[sqlx(flatten)]
+    pub draw: DrawModel,
+}
+
+/// Activity summary of an account over every lottery it took part in
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AccountStats {
+    pub lotteries_entered: i64,
+    pub total_tickets: i64,
+    pub spent: Vec<AssetAmount>, // Ticket purchases, per ticket asset
+    pub wins: i64,
+    pub winnings: Vec<AssetAmount>, // Completed draws, per prize asset
+    pub biggest_wins: Vec<AssetDraw>, // Largest completed draw, per prize asset
+    pub first_activity: Option<DateTime<Utc>>,
+    pub last_activity: Option<DateTime<Utc>>,
+}
+
+impl AccountStats {
+    /// Winnings minus spendings, per asset
+    pub fn net_profit(&self) -> Vec<AssetAmount> {
+        let mut net = BTreeMap::new();
+        for spent in &self.spent {
+            *net.entry(spent.asset_id).or_insert(Decimal::ZERO) -= spent.amount;
+        }
+        for won in &self.winnings {
+            *net.entry(won.asset_id).or_insert(Decimal::ZERO) += won.amount;
+        }
+
+        net.into_iter()
+            .map(|(asset_id, amount)| AssetAmount { asset_id, amount })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amount(asset_id: Uuid, amount: i64) -> AssetAmount {
+        AssetAmount { asset_id, amount: Decimal::from(amount) }
+    }
+
+    #[test]
+    fn net_profit_is_winnings_minus_spendings_per_asset() {
+        let (usdc, weth) = (Uuid::new_v4(), Uuid::new_v4());
+        let stats = AccountStats {
+            spent: vec![amount(usdc, 30), amount(weth, 2)],
+            winnings: vec![amount(usdc, 100)],
+            ..Default::default()
+        };
+
+        let mut net = stats.net_profit();
+        net.sort_by_key(|net| net.asset_id);
+        let mut expected = vec![amount(usdc, 70), amount(weth, -2)];
+        expected.sort_by_key(|net| net.asset_id);
+
+        assert_eq!(net, expected);
+    }
+
+    #[test]
+    fn net_profit_counts_winnings_in_assets_never_spent() {
+        let asset_id = Uuid::new_v4();
+        let stats = AccountStats {
+            winnings: vec![amount(asset_id, 5)],
+            ..Default::default()
+        };
+
+        assert_eq!(stats.net_profit(), vec![amount(asset_id, 5)]);
+    }
+
+    #[test]
+    fn net_profit_is_empty_without_activity() {
+        assert!(AccountStats::default().net_profit().is_empty());
+    }
+}
@@ -0,0 +1,114 @@
+pub mod store;
+pub mod types;
+
+use crate::chain::types::EventContext;
+use crate::config::service::{AchievementMetric, ConfigService};
+use crate::message_broker::{channels, dedup_key, MessageBrokerService};
+use crate::prelude::ServiceProvider;
+use crate::services::ServiceFactory;
+use crate::store::service::DatabaseTransaction;
+use chrono::Utc;
+use entity::account_achievement::AccountAchievementModel;
+use error_stack::{Result, ResultExt};
+use lib::error::Error;
+use serenity::async_trait;
+use std::sync::Arc;
+use store::AchievementStore;
+use types::CreateAccountAchievement;
+use uuid::Uuid;
+
+/// Metrics changed by a ticket purchase
+pub const PURCHASE_METRICS: [AchievementMetric; 3] = [
+    AchievementMetric::TotalTickets,
+    AchievementMetric::LotteriesEntered,
+    AchievementMetric::PlayStreakDays,
+];
+
+/// Metrics changed by a win
+pub const WIN_METRICS: [AchievementMetric; 1] = [AchievementMetric::Wins];
+
+pub struct AchievementService {
+    config: Arc<ConfigService>,
+    message_broker: Arc<MessageBrokerService>,
+}
+
+impl AchievementService {
+    pub fn new(config: Arc<ConfigService>, message_broker: Arc<MessageBrokerService>) -> Self {
+        Self { config, message_broker }
+    }
+
+    /// Unlock the achievements of the account whose rules are now met, returns the new unlocks.
+    ///
+    /// Only the rules on the metrics changed by the event are evaluated, and only their
+    /// metrics are computed. Each unlock records the event which triggered it, and is
+    /// announced once committed.
+    pub async fn evaluate(
+        &self,
+        account_id: Uuid,
+        changed: &[AchievementMetric],
+        context: Option<&EventContext>,
+        db_tx: &mut DatabaseTransaction<'_>,
+    ) -> Result<Vec<AccountAchievementModel>, Error> {
+        let rules: Vec<_> = self
+            .config
+            .achievements
+            .rules
+            .iter()
+            .filter(|rule| changed.contains(&rule.metric))
+            .collect();
+
+        if rules.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let unlocked = AchievementStore::find_all_by_account_id(db_tx.as_mut(), account_id).await?;
+        let pending: Vec<_> = rules
+            .into_iter()
+            .filter(|rule| !unlocked.iter().any(|unlock| unlock.achievement == rule.key))
+            .collect();
+
+        if pending.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let metrics = pending.iter().map(|rule| rule.metric).collect();
+        let metrics = AchievementStore::find_metrics(db_tx.as_mut(), account_id, metrics).await?;
+        let event = context
+            .map(serde_json::to_value)
+            .transpose()
+            .change_context(Error::SerdeSerialize)?;
+
+        let mut unlocks = Vec::new();
+        for rule in pending {
+            if metrics.value(rule.metric) < rule.threshold {
+                continue;
+            }
+
+            let dto = CreateAccountAchievement {
+                account_id,
+                achievement: rule.key.clone(),
+                context: event.clone(),
+                unlocked_at: context.map(|context| context.triggered_at).unwrap_or_else(Utc::now),
+            };
+
+            if let Some(unlock) = AchievementStore::create(db_tx.as_mut(), dto).await? {
+                let key = dedup_key(channels::ACHIEVEMENT_UNLOCKED, unlock.id, context);
+                self.message_broker.enqueue(channels::ACHIEVEMENT_UNLOCKED, &unlock, key, db_tx).await?;
+
+                unlocks.push(unlock);
+            }
+        }
+
+        Ok(unlocks)
+    }
+}
+
+#[async_trait]
+impl ServiceFactory for AchievementService {
+    async fn factory(services: ServiceProvider) -> Result<Self, Error> {
+        let config = services.get_service_unchecked::<ConfigService>().await;
+        let message_broker = services.get_service_unchecked::<MessageBrokerService>().await;
+
+        Ok(Self { config, message_broker })
+    }
+}
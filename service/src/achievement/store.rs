@@ -0,0 +1,108 @@
+use crate::achievement::types::{longest_streak, AchievementMetrics, CreateAccountAchievement};
+use crate::config::service::AchievementMetric;
+use crate::define_find_all_fns;
+use chrono::NaiveDate;
+use entity::account_achievement::AccountAchievementModel;
+use error_stack::{Result, ResultExt};
+use lib::error::Error;
+use sqlx::{Acquire, Postgres};
+use std::future::Future;
+use uuid::Uuid;
+
+pub struct AchievementStore;
+
+impl AchievementStore {
+    define_find_all_fns!(
+        find_all_by_account_id,
+        "SELECT * FROM account_achievement WHERE account_id = $1 ORDER BY unlocked_at ASC",
+        Uuid,
+        AccountAchievementModel
+    );
+
+    /// Compute the achievement metrics of the account, the others are left at zero
+    #[allow(clippy::manual_async_fn)]
+    pub fn find_metrics<'a, 'c, Conn>(
+        conn: Conn,
+        account_id: Uuid,
+        metrics: Vec<AchievementMetric>,
+    ) -> impl Future<Output = Result<AchievementMetrics, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn.acquire().await.change_context(Error::Store)?;
+
+            let query = r#"
+                SELECT
+                    CASE WHEN $2 THEN (SELECT COALESCE(SUM(amount), 0)::BIGINT FROM ticket WHERE account_id = $1) ELSE 0 END AS total_tickets,
+                    CASE WHEN $3 THEN (SELECT COUNT(DISTINCT lottery_id) FROM ticket WHERE account_id = $1) ELSE 0 END AS lotteries_entered,
+                    CASE WHEN $4 THEN (SELECT COUNT(*) FROM draw WHERE winner = $1 AND status = 'COMPLETED') ELSE 0 END AS wins,
+                    0::BIGINT AS play_streak_days
+            "#;
+
+            let mut values: AchievementMetrics = sqlx::query_as(query)
+                .bind(account_id) // Bind the account ID
+                .bind(metrics.contains(&AchievementMetric::TotalTickets)) // Bind whether to count the tickets
+                .bind(metrics.contains(&AchievementMetric::LotteriesEntered)) // Bind whether to count the lotteries
+                .bind(metrics.contains(&AchievementMetric::Wins)) // Bind whether to count the wins
+                .fetch_one(conn.as_mut())
+                .await
+                .change_context(Error::Store)?;
+
+            if metrics.contains(&AchievementMetric::PlayStreakDays) {
+                let query = r#"
+                    SELECT DISTINCT (purchased_at AT TIME ZONE 'UTC')::DATE AS day
+                    FROM ticket
+                    WHERE account_id = $1
+                    ORDER BY day ASC
+                "#;
+
+                let days: Vec<NaiveDate> = sqlx::query_scalar(query)
+                    .bind(account_id) // Bind the account ID
+                    .fetch_all(conn.as_mut())
+                    .await
+                    .change_context(Error::Store)?;
+
+                values.play_streak_days = longest_streak(&days);
+            }
+
+            Ok(values)
+        }
+    }
+
+    /// Record the unlock, `None` if the account already unlocked the achievement
+    #[allow(clippy::manual_async_fn)]
+    pub fn create<'a, 'c, Conn>(
+        conn: Conn,
+        input: CreateAccountAchievement,
+    ) -> impl Future<Output = Result<Option<AccountAchievementModel>, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn
+                .acquire()
+                .await
+                .change_context(Error::StoreTransactionFailed)?;
+
+            let query = r#"
+                INSERT INTO account_achievement (id, account_id, achievement, context, unlocked_at)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (account_id, achievement) DO NOTHING
+                RETURNING *
+            "#;
+
+            let achievement = sqlx::query_as(query)
+                .bind(Uuid::new_v4()) // Generate a new UUID for the unlock
+                .bind(input.account_id) // Bind the account
+                .bind(input.achievement) // Bind the achievement key
+                .bind(input.context) // Bind the optional triggering event
+                .bind(input.unlocked_at) // Bind the unlock date
+                .fetch_optional(conn.as_mut())
+                .await
+                .change_context(Error::StoreInsertFailed)?;
+
+            Ok(achievement)
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use crate::config::service::AchievementMetric;
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+#[derive(Clone, Debug)]
+pub struct CreateAccountAchievement {
+    pub account_id: Uuid,
+    pub achievement: String,
+    pub context: Option<serde_json::Value>,
+    pub unlocked_at: DateTime<Utc>,
+}
+
+/// Values of the achievement metrics of an account
+#[derive(Clone, Debug, Default, FromRow)]
+pub struct AchievementMetrics {
+    pub total_tickets: i64,
+    pub lotteries_entered: i64,
+    pub wins: i64,
+    pub play_streak_days: i64,
+}
+
+impl AchievementMetrics {
+    pub fn value(&self, metric: AchievementMetric) -> i64 {
+        match metric {
+            AchievementMetric::TotalTickets => self.total_tickets,
+            AchievementMetric::LotteriesEntered => self.lotteries_entered,
+            AchievementMetric::Wins => self.wins,
+            AchievementMetric::PlayStreakDays => self.play_streak_days,
+        }
+    }
+}
+
+/// Longest run of consecutive days, the days being distinct and sorted
+pub fn longest_streak(days: &[NaiveDate]) -> i64 {
+    let mut longest = 0;
+    let mut current = 0;
+    let mut previous: Option<NaiveDate> = None;
+
+    for day in days {
+        current = match previous {
+            Some(previous) if previous.succ_opt() == Some(*day) => current + 1,
+            _ => 1,
+        };
+        longest = longest.max(current);
+        previous = Some(*day);
+    }
+
+    longest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 2, day).unwrap()
+    }
+
+    #[test]
+    fn value_reads_the_metric() {
+        let metrics = AchievementMetrics {
+            total_tickets: 120,
+            lotteries_entered: 4,
+            wins: 1,
+            play_streak_days: 3,
+        };
+
+        assert_eq!(metrics.value(AchievementMetric::TotalTickets), 120);
+        assert_eq!(metrics.value(AchievementMetric::LotteriesEntered), 4);
+        assert_eq!(metrics.value(AchievementMetric::Wins), 1);
+        assert_eq!(metrics.value(AchievementMetric::PlayStreakDays), 3);
+    }
+
+    #[test]
+    fn longest_streak_is_zero_without_purchases() {
+        assert_eq!(longest_streak(&[]), 0);
+    }
+
+    #[test]
+    fn longest_streak_keeps_the_longest_run() {
+        let days = [day(1), day(2), day(4), day(5), day(6), day(8)];
+
+        assert_eq!(longest_streak(&days), 3);
+    }
+
+    #[test]
+    fn longest_streak_spans_months() {
+        let days = [day(27), day(28), NaiveDate::from_ymd_opt(2025, 3, 1).unwrap()];
+
+        assert_eq!(longest_streak(&days), 3);
+    }
+}
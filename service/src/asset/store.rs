@@ -5,7 +5,7 @@ use lib::error::Error;
 use sqlx::{types::Uuid, Acquire, PgPool, Postgres, QueryBuilder};
 use std::future::Future;
 
-use crate::{define_find_all_fns, define_find_optional_fns};
+use crate::{common::types::KeysetCursor, define_find_all_fns, define_find_optional_fns};
 
 pub struct AssetStore;
 
@@ -53,4 +53,36 @@ impl AssetStore {
 
         Ok(assets)
     }
-}
\ No newline at end of file
+
+    /// Sort of the cursors of `find_page`
+    pub const PAGE_SORT: &'static str = "created_at";
+
+    /// Find assets in creation order, starting after the cursor
+    pub async fn find_page(
+        pool: &PgPool,
+        after: Option<KeysetCursor>,
+        limit: i64,
+    ) -> Result<Vec<AssetModel>, Error> {
+        let query = r#"
+            SELECT *
+            FROM asset
+            WHERE $1::TIMESTAMPTZ IS NULL OR (created_at, id) > ($1, $2)
+            ORDER BY created_at ASC, id ASC
+            LIMIT $3
+            "#;
+
+        let (key, id) = match after {
+            Some(cursor) => (Some(cursor.timestamp(Self::PAGE_SORT)?), Some(cursor.id)),
+            None => (None, None),
+        };
+        let assets = sqlx::query_as(query)
+            .bind(key)
+            .bind(id)
+            .bind(limit)
+            .fetch_all(pool)
+            .await
+            .change_context(Error::Store)?;
+
+        Ok(assets)
+    }
+}
@@ -0,0 +1,54 @@
+use crate::audit::types::CreateAuditLog;
+use crate::define_find_all_fns;
+use entity::audit_log::AuditLogModel;
+use error_stack::{Result, ResultExt};
+use lib::error::Error;
+use sqlx::{Acquire, Postgres};
+use std::future::Future;
+use uuid::Uuid;
+
+pub struct AuditLogStore;
+
+impl AuditLogStore {
+    define_find_all_fns!(
+        find_by_entity_id,
+        "SELECT * FROM audit_log WHERE entity_id = $1 ORDER BY created_at DESC",
+        Uuid,
+        AuditLogModel
+    );
+
+    #[allow(clippy::manual_async_fn)]
+    pub fn create<'a, 'c, Conn>(
+        conn: Conn,
+        input: CreateAuditLog,
+    ) -> impl Future<Output = Result<AuditLogModel, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn
+                .acquire()
+                .await
+                .change_context(Error::StoreTransactionFailed)?;
+
+            let query = r#"
+                INSERT INTO audit_log (id, account_id, action, entity_type, entity_id, changes)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                RETURNING *
+            "#;
+
+            let log = sqlx::query_as(query)
+                .bind(Uuid::new_v4()) // Generate a new UUID for the log entry
+                .bind(input.account_id) // Bind the account which made the change
+                .bind(input.action.to_string()) // Bind the action
+                .bind(input.entity_type) // Bind the changed entity type
+                .bind(input.entity_id) // Bind the changed entity ID
+                .bind(input.changes) // Bind the changed fields
+                .fetch_one(conn.as_mut())
+                .await
+                .change_context(Error::StoreInsertFailed)?;
+
+            Ok(log)
+        }
+    }
+}
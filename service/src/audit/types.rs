@@ -0,0 +1,40 @@
+use std::fmt::Display;
+
+use uuid::Uuid;
+
+/// Changes made through the admin API
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuditAction {
+    LotteryFeatured,
+    LotteryUnfeatured,
+    LotteryRenamed,
+    LotteryReordered,
+    LotteryHidden,
+    LotteryUnhidden,
+    LotteryContentUpdated,
+    LotterySponsored,
+}
+
+impl Display for AuditAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuditAction::LotteryFeatured => write!(f, "LOTTERY_FEATURED"),
+            AuditAction::LotteryUnfeatured => write!(f, "LOTTERY_UNFEATURED"),
+            AuditAction::LotteryRenamed => write!(f, "LOTTERY_RENAMED"),
+            AuditAction::LotteryReordered => write!(f, "LOTTERY_REORDERED"),
+            AuditAction::LotteryHidden => write!(f, "LOTTERY_HIDDEN"),
+            AuditAction::LotteryUnhidden => write!(f, "LOTTERY_UNHIDDEN"),
+            AuditAction::LotteryContentUpdated => write!(f, "LOTTERY_CONTENT_UPDATED"),
+            AuditAction::LotterySponsored => write!(f, "LOTTERY_SPONSORED"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CreateAuditLog {
+    pub account_id: Uuid,
+    pub action: AuditAction,
+    pub entity_type: String,
+    pub entity_id: Uuid,
+    pub changes: serde_json::Value,
+}
@@ -0,0 +1,306 @@
+pub mod store;
+
+use self::store::SessionStore;
+use crate::cache::service::CacheService;
+use crate::config::service::ConfigService;
+use crate::services::{ServiceFactory, ServiceProvider};
+use crate::store::service::{DatabaseTransaction, StoreService};
+use chrono::{Duration, Utc};
+use entity::session::SessionModel;
+use error_stack::{Report, Result, ResultExt};
+use ethers::utils::keccak256;
+use lib::crypto::{verify_siwe_message, SiweMessage};
+use lib::error::Error;
+use rand::distributions::{Alphanumeric, DistString};
+use redis::AsyncCommands;
+use serenity::async_trait;
+use std::sync::Arc;
+use uuid::Uuid;
+
+const SIWE_NONCE_LENGTH: usize = 17;
+const SIWE_NONCE_REDIS_KEY: &str = "siwe:nonce";
+const REFRESH_TOKEN_SECRET_LENGTH: usize = 32;
+const REVOKED_TOKEN_REDIS_KEY: &str = "auth:revoked";
+
+pub struct AuthService {
+    config: Arc<ConfigService>,
+    cache: Arc<CacheService>,
+    store: Arc<StoreService>,
+}
+
+/// Session along with its refresh token, the token is only known at issuance
+pub struct SessionTokens {
+    pub session: SessionModel,
+    pub refresh_token: String,
+}
+
+/// Refresh tokens are `{session_id}.{secret}`, only the hash of the whole token is stored
+fn new_refresh_token(session_id: Uuid) -> (String, String) {
+    let secret = Alphanumeric.sample_string(&mut rand::thread_rng(), REFRESH_TOKEN_SECRET_LENGTH);
+    let token = format!("{session_id}.{secret}");
+    let hash = hash_refresh_token(&token);
+
+    (token, hash)
+}
+
+fn hash_refresh_token(token: &str) -> String {
+    hex::encode(keccak256(token.as_bytes()))
+}
+
+/// How a presented refresh token relates to its session
+#[derive(Debug, PartialEq, Eq)]
+enum RefreshTokenUse {
+    /// The current token, it can be rotated
+    Current,
+    /// The token rotated last, presented again it leaked
+    Reused,
+    /// Neither, e.g. a forged or long rotated token
+    Unknown,
+}
+
+fn refresh_token_use(session: &SessionModel, refresh_token_hash: &str) -> RefreshTokenUse {
+    if session.refresh_token_hash == refresh_token_hash {
+        RefreshTokenUse::Current
+    } else if session.previous_refresh_token_hash.as_deref() == Some(refresh_token_hash) {
+        RefreshTokenUse::Reused
+    } else {
+        RefreshTokenUse::Unknown
+    }
+}
+
+impl AuthService {
+    pub fn new(config: Arc<ConfigService>, cache: Arc<CacheService>, store: Arc<StoreService>) -> Self {
+        Self { config, cache, store }
+    }
+
+    /// Issue a nonce to be included in a Sign-In with Ethereum message, it can be used once
+    pub async fn issue_nonce(&self) -> Result<String, Error> {
+        let nonce = Alphanumeric.sample_string(&mut rand::thread_rng(), SIWE_NONCE_LENGTH);
+
+        let mut connection = self.cache.get_connection().await?;
+        connection
+            .set_ex::<_, _, ()>(
+                format!("{SIWE_NONCE_REDIS_KEY}:{nonce}"),
+                1,
+                self.config.graphql.siwe.nonce_ttl as usize,
+            )
+            .await
+            .change_context(Error::Redis)?;
+
+        Ok(nonce)
+    }
+
+    /// Verify the signed Sign-In with Ethereum message and burn its nonce.
+    ///
+    /// Returns the message on success, its address is the one signing in.
+    pub async fn verify_siwe(&self, message: &str, signature: &str) -> Result<SiweMessage, Error> {
+        let siwe = verify_siwe_message(message, signature)?;
+
+        let config = &self.config.graphql.siwe;
+        let chain_ids = self
+            .config
+            .chains
+            .iter()
+            .map(|chain| chain.chain_id as u64)
+            .collect::<Vec<_>>();
+        siwe.validate(&config.domain, &config.uri, &chain_ids, Utc::now())?;
+
+        // Deleting is atomic, of concurrent logins with the same nonce only one succeeds
+        let mut connection = self.cache.get_connection().await?;
+        let burned: usize = connection
+            .del(format!("{SIWE_NONCE_REDIS_KEY}:{}", siwe.nonce))
+            .await
+            .change_context(Error::Redis)?;
+        if burned == 0 {
+            return Err(Report::from(Error::SiweInvalidNonce));
+        }
+
+        Ok(siwe)
+    }
+
+    /// Open a session for the account, its access token id is the `jti` to issue the access token with
+    pub async fn create_session(
+        &self,
+        account_id: Uuid,
+        db_tx: &mut DatabaseTransaction<'_>,
+    ) -> Result<SessionTokens, Error> {
+        let id = Uuid::new_v4();
+        let (refresh_token, refresh_token_hash) = new_refresh_token(id);
+        let expires_at = Utc::now() + Duration::seconds(self.config.jwt.ttl.refresh);
+
+        let session = SessionStore::create(
+            db_tx.as_mut(),
+            id,
+            account_id,
+            refresh_token_hash,
+            Uuid::new_v4(),
+            expires_at,
+        )
+        .await?;
+
+        Ok(SessionTokens {
+            session,
+            refresh_token,
+        })
+    }
+
+    /// Exchange the refresh token for a new one, the previous access token of the session is revoked.
+    ///
+    /// A refresh token can be used once, presenting the token rotated last again means it leaked
+    /// and the whole session is revoked. Unknown tokens are only rejected.
+    pub async fn refresh_session(&self, refresh_token: &str) -> Result<SessionTokens, Error> {
+        let session_id = refresh_token
+            .split_once('.')
+            .and_then(|(id, _)| Uuid::parse_str(id).ok())
+            .ok_or(Report::from(Error::SessionInvalid))
+            .attach_printable("Malformed refresh token")?;
+
+        let session = SessionStore::try_find_by_id(self.store.write(), session_id)
+            .await?
+            .ok_or(Report::from(Error::SessionInvalid))?;
+
+        let refresh_token_hash = hash_refresh_token(refresh_token);
+        match refresh_token_use(&session, &refresh_token_hash) {
+            RefreshTokenUse::Current => {}
+            RefreshTokenUse::Reused => {
+                if session.revoked_at.is_none() {
+                    self.revoke_session(session.id, None).await?;
+                }
+
+                return Err(Report::from(Error::SessionInvalid)
+                    .attach_printable(format!("Refresh token of session {} was reused", session.id)));
+            }
+            // Only the holder of a rotated token proves a leak, anyone can guess a session id
+            RefreshTokenUse::Unknown => {
+                return Err(Report::from(Error::SessionInvalid)
+                    .attach_printable(format!("Unknown refresh token for session {}", session.id)));
+            }
+        }
+
+        let (new_refresh_token, new_refresh_token_hash) = new_refresh_token(session.id);
+        let expires_at = Utc::now() + Duration::seconds(self.config.jwt.ttl.refresh);
+
+        let rotated = SessionStore::rotate(
+            self.store.write(),
+            session.id,
+            refresh_token_hash,
+            new_refresh_token_hash,
+            Uuid::new_v4(),
+            expires_at,
+        )
+        .await?
+        .ok_or(Report::from(Error::SessionInvalid))?;
+
+        self.revoke_token(session.access_token_id).await?;
+
+        Ok(SessionTokens {
+            session: rotated,
+            refresh_token: new_refresh_token,
+        })
+    }
+
+    /// Sessions of the account that can still be refreshed
+    pub async fn sessions(&self, account_id: Uuid) -> Result<Vec<SessionModel>, Error> {
+        SessionStore::find_active_by_account(self.store.read(), account_id).await
+    }
+
+    /// Revoke the session and its access token, `account_id` restricts it to the sessions of that account.
+    ///
+    /// Returns `None` when there is no such active session.
+    pub async fn revoke_session(
+        &self,
+        id: Uuid,
+        account_id: Option<Uuid>,
+    ) -> Result<Option<SessionModel>, Error> {
+        let session = SessionStore::revoke(self.store.write(), id, account_id).await?;
+
+        if let Some(session) = &session {
+            self.revoke_token(session.access_token_id).await?;
+        }
+
+        Ok(session)
+    }
+
+    /// Whether the access token with the `jti` was revoked
+    pub async fn is_token_revoked(&self, token_id: Uuid) -> Result<bool, Error> {
+        let mut connection = self.cache.get_connection().await?;
+
+        connection
+            .exists(format!("{REVOKED_TOKEN_REDIS_KEY}:{token_id}"))
+            .await
+            .change_context(Error::Redis)
+    }
+
+    /// Add the access token to the revocation list until it would have expired anyway
+    async fn revoke_token(&self, token_id: Uuid) -> Result<(), Error> {
+        let mut connection = self.cache.get_connection().await?;
+
+        connection
+            .set_ex::<_, _, ()>(
+                format!("{REVOKED_TOKEN_REDIS_KEY}:{token_id}"),
+                1,
+                self.config.jwt.ttl.access.max(1) as usize,
+            )
+            .await
+            .change_context(Error::Redis)
+    }
+}
+
+#[async_trait]
+impl ServiceFactory for AuthService {
+    async fn factory(services: ServiceProvider) -> Result<Self, Error> {
+        let config = services.get_service_unchecked::<ConfigService>().await;
+        let cache = services.get_service_unchecked::<CacheService>().await;
+        let store = services.get_service_unchecked::<StoreService>().await;
+
+        Ok(Self::new(config, cache, store))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(refresh_token_hash: &str, previous_refresh_token_hash: Option<&str>) -> SessionModel {
+        SessionModel {
+            id: Uuid::new_v4(),
+            account_id: Uuid::new_v4(),
+            refresh_token_hash: refresh_token_hash.to_string(),
+            previous_refresh_token_hash: previous_refresh_token_hash.map(str::to_string),
+            access_token_id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            refreshed_at: Utc::now(),
+            expires_at: Utc::now() + Duration::days(1),
+            revoked_at: None,
+        }
+    }
+
+    #[test]
+    fn refresh_token_is_bound_to_its_session() {
+        let id = Uuid::new_v4();
+        let (token, hash) = new_refresh_token(id);
+
+        assert!(token.starts_with(&format!("{id}.")));
+        assert_eq!(hash_refresh_token(&token), hash);
+    }
+
+    #[test]
+    fn current_refresh_token_is_rotated() {
+        let session = session("current", Some("previous"));
+
+        assert_eq!(refresh_token_use(&session, "current"), RefreshTokenUse::Current);
+    }
+
+    #[test]
+    fn rotated_refresh_token_is_reused() {
+        let session = session("current", Some("previous"));
+
+        assert_eq!(refresh_token_use(&session, "previous"), RefreshTokenUse::Reused);
+    }
+
+    #[test]
+    fn other_refresh_tokens_are_unknown() {
+        assert_eq!(refresh_token_use(&session("current", Some("previous")), "forged"), RefreshTokenUse::Unknown);
+        assert_eq!(refresh_token_use(&session("current", None), "forged"), RefreshTokenUse::Unknown);
+    }
+}
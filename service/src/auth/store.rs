@@ -0,0 +1,164 @@
+use crate::define_find_optional_fns;
+use chrono::{DateTime, Utc};
+use entity::session::SessionModel;
+use error_stack::{Result, ResultExt};
+use lib::error::Error;
+use sqlx::{Acquire, Postgres};
+use std::future::Future;
+use uuid::Uuid;
+
+pub struct SessionStore;
+
+impl SessionStore {
+    define_find_optional_fns!(
+        find_by_id,
+        try_find_by_id,
+        "SELECT * FROM session WHERE id = $1",
+        Uuid,
+        SessionModel
+    );
+
+    #[allow(clippy::manual_async_fn)]
+    pub fn create<'a, 'c, Conn>(
+        conn: Conn,
+        id: Uuid,
+        account_id: Uuid,
+        refresh_token_hash: String,
+        access_token_id: Uuid,
+        expires_at: DateTime<Utc>,
+    ) -> impl Future<Output = Result<SessionModel, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn
+                .acquire()
+                .await
+                .change_context(Error::StoreTransactionFailed)?;
+
+            let query = r#"
+                INSERT INTO session (id, account_id, refresh_token_hash, access_token_id, expires_at)
+                VALUES ($1, $2, $3, $4, $5)
+                RETURNING *
+            "#;
+
+            let session = sqlx::query_as(query)
+                .bind(id) // Bind the session ID, it is part of the refresh token
+                .bind(account_id) // Bind the account ID
+                .bind(refresh_token_hash) // Bind the refresh token hash
+                .bind(access_token_id) // Bind the access token ID
+                .bind(expires_at) // Bind the refresh token expiry
+                .fetch_one(conn.as_mut())
+                .await
+                .change_context(Error::StoreInsertFailed)?;
+
+            Ok(session)
+        }
+    }
+
+    /// Sessions of the account that are neither revoked nor expired, most recently refreshed first
+    #[allow(clippy::manual_async_fn)]
+    pub fn find_active_by_account<'a, 'c, Conn>(
+        conn: Conn,
+        account_id: Uuid,
+    ) -> impl Future<Output = Result<Vec<SessionModel>, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn.acquire().await.change_context(Error::Store)?;
+
+            let query = r#"
+                SELECT * FROM session
+                WHERE account_id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+                ORDER BY refreshed_at DESC
+            "#;
+
+            let sessions = sqlx::query_as(query)
+                .bind(account_id) // Bind the account ID
+                .fetch_all(&mut *conn)
+                .await
+                .change_context(Error::Store)?;
+
+            Ok(sessions)
+        }
+    }
+
+    /// Replace the refresh token of the session, only if `refresh_token_hash` is still the current one.
+    ///
+    /// Returns `None` when the session was rotated, revoked or has expired in the meantime.
+    #[allow(clippy::manual_async_fn)]
+    pub fn rotate<'a, 'c, Conn>(
+        conn: Conn,
+        id: Uuid,
+        refresh_token_hash: String,
+        new_refresh_token_hash: String,
+        access_token_id: Uuid,
+        expires_at: DateTime<Utc>,
+    ) -> impl Future<Output = Result<Option<SessionModel>, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn
+                .acquire()
+                .await
+                .change_context(Error::StoreTransactionFailed)?;
+
+            let query = r#"
+                UPDATE session
+                SET refresh_token_hash = $3, previous_refresh_token_hash = $2, access_token_id = $4, expires_at = $5, refreshed_at = NOW()
+                WHERE id = $1 AND refresh_token_hash = $2 AND revoked_at IS NULL AND expires_at > NOW()
+                RETURNING *
+            "#;
+
+            let session = sqlx::query_as(query)
+                .bind(id) // Bind the session ID to update
+                .bind(refresh_token_hash) // Bind the presented refresh token hash
+                .bind(new_refresh_token_hash) // Bind the rotated refresh token hash
+                .bind(access_token_id) // Bind the new access token ID
+                .bind(expires_at) // Bind the extended expiry
+                .fetch_optional(conn.as_mut())
+                .await
+                .change_context(Error::StoreUpdateFailed)?;
+
+            Ok(session)
+        }
+    }
+
+    /// Revoke the session, `account_id` restricts it to the sessions of that account.
+    ///
+    /// Returns `None` when there is no such active session.
+    #[allow(clippy::manual_async_fn)]
+    pub fn revoke<'a, 'c, Conn>(
+        conn: Conn,
+        id: Uuid,
+        account_id: Option<Uuid>,
+    ) -> impl Future<Output = Result<Option<SessionModel>, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn
+                .acquire()
+                .await
+                .change_context(Error::StoreTransactionFailed)?;
+
+            let query = r#"
+                UPDATE session
+                SET revoked_at = NOW()
+                WHERE id = $1 AND ($2::UUID IS NULL OR account_id = $2) AND revoked_at IS NULL
+                RETURNING *
+            "#;
+
+            let session = sqlx::query_as(query)
+                .bind(id) // Bind the session ID to revoke
+                .bind(account_id) // Bind the optional owner account ID
+                .fetch_optional(conn.as_mut())
+                .await
+                .change_context(Error::StoreUpdateFailed)?;
+
+            Ok(session)
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use super::{service::CacheService, tags};
+use crate::message_broker::{channels, Delivery, Event, MessageBrokerService};
+use error_stack::Result;
+use futures::stream::{select_all, StreamExt};
+use lib::error::Error;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Tags of the cached values made stale by the event
+fn stale_tags(event: &Event) -> Vec<String> {
+    let lottery_id = match event {
+        Event::TicketBought(ticket) => ticket.lottery_id,
+        Event::PrizePoolUpdated(prize) => prize.lottery_id,
+        Event::LotteryOpened(lottery) | Event::LotteryClosed(lottery) | Event::LotteryCanceled(lottery) => lottery.id,
+        Event::WinnerDrawn(draw) => draw.lottery_id,
+        Event::AchievementUnlocked(_) => return Vec::new(),
+    };
+
+    let mut stale = vec![tags::lottery(lottery_id)];
+    match event {
+        Event::TicketBought(ticket) => stale.push(tags::account(ticket.account_id)),
+        Event::WinnerDrawn(draw) => stale.extend(draw.winner.map(tags::account)),
+        _ => {}
+    }
+
+    stale
+}
+
+/// Invalidate the cached values made stale by broker events, until the process stops
+pub async fn invalidate_on_events(cache: Arc<CacheService>, broker: Arc<MessageBrokerService>) -> Result<(), Error> {
+    let streams = vec![
+        broker.subscribe(channels::TICKET_BOUGHT, None).await?.boxed(),
+        broker.subscribe(channels::PRIZE_POOL_UPDATED, None).await?.boxed(),
+        broker.subscribe(channels::LOTTERY_OPENED, None).await?.boxed(),
+        broker.subscribe(channels::LOTTERY_CLOSED, None).await?.boxed(),
+        broker.subscribe(channels::LOTTERY_CANCELED, None).await?.boxed(),
+        broker.subscribe(channels::WINNER_DRAWN, None).await?.boxed(),
+    ];
+
+    let mut deliveries = select_all(streams);
+
+    while let Some(delivery) = deliveries.next().await {
+        match delivery {
+            Delivery::Event(event) => {
+                for tag in stale_tags(&event.event) {
+                    if let Err(e) = cache.invalidate_tag(&tag).await {
+                        warn!("Failed to invalidate cache tag {tag}: {e:?}");
+                    }
+                }
+            }
+            Delivery::Lagged => {
+                // The missed events are unknown, so every tag they could have touched is flushed
+                warn!("Cache invalidation lagged behind, flushing the cache");
+                if let Err(e) = cache.invalidate_all().await {
+                    warn!("Failed to flush the cache: {e:?}");
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1 +1,20 @@
+pub mod invalidation;
 pub mod service;
+
+/// Tags cached values are grouped under, to invalidate them together
+pub mod tags {
+    use uuid::Uuid;
+
+    /// Supported assets
+    pub const ASSETS: &str = "assets";
+
+    /// Everything derived from the lottery
+    pub fn lottery(id: Uuid) -> String {
+        format!("lottery:{id}")
+    }
+
+    /// Everything derived from the account activity
+    pub fn account(id: Uuid) -> String {
+        format!("account:{id}")
+    }
+}
@@ -3,29 +3,180 @@ use crate::prelude::ServiceProvider;
 use crate::services::ServiceFactory;
 use error_stack::{Result, ResultExt};
 use lib::error::Error;
-use redis::aio::Connection;
-use redis::Client;
+use redis::aio::ConnectionManager;
+use redis::{AsyncCommands, Client};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serenity::async_trait;
+use std::fmt::Display;
+use std::future::Future;
 use std::ops::Deref;
-use tracing::info;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+struct CacheServiceInner {
+    client: Client,
+    connection: ConnectionManager,
+}
 
 #[derive(Clone)]
-pub struct CacheService(Client);
+pub struct CacheService(Arc<CacheServiceInner>);
+
+/// Key of a cached value, namespaced so unrelated values never collide
+#[derive(Clone, Debug)]
+pub struct CacheKey(String);
+
+impl CacheKey {
+    pub fn new(namespace: &str, key: impl Display) -> Self {
+        Self(format!("cache:{namespace}:{key}"))
+    }
+}
+
+impl Display for CacheKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Counter bumped by every invalidation, values remember the epoch they were loaded at
+const EPOCH_KEY: &str = "cache:epoch";
+
+/// Epoch of the last full flush, values loaded before it are stale
+const FLUSH_KEY: &str = "cache:flush";
+
+/// Tag versions outlive every cached value so that no stale value is served once they expire
+const TAG_VERSION_TTL: usize = 24 * 60 * 60;
+
+/// Epoch at which the tag was last invalidated
+fn tag_key(tag: &str) -> String {
+    format!("cache:tag:{tag}")
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedValue<T> {
+    epoch: i64,
+    tags: Vec<String>,
+    value: T,
+}
+
+/// Whether a value loaded at `epoch` survived the invalidations of its tags, given as epochs
+fn is_fresh(epoch: i64, invalidated: &[Option<i64>]) -> bool {
+    invalidated.iter().flatten().all(|invalidated| *invalidated < epoch)
+}
 
 impl CacheService {
-    pub fn new(config: RedisConfig) -> Result<Self, Error> {
+    pub async fn new(config: RedisConfig) -> Result<Self, Error> {
         info!(url = config.url, "Connecting to redis");
 
         let client = Client::open(config.url).change_context(Error::Redis)?;
 
-        Ok(CacheService(client))
+        let connection = client
+            .get_tokio_connection_manager()
+            .await
+            .change_context(Error::RedisConnect)?;
+
+        Ok(CacheService(Arc::new(CacheServiceInner { client, connection })))
     }
 
-    pub async fn get_connection(&self) -> Result<Connection, Error> {
-        self.0
-            .get_tokio_connection()
+    /// Shared connection, reconnecting on failures
+    pub async fn get_connection(&self) -> Result<ConnectionManager, Error> {
+        Ok(self.0.connection.clone())
+    }
+
+    /// Get the cached value, or load and cache it for `ttl` under the tags returned by `tags`.
+    ///
+    /// The epoch is taken before loading, so a value loaded while one of its tags is
+    /// invalidated is never served. Loaders should read from the primary, a replica may not
+    /// have caught up with the invalidation yet. The cache never fails a read, when Redis is
+    /// unavailable the value is loaded.
+    pub async fn get_or_load<T, L, F, G>(&self, key: &CacheKey, ttl: Duration, tags: G, load: L) -> Result<T, Error>
+    where
+        T: Serialize + DeserializeOwned,
+        L: FnOnce() -> F,
+        F: Future<Output = Result<T, Error>>,
+        G: FnOnce(&T) -> Vec<String>,
+    {
+        let mut conn = self.0.connection.clone();
+
+        match conn.get::<_, Option<String>>(&key.0).await {
+            Ok(Some(cached)) => match serde_json::from_str::<CachedValue<T>>(&cached) {
+                Ok(cached) => match self.is_fresh(&cached).await {
+                    Ok(true) => return Ok(cached.value),
+                    Ok(false) => {}
+                    Err(e) => warn!("Failed to check cached value {key}: {e:?}"),
+                },
+                Err(e) => warn!("Discarding undecodable cached value {key}: {e:?}"),
+            },
+            Ok(None) => {}
+            Err(e) => warn!("Failed to read cached value {key}: {e:?}"),
+        }
+
+        // Bind the epoch before loading, a failure only skips caching
+        let epoch = match conn.incr::<_, _, i64>(EPOCH_KEY, 1).await {
+            Ok(epoch) => Some(epoch),
+            Err(e) => {
+                warn!("Failed to read cache epoch: {e:?}");
+                None
+            }
+        };
+
+        let value = load().await?;
+
+        if let Some(epoch) = epoch {
+            let cached = CachedValue { epoch, tags: tags(&value), value };
+            if let Err(e) = self.set(key, &cached, ttl).await {
+                warn!("Failed to cache value {key}: {e:?}");
+            }
+            return Ok(cached.value);
+        }
+
+        Ok(value)
+    }
+
+    async fn is_fresh<T>(&self, cached: &CachedValue<T>) -> Result<bool, Error> {
+        let keys = std::iter::once(FLUSH_KEY.to_string())
+            .chain(cached.tags.iter().map(|tag| tag_key(tag)))
+            .collect::<Vec<_>>();
+
+        let mut conn = self.0.connection.clone();
+        let invalidated: Vec<Option<i64>> = redis::cmd("MGET")
+            .arg(keys)
+            .query_async(&mut conn)
             .await
-            .change_context(Error::RedisConnect)
+            .change_context(Error::Redis)?;
+
+        Ok(is_fresh(cached.epoch, &invalidated))
+    }
+
+    async fn set<T: Serialize>(&self, key: &CacheKey, cached: &CachedValue<T>, ttl: Duration) -> Result<(), Error> {
+        let payload = serde_json::to_string(cached).change_context(Error::SerdeSerialize)?;
+        let ttl = ttl.as_secs().max(1) as usize;
+
+        let mut conn = self.0.connection.clone();
+        conn.set_ex::<_, _, ()>(&key.0, payload, ttl).await.change_context(Error::Redis)
+    }
+
+    pub async fn invalidate(&self, key: &CacheKey) -> Result<(), Error> {
+        let mut conn = self.0.connection.clone();
+        conn.del::<_, ()>(&key.0).await.change_context(Error::Redis)
+    }
+
+    /// Mark every value cached under the tag stale, including values still being loaded
+    pub async fn invalidate_tag(&self, tag: &str) -> Result<(), Error> {
+        self.invalidate_at(&tag_key(tag)).await
+    }
+
+    /// Mark every cached value stale
+    pub async fn invalidate_all(&self) -> Result<(), Error> {
+        self.invalidate_at(FLUSH_KEY).await
+    }
+
+    async fn invalidate_at(&self, version_key: &str) -> Result<(), Error> {
+        let mut conn = self.0.connection.clone();
+        let epoch: i64 = conn.incr(EPOCH_KEY, 1).await.change_context(Error::Redis)?;
+        conn.set_ex::<_, _, ()>(version_key, epoch, TAG_VERSION_TTL)
+            .await
+            .change_context(Error::Redis)
     }
 }
 
@@ -33,7 +184,7 @@ impl Deref for CacheService {
     type Target = Client;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.0.client
     }
 }
 
@@ -41,6 +192,28 @@ impl Deref for CacheService {
 impl ServiceFactory for CacheService {
     async fn factory(services: ServiceProvider) -> Result<Self, Error> {
         let config = services.get_service_unchecked::<ConfigService>().await;
-        Self::new(config.redis.clone())
+        Self::new(config.redis.clone()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn value_is_fresh_without_invalidations() {
+        assert!(is_fresh(5, &[None, None]));
+        assert!(is_fresh(5, &[]));
+    }
+
+    #[test]
+    fn value_is_fresh_when_invalidated_before_load() {
+        assert!(is_fresh(5, &[Some(4), None, Some(1)]));
+    }
+
+    #[test]
+    fn value_is_stale_when_invalidated_during_or_after_load() {
+        assert!(!is_fresh(5, &[None, Some(6)]));
+        assert!(!is_fresh(5, &[Some(7), Some(1)]));
     }
 }
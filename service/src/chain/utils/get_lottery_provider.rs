@@ -5,17 +5,23 @@ use error_stack::{Result, ResultExt};
 
 use crate::chain::provider::ChainProvider;
 
-/// Get the parent vault for the given address
+/// Get the parent vault for the given address, at the given block or the latest one
 pub async fn get_lottery_data<Provider: ChainProvider>(
     provider: &Provider,
     address: Address,
-    lottery_id: H256
+    lottery_id: H256,
+    block_number: Option<u64>,
 ) -> Result<LotteryChainData, Error> {
     let client = provider.get_client()?;
     
     let contract = LotteryProviderContract::new(address, client);
     
-    let lottery = contract.lotteries(lottery_id.to_fixed_bytes())
+    let mut call = contract.lotteries(lottery_id.to_fixed_bytes());
+    if let Some(block_number) = block_number {
+        call = call.block(block_number);
+    }
+    
+    let lottery = call
         .call()
         .await
         .change_context(Error::ContractQuery)?;
@@ -39,6 +45,31 @@ pub async fn get_lottery_data<Provider: ChainProvider>(
     })
 }
 
+/// Get the amount of tickets the participant holds in the lottery, at the given block or the latest one
+pub async fn get_number_of_tickets_by_address<Provider: ChainProvider>(
+    provider: &Provider,
+    address: Address,
+    lottery_id: H256,
+    participant: Address,
+    block_number: Option<u64>,
+) -> Result<u32, Error> {
+    let client = provider.get_client()?;
+    
+    let contract = LotteryProviderContract::new(address, client);
+    
+    let mut call = contract.number_of_tickets_by_address(lottery_id.to_fixed_bytes(), participant);
+    if let Some(block_number) = block_number {
+        call = call.block(block_number);
+    }
+    
+    let tickets = call
+        .call()
+        .await
+        .change_context(Error::ContractQuery)?;
+    
+    Ok(tickets)
+}
+
 pub struct LotteryChainData {
    pub entrance_token_address: Address,
    pub fee_amount_per_time: u128,
@@ -135,21 +135,48 @@ macro_rules! build_count_query {
 
 #[macro_export]
 /// Builds query with page and page size
-/// Returns query (as SQL) with limit and offset
-/// Example (page: 200, page_size: 10):
+/// Returns query (as SQL) with limit and offset, `None` when the offset does not fit in a `BIGINT`.
+/// The page size is capped to `MAX_PAGE_SIZE`.
+/// Example (page: 2, page_size: 10):
 /// ```rust
-/// let query = build_paginated_query!("SELECT * FROM table", 20, 10);
+/// let query = build_paginated_query!("SELECT * FROM table", 2, 10);
 /// ```
 ///
 /// Will return:
 /// ```sql
-/// SELECT * FROM table LIMIT 20 OFFSET 10
+/// SELECT * FROM table LIMIT 10 OFFSET 20
 /// ```
 macro_rules! build_paginated_query {
     ($base_query:expr, $page:expr, $page_size:expr) => {{
-        let limit = format!(" LIMIT {}", $page_size);
-        let offset = format!(" OFFSET {}", ($page * $page_size) as i64);
+        let page_size = ($page_size as u64).clamp(1, $crate::common::types::MAX_PAGE_SIZE);
 
-        format!("{} {} {}", $base_query, limit, offset)
+        ($page as u64)
+            .checked_mul(page_size)
+            .filter(|offset| *offset <= i64::MAX as u64)
+            .map(|offset| format!("{} LIMIT {} OFFSET {}", $base_query, page_size, offset))
     }};
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::common::types::MAX_PAGE_SIZE;
+
+    #[test]
+    fn test_build_paginated_query() {
+        let query = build_paginated_query!("SELECT * FROM table", 2, 10);
+        assert_eq!(query.as_deref(), Some("SELECT * FROM table LIMIT 10 OFFSET 20"));
+    }
+
+    #[test]
+    fn test_build_paginated_query_caps_page_size() {
+        let query = build_paginated_query!("SELECT * FROM table", 1, 10_000);
+        let expected = format!("SELECT * FROM table LIMIT {MAX_PAGE_SIZE} OFFSET {MAX_PAGE_SIZE}");
+        assert_eq!(query, Some(expected));
+    }
+
+    #[test]
+    fn test_build_paginated_query_rejects_overflowing_offset() {
+        assert_eq!(build_paginated_query!("SELECT * FROM table", u64::MAX, 10), None);
+        assert_eq!(build_paginated_query!("SELECT * FROM table", i64::MAX as u64, 2), None);
+    }
+}
@@ -1,7 +1,11 @@
 use async_graphql::Enum;
 use chrono::{DateTime, Days, Months, NaiveDateTime, Utc};
+use error_stack::{Report, Result, ResultExt};
+use lib::error::Error;
 use serde::Serialize;
 use sqlx::types::Decimal;
+use std::str::FromStr;
+use uuid::Uuid;
 
 #[derive(Enum, Copy, Clone, Eq, PartialEq, Debug, Serialize)]
 pub enum DateRangeInput {
@@ -61,6 +65,9 @@ pub struct ChartDataset {
     pub value: Decimal,
 }
 
+/// Upper bound of a page size, so a single query never fetches a whole table
+pub const MAX_PAGE_SIZE: u64 = 100;
+
 /// Paginated results for a list of items of type T
 pub struct PaginatedResults<T> {
     pub items: Vec<T>,
@@ -68,6 +75,59 @@ pub struct PaginatedResults<T> {
     pub num_items: u64,
 }
 
+/// Position of the last item of a keyset paginated page.
+///
+/// `sort` names the order the cursor was issued for, `key` is the text representation of the sorted column,
+/// `id` breaks ties between equal keys.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeysetCursor {
+    pub sort: String,
+    pub key: String,
+    pub id: Uuid,
+}
+
+/// Type of the column a keyset paginated query is sorted on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeysetKey {
+    Timestamp,
+    Numeric,
+    Integer,
+}
+
+impl KeysetCursor {
+    pub fn from_timestamp(sort: &str, timestamp: DateTime<Utc>, id: Uuid) -> Self {
+        Self {
+            sort: sort.to_string(),
+            key: timestamp.to_rfc3339(),
+            id,
+        }
+    }
+
+    /// Check the cursor was issued for the sort, and its key is a value of the sorted column
+    pub fn check(&self, sort: &str, key: KeysetKey) -> Result<(), Error> {
+        let is_valid = self.sort == sort
+            && match key {
+                KeysetKey::Timestamp => DateTime::parse_from_rfc3339(&self.key).is_ok(),
+                KeysetKey::Numeric => Decimal::from_str(&self.key).is_ok(),
+                KeysetKey::Integer => self.key.parse::<i32>().is_ok(),
+            };
+
+        if !is_valid {
+            return Err(Report::new(Error::InvalidCursor));
+        }
+
+        Ok(())
+    }
+
+    /// Timestamp key of the cursor, when it was issued for the sort
+    pub fn timestamp(&self, sort: &str) -> Result<DateTime<Utc>, Error> {
+        self.check(sort, KeysetKey::Timestamp)?;
+
+        let timestamp = DateTime::parse_from_rfc3339(&self.key).change_context(Error::InvalidCursor)?;
+        Ok(timestamp.with_timezone(&Utc))
+    }
+}
+
 pub(crate) fn parse_range_to_key_and_query<'a>(
     range: Option<DateRangeInput>,
     created_at: DateTime<Utc>,
@@ -91,3 +151,41 @@ pub(crate) fn parse_range_to_key_and_query<'a>(
 
     (key_trunk, range_query)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cursor(sort: &str, key: &str) -> KeysetCursor {
+        KeysetCursor {
+            sort: sort.to_string(),
+            key: key.to_string(),
+            id: Uuid::nil(),
+        }
+    }
+
+    #[test]
+    fn test_keyset_cursor_round_trips_timestamps() {
+        let now = Utc::now();
+        let cursor = KeysetCursor::from_timestamp("purchased_at", now, Uuid::nil());
+
+        assert_eq!(cursor.timestamp("purchased_at").unwrap(), now);
+    }
+
+    #[test]
+    fn test_keyset_cursor_rejects_other_sorts() {
+        let cursor = KeysetCursor::from_timestamp("created_at", Utc::now(), Uuid::nil());
+
+        let err = cursor.timestamp("purchased_at").unwrap_err();
+        assert!(matches!(err.current_context(), Error::InvalidCursor));
+    }
+
+    #[test]
+    fn test_keyset_cursor_rejects_malformed_keys() {
+        assert!(cursor("largest_pool", "12.5").check("largest_pool", KeysetKey::Numeric).is_ok());
+        assert!(cursor("largest_pool", "lots").check("largest_pool", KeysetKey::Numeric).is_err());
+        assert!(cursor("featured_position", "3").check("featured_position", KeysetKey::Integer).is_ok());
+        assert!(cursor("featured_position", "3.5").check("featured_position", KeysetKey::Integer).is_err());
+        assert!(cursor("newest", "yesterday").check("newest", KeysetKey::Timestamp).is_err());
+    }
+}
@@ -1,6 +1,7 @@
 use error_stack::{Report, Result, ResultExt};
 use ethers::types::Address;
 use serde::{Deserialize, Serialize};
+use rust_decimal::Decimal;
 use serenity::async_trait;
 use std::{
     collections::HashMap, fmt::Display, fs, ops::Deref, path::Path, str::FromStr, sync::Arc,
@@ -15,10 +16,14 @@ pub struct ConfigServiceInner {
     pub environment: AppEnvironment,
     pub database: DatabaseConfig,
     pub redis: RedisConfig,
+    pub message_bus: MessageBusConfig,
     pub graphql: GQLConfig,
     pub chains: Vec<ChainConfig>,
     pub jwt: JWTConfig,
     pub twitter: TwitterConfig,
+    pub referral: ReferralConfig,
+    pub achievements: AchievementConfig,
+    pub draws: DrawConfig,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -34,10 +39,18 @@ impl<'de> serde::Deserialize<'de> for ConfigService {
             pub environment: AppEnvironment,
             pub database: DatabaseConfig,
             pub redis: RedisConfig,
+            #[serde(default)]
+            pub message_bus: MessageBusConfig,
             pub graphql: GQLConfig,
             pub chains: Vec<ChainConfig>,
             pub jwt: JWTConfig,
             pub twitter: TwitterConfig,
+            #[serde(default)]
+            pub referral: ReferralConfig,
+            #[serde(default)]
+            pub achievements: AchievementConfig,
+            #[serde(default)]
+            pub draws: DrawConfig,
         }
 
         let ad_hoc: AdHocConfig = serde::Deserialize::deserialize(deserializer)?;
@@ -46,10 +59,14 @@ impl<'de> serde::Deserialize<'de> for ConfigService {
             .environment(ad_hoc.environment)
             .database(ad_hoc.database)
             .redis(ad_hoc.redis)
+            .message_bus(ad_hoc.message_bus)
             .graphql(ad_hoc.graphql)
             .chains(ad_hoc.chains)
             .jwt(ad_hoc.jwt)
             .twitter(ad_hoc.twitter)
+            .referral(ad_hoc.referral)
+            .achievements(ad_hoc.achievements)
+            .draws(ad_hoc.draws)
             .build()
             .map_err(|e| serde::de::Error::custom(e.to_string()))
     }
@@ -94,23 +111,72 @@ impl ConfigService {
 #[buildstructor::buildstructor]
 impl ConfigService {
     #[builder]
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         environment: Option<AppEnvironment>,
         database: Option<DatabaseConfig>,
         redis: Option<RedisConfig>,
+        message_bus: Option<MessageBusConfig>,
         graphql: Option<GQLConfig>,
         chains: Option<Vec<ChainConfig>>,
         jwt: Option<JWTConfig>,
         twitter: Option<TwitterConfig>,
+        referral: Option<ReferralConfig>,
+        achievements: Option<AchievementConfig>,
+        draws: Option<DrawConfig>,
     ) -> Result<Self, Error> {
+        // The message bus backend aside, the cache, sessions and leaderboards live in Redis
+        let redis = redis.unwrap_or_default();
+        if redis.url.is_empty() {
+            return Err(Report::new(Error::ConfigInvalid)
+                .attach_printable("Redis is required by the cache, sessions and leaderboards, whatever the message bus backend"));
+        }
+
+        // SIWE messages are bound to the domain and URI, accepting any of them would allow replays across sites
+        let graphql = graphql.unwrap_or_default();
+        if graphql.siwe.domain.is_empty() || graphql.siwe.uri.is_empty() {
+            return Err(Report::new(Error::ConfigInvalid)
+                .attach_printable("Sign-In with Ethereum requires both the domain and the URI"));
+        }
+
+        let draws = draws.unwrap_or_default();
+        if draws.tier_shares_bps.iter().sum::<u32>() > 10_000 {
+            return Err(Report::new(Error::ConfigInvalid)
+                .attach_printable("Draw tier shares exceed the prize pool"));
+        }
+
+        let referral = referral.unwrap_or_default();
+        if let Some((asset, _)) = referral.rewards_bps.iter().find(|(_, bps)| **bps > 10_000) {
+            return Err(Report::new(Error::ConfigInvalid)
+                .attach_printable(format!("Referral reward of {asset} exceeds the amount spent")));
+        }
+
+        // Unlocks are stored under the rule key, it must fit the column and identify a single rule
+        let achievements = achievements.unwrap_or_default();
+        for (index, rule) in achievements.rules.iter().enumerate() {
+            if rule.key.is_empty() || rule.key.chars().count() > ACHIEVEMENT_KEY_MAX_LENGTH {
+                return Err(Report::new(Error::ConfigInvalid)
+                    .attach_printable(format!("Achievement key {:?} must be 1 to {ACHIEVEMENT_KEY_MAX_LENGTH} characters", rule.key)));
+            }
+
+            if achievements.rules[..index].iter().any(|other| other.key == rule.key) {
+                return Err(Report::new(Error::ConfigInvalid)
+                    .attach_printable(format!("Achievement key {} is declared twice", rule.key)));
+            }
+        }
+
         let inner = ConfigServiceInner {
             environment: environment.unwrap_or_default(),
             database: database.unwrap_or_default(),
-            redis: redis.unwrap_or_default(),
-            graphql: graphql.unwrap_or_default(),
+            redis,
+            message_bus: message_bus.unwrap_or_default(),
+            graphql,
             chains: chains.unwrap_or_default(),
             jwt: jwt.unwrap_or_default(),
             twitter: twitter.unwrap_or_default(),
+            referral,
+            achievements,
+            draws,
         };
 
         Ok(ConfigService(Arc::new(inner)))
@@ -145,6 +211,8 @@ pub struct ChainConfig {
     pub explorer_url: String,
     pub contracts: HashMap<String, Address>,
     pub keeper: KeeperConfig,
+    #[serde(default)]
+    pub reconciliation: ReconciliationConfig,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
@@ -152,6 +220,130 @@ pub struct GQLConfig {
     pub listen: String,
     pub endpoint: String,
     pub subscription_endpoint: String,
+    #[serde(default)]
+    pub siwe: SiweConfig,
+}
+
+/// Sign-In with Ethereum messages are only accepted for this domain and URI
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct SiweConfig {
+    pub domain: String,
+    pub uri: String,
+    /// Seconds a login nonce can be used for
+    pub nonce_ttl: u64,
+    /// Keeps the deprecated `loginWithSignature` available, its signed timestamps can be replayed
+    pub allow_signature_login: bool,
+}
+
+impl Default for SiweConfig {
+    fn default() -> Self {
+        Self {
+            domain: String::new(),
+            uri: String::new(),
+            nonce_ttl: 300,
+            allow_signature_login: false,
+        }
+    }
+}
+
+/// Rewards accrued to referrers on the purchases of the accounts they referred
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ReferralConfig {
+    /// Link shared by referrers, their code is appended to it
+    pub link_base_url: String,
+    /// Basis points of the amount spent accrued to the referrer, per ticket asset address.
+    /// Purchases in other assets are not rewarded
+    pub rewards_bps: HashMap<String, u32>,
+}
+
+impl ReferralConfig {
+    /// Reward rate of purchases made in the asset, if rewarded
+    pub fn reward_bps(&self, asset_address: &str) -> Option<u32> {
+        self.rewards_bps
+            .iter()
+            .find(|(address, _)| address.eq_ignore_ascii_case(asset_address))
+            .map(|(_, bps)| *bps)
+    }
+}
+
+/// Payout structure of the lotteries paying several places
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct DrawConfig {
+    /// Basis points of the prize pool paid to each place, the first entry being the first
+    /// place. Used for payouts whose amount isn't known
+    pub tier_shares_bps: Vec<u32>,
+}
+
+impl DrawConfig {
+    /// Configured percentage of the prize pool paid to the place, if any
+    pub fn tier_share(&self, tier: i32) -> Option<Decimal> {
+        let index = usize::try_from(tier).ok()?.checked_sub(1)?;
+        let bps = *self.tier_shares_bps.get(index)?;
+
+        Some(Decimal::from(bps) / Decimal::ONE_HUNDRED)
+    }
+}
+
+/// Length of the `account_achievement.achievement` column
+const ACHIEVEMENT_KEY_MAX_LENGTH: usize = 40;
+
+/// Achievements unlocked by accounts, evaluated as their tickets and wins are indexed
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct AchievementConfig {
+    pub rules: Vec<AchievementRule>,
+}
+
+impl AchievementConfig {
+    pub fn rule(&self, key: &str) -> Option<&AchievementRule> {
+        self.rules.iter().find(|rule| rule.key == key)
+    }
+}
+
+impl Default for AchievementConfig {
+    fn default() -> Self {
+        let rule = |key: &str, name: &str, description: &str, metric, threshold| AchievementRule {
+            key: key.to_string(),
+            name: name.to_string(),
+            description: description.to_string(),
+            metric,
+            threshold,
+        };
+
+        Self {
+            rules: vec![
+                rule("first_ticket", "First ticket", "Buy a ticket", AchievementMetric::TotalTickets, 1),
+                rule("hundred_tickets", "Regular", "Buy 100 tickets", AchievementMetric::TotalTickets, 100),
+                rule("ten_lotteries", "Explorer", "Enter 10 lotteries", AchievementMetric::LotteriesEntered, 10),
+                rule("weekly_streak", "On a roll", "Buy tickets 7 days in a row", AchievementMetric::PlayStreakDays, 7),
+                rule("first_win", "Lucky", "Win a draw", AchievementMetric::Wins, 1),
+            ],
+        }
+    }
+}
+
+/// An achievement is unlocked once the metric of the account reaches the threshold
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct AchievementRule {
+    /// Identifies the achievement once unlocked, it must not change
+    pub key: String,
+    pub name: String,
+    pub description: String,
+    pub metric: AchievementMetric,
+    pub threshold: i64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AchievementMetric {
+    TotalTickets,
+    LotteriesEntered,
+    Wins,
+    /// Longest run of consecutive days with a purchase, in UTC
+    PlayStreakDays,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
@@ -165,9 +357,79 @@ pub struct DatabaseConfigServer {
     pub read_only: bool,
 }
 
+/// Redis backs the cache, the sessions and the leaderboards, and the message bus with the Redis backend
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct RedisConfig {
     pub url: String,
+    #[serde(default)]
+    pub streams: RedisStreamsConfig,
+}
+
+/// Transport the broker events are delivered to the subscribers with.
+///
+/// Redis stays required with every backend, see `RedisConfig`.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageBusBackend {
+    /// Redis Streams, see `RedisStreamsConfig`
+    #[default]
+    Redis,
+    /// Within the process only, for tests and single binary deployments
+    Memory,
+    /// Postgres `LISTEN/NOTIFY`, keeps the broker events out of Redis
+    Postgres,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct MessageBusConfig {
+    pub backend: MessageBusBackend,
+    /// Days the broker events can be replayed for, older events are deleted
+    pub retention_days: u64,
+}
+
+impl Default for MessageBusConfig {
+    fn default() -> Self {
+        Self {
+            backend: MessageBusBackend::default(),
+            retention_days: 7,
+        }
+    }
+}
+
+/// Configuration of the Redis Streams the broker events are appended to
+///
+/// Without `group` every process reads every event, which is what GraphQL replicas need.
+/// Every consumer group receives every event too, while consumers of the same group share them,
+/// so a group only suits workers which must handle each event once.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct RedisStreamsConfig {
+    /// Approximate amount of events kept per stream
+    pub max_len: usize,
+    /// Consumer group this process reads the streams with, `None` reads them without group
+    pub group: Option<String>,
+    /// Name of this process within its consumer group, unique per process when `None`
+    pub consumer: Option<String>,
+    /// Maximum amount of events read at once
+    pub batch_size: usize,
+    /// Milliseconds a read waits for new events
+    pub block_ms: usize,
+    /// Milliseconds an entry stays unacknowledged before a starting consumer of the group claims it
+    pub claim_idle_ms: usize,
+}
+
+impl Default for RedisStreamsConfig {
+    fn default() -> Self {
+        Self {
+            max_len: 100_000,
+            group: None,
+            consumer: None,
+            batch_size: 100,
+            block_ms: 5_000,
+            claim_idle_ms: 60_000,
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
@@ -208,6 +470,28 @@ pub struct KeeperConfig {
     pub private_key: String,
 }
 
+/// Configuration of the periodic comparison between indexed lotteries and the chain
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct ReconciliationConfig {
+    /// Seconds between two reconciliation runs
+    pub interval: u64,
+    /// Amount of participants per lottery checked against `numberOfTicketsByAddress`
+    pub participants_sample: i64,
+    /// Correct repairable drifts (status, winner) instead of only reporting them
+    pub auto_repair: bool,
+}
+
+impl Default for ReconciliationConfig {
+    fn default() -> Self {
+        Self {
+            interval: 300,
+            participants_sample: 10,
+            auto_repair: false,
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct TasksConfig {
     pub ohlc_archive: TaskConfig,
@@ -245,6 +529,27 @@ pub struct AWSS3Config {
 pub struct JWTConfig {
     pub private_key: String,
     pub public_key: String,
+    #[serde(default)]
+    pub ttl: TokenTtlConfig,
+}
+
+/// Lifetimes of the issued tokens, in seconds
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct TokenTtlConfig {
+    /// Access tokens can't be refreshed, keep them short-lived
+    pub access: i64,
+    /// Refresh tokens are rotated on use, the session expires after being unused that long
+    pub refresh: i64,
+}
+
+impl Default for TokenTtlConfig {
+    fn default() -> Self {
+        Self {
+            access: 900,
+            refresh: 2_592_000,
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
@@ -1,28 +1,36 @@
 use std::sync::Arc;
 
 use chrono::Utc;
-use entity::{draw::DrawModel, prize::PrizeStatus};
+use entity::{draw::{DrawModel, DrawStatus}, prize::PrizeStatus, ticket::TicketModel};
 use lib::error::Error;
+use rust_decimal::Decimal;
 use serenity::async_trait;
 use error_stack::{Result, ResultExt};
 use store::DrawStore;
 use types::{CreateDraw, UpdateDraw};
 use uuid::Uuid;
-use crate::{chain::types::EventContext, prelude::{ServiceProvider, StoreService}, prize::{store::PrizeStore, types::UpdatePrize}, services::ServiceFactory, store::service::DatabaseTransaction, transaction::{service::TransactionService, types::{CreateTransaction, TransactionSideEffect}}};
+use tracing::warn;
+use crate::{achievement::{AchievementService, WIN_METRICS}, chain::{traits::string::ToHexString, types::EventContext}, config::service::ConfigService, message_broker::{channels, dedup_key, MessageBrokerService}, prelude::{ServiceProvider, StoreService}, prize::{prize_pool, store::PrizeStore, types::UpdatePrize}, services::ServiceFactory, store::service::DatabaseTransaction, ticket::store::TicketStore, transaction::{service::TransactionService, types::{CreateTransaction, TransactionSideEffect}}};
 
 pub mod store;
 pub mod types;
 
 pub struct DrawService {
    pub store: Arc<StoreService>,
+   pub config: Arc<ConfigService>,
    pub transaction_service: Arc<TransactionService>,
+   pub message_broker: Arc<MessageBrokerService>,
+   pub achievement_service: Arc<AchievementService>,
 }
 
 impl DrawService {
-    pub fn new(store: Arc<StoreService>, transaction_service: Arc<TransactionService>) -> Self {
+    pub fn new(store: Arc<StoreService>, config: Arc<ConfigService>, transaction_service: Arc<TransactionService>, message_broker: Arc<MessageBrokerService>, achievement_service: Arc<AchievementService>) -> Self {
         Self {
             store,
+            config,
             transaction_service,
+            message_broker,
+            achievement_service,
         }
     }
     
@@ -34,13 +42,21 @@ impl DrawService {
     ) -> Result<DrawModel, Error> {
         let draw = DrawStore::create(db_tx.as_mut(), input).await?;
         
+        let side_effects = vec![TransactionSideEffect::new(draw.id, "draw")];
+        self.transaction_service.record_side_effects(context, side_effects, db_tx).await?;
+        
         Ok(draw)
     }
     
+    /// Record a payout of the lottery.
+    ///
+    /// Payouts fill pending draws in tier order, once none is left every further payout
+    /// opens a draw for the next place. A payout event is only recorded once, replaying it
+    /// returns the draw it filled.
     pub async fn mark_winner_as_drawn(
         &self,
         lottery_id: Uuid,
-        input: UpdateDraw,
+        mut input: UpdateDraw,
         context: Option<EventContext>,
         db_tx: &mut DatabaseTransaction<'_>,
     ) -> Result<DrawModel, Error> {
@@ -49,14 +65,137 @@ impl DrawService {
             ..Default::default()
         };
         
-        let prize = PrizeStore::find_by_lottery_id(db_tx.as_mut(), lottery_id.clone()).await?;
-        let draw = DrawStore::find_by_lottery_id(db_tx.as_mut(), lottery_id.clone()).await?;
+        if let Some(context) = context.as_ref() {
+            input.transaction_hash = Some(context.transaction_hash.to_hex_string());
+            input.log_index = Some(context.log_index.as_u32() as i32);
+        }
+        
+        if let Some(transaction_hash) = input.transaction_hash.clone() {
+            let paid = DrawStore::find_paid_by_lottery_id_and_transaction_hash(db_tx.as_mut(), lottery_id, transaction_hash).await?;
+            if let Some(draw) = paid.iter().find(|draw| input.log_index.is_some() && draw.log_index == input.log_index) {
+                return Ok(draw.clone());
+            }
+            
+            // Places paid by the same transaction share its random number, which can only
+            // designate the ticket of one of them
+            if !paid.is_empty() {
+                input.winning_number = None;
+            }
+        }
+        
+        let prize = PrizeStore::find_by_lottery_id(db_tx.as_mut(), lottery_id).await?;
+        let prizes = PrizeStore::find_all_by_lottery_id(db_tx.as_mut(), lottery_id).await?;
+        let draw = match DrawStore::try_find_pending_by_lottery_id(db_tx.as_mut(), lottery_id).await? {
+            Some(draw) => draw,
+            None => {
+                let draws = DrawStore::find_all_by_lottery_id(db_tx.as_mut(), lottery_id).await?;
+                let dto = CreateDraw {
+                    lottery_id,
+                    status: DrawStatus::Pending,
+                    tier: draws.iter().map(|draw| draw.tier).max().unwrap_or(0) + 1,
+                };
+                
+                DrawStore::create(db_tx.as_mut(), dto).await?
+            }
+        };
+        
+        let mut input = self.resolve_winning_ticket(lottery_id, input, db_tx).await?;
+        
+        // Sponsor contributions in the asset of the pool are paid out together with it
+        let pool = prize_pool(&prizes, prize.prize_asset);
+        let (amount, share) = payout(input.amount, input.share, self.config.draws.tier_share(draw.tier), pool);
+        input.amount = amount;
+        input.share = share;
+        
+        let mut side_effects = Vec::new();
+        for prize in prizes {
+            let prize = PrizeStore::update(db_tx.as_mut(), prize.id, prize_dto.clone()).await?;
+            side_effects.push(TransactionSideEffect::new(prize.id, "prize"));
+        }
         
-        let prize = PrizeStore::update(db_tx.as_mut(), prize.id, prize_dto).await?;
         let draw = DrawStore::update(db_tx.as_mut(), draw.id, input).await?;
+        side_effects.insert(0, TransactionSideEffect::new(draw.id, "draw"));
+        
+        if let Some(winner) = draw.winner {
+            for unlock in self.achievement_service.evaluate(winner, &WIN_METRICS, context.as_ref(), db_tx).await? {
+                side_effects.push(TransactionSideEffect::new(unlock.id, "account_achievement"));
+            }
+        }
+        
+        let dedup_key = dedup_key(channels::WINNER_DRAWN, draw.id, context.as_ref());
+        self.message_broker.enqueue(channels::WINNER_DRAWN, &draw, dedup_key, db_tx).await?;
+        
+        self.transaction_service.record_side_effects(context, side_effects, db_tx).await?;
         
         Ok(draw)
     }
+    
+    /// Link the draw to the purchase owning the winning ticket number.
+    ///
+    /// When the winning number is unknown, or it doesn't belong to the paid winner, the draw
+    /// is left without number nor purchase rather than guessing one.
+    async fn resolve_winning_ticket(
+        &self,
+        lottery_id: Uuid,
+        mut input: UpdateDraw,
+        db_tx: &mut DatabaseTransaction<'_>,
+    ) -> Result<UpdateDraw, Error> {
+        let Some(number) = input.winning_number else {
+            return Ok(input);
+        };
+
+        let ticket = TicketStore::try_find_by_lottery_id_and_number(db_tx.as_mut(), lottery_id, number).await?;
+        input.winning_ticket = winning_ticket(ticket.as_ref(), input.winner);
+
+        if input.winning_ticket.is_none() {
+            warn!(
+                lottery_id = lottery_id.to_string(),
+                winning_number = number,
+                "Winning number doesn't belong to the winner",
+            );
+            input.winning_number = None;
+        }
+
+        Ok(input)
+    }
+}
+
+/// Purchase owning the winning number, only when it belongs to the paid winner
+fn winning_ticket(ticket: Option<&TicketModel>, winner: Option<Uuid>) -> Option<Uuid> {
+    ticket
+        .filter(|ticket| winner.is_none_or(|winner| winner == ticket.account_id))
+        .map(|ticket| ticket.id)
+}
+
+/// Amount and share of the pool paid to a place.
+///
+/// The share is computed from the paid amount when known, the configured share of the
+/// place is used otherwise, and gives the amount when it wasn't paid by an event.
+fn payout(
+    amount: Option<Decimal>,
+    share: Option<Decimal>,
+    tier_share: Option<Decimal>,
+    pool: Decimal,
+) -> (Option<Decimal>, Option<Decimal>) {
+    let share = share
+        .or_else(|| amount.and_then(|amount| share_of_pool(amount, pool)))
+        .or(tier_share);
+    let amount = amount.or_else(|| share.and_then(|share| amount_of_pool(share, pool)));
+
+    (amount, share)
+}
+
+/// Percentage of the prize pool represented by the paid amount
+fn share_of_pool(amount: Decimal, pool: Decimal) -> Option<Decimal> {
+    amount
+        .checked_mul(Decimal::ONE_HUNDRED)?
+        .checked_div(pool)
+        .map(|share| share.round_dp(2))
+}
+
+/// Amount of the prize pool represented by the percentage
+fn amount_of_pool(share: Decimal, pool: Decimal) -> Option<Decimal> {
+    pool.checked_mul(share)?.checked_div(Decimal::ONE_HUNDRED)
 }
 
 #[async_trait]
@@ -66,7 +205,74 @@ impl ServiceFactory for DrawService {
         
         Ok(Self {
             store,
+            config: services.get_service_unchecked::<ConfigService>().await,
             transaction_service: services.get_service_unchecked::<TransactionService>().await,
+            message_broker: services.get_service_unchecked::<MessageBrokerService>().await,
+            achievement_service: services.get_service_unchecked::<AchievementService>().await,
         })
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticket(account_id: Uuid) -> TicketModel {
+        TicketModel {
+            id: Uuid::new_v4(),
+            lottery_id: Uuid::new_v4(),
+            account_id,
+            ticket_price: Decimal::ONE,
+            ticket_asset: Uuid::new_v4(),
+            amount: 5,
+            first_number: 1,
+            last_number: 5,
+            block_number: 1,
+            log_index: 0,
+            purchased_at: Utc::now(),
+            transaction_hash: String::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_winning_ticket() {
+        let winner = Uuid::new_v4();
+        let owned = ticket(winner);
+        let other = ticket(Uuid::new_v4());
+
+        assert_eq!(winning_ticket(Some(&owned), Some(winner)), Some(owned.id));
+        assert_eq!(winning_ticket(Some(&owned), None), Some(owned.id));
+        // Never fall back to another purchase
+        assert_eq!(winning_ticket(Some(&other), Some(winner)), None);
+        assert_eq!(winning_ticket(None, Some(winner)), None);
+    }
+
+    #[test]
+    fn test_payout() {
+        let pool = Decimal::from(1_000);
+        let tier_share = Some(Decimal::from(30));
+
+        // Paid amount wins over the configured share
+        assert_eq!(
+            payout(Some(Decimal::from(500)), None, tier_share, pool),
+            (Some(Decimal::from(500)), Some(Decimal::from(50))),
+        );
+        // Configured share gives the amount when unknown
+        assert_eq!(payout(None, None, tier_share, pool), (Some(Decimal::from(300)), Some(Decimal::from(30))));
+        // Explicit share is kept
+        assert_eq!(
+            payout(Some(Decimal::from(500)), Some(Decimal::from(40)), tier_share, pool),
+            (Some(Decimal::from(500)), Some(Decimal::from(40))),
+        );
+        assert_eq!(payout(None, None, None, pool), (None, None));
+        // Empty pool has no share
+        assert_eq!(payout(Some(Decimal::from(5)), None, None, Decimal::ZERO), (Some(Decimal::from(5)), None));
+    }
+
+    #[test]
+    fn test_share_of_pool_rounds_to_cents() {
+        assert_eq!(share_of_pool(Decimal::ONE, Decimal::from(3)), Some(Decimal::new(3333, 2)));
+    }
+}
@@ -21,11 +21,40 @@ impl DrawStore {
     define_find_optional_fns!(
             find_by_lottery_id,
             try_find_by_lottery_id,
-            "SELECT * FROM draw WHERE lottery_id = $1",
+            "SELECT * FROM draw WHERE lottery_id = $1 ORDER BY tier ASC LIMIT 1",
             Uuid,
             DrawModel
         );
         
+    define_find_all_fns!(
+        find_all_by_lottery_id,
+        "SELECT * FROM draw WHERE lottery_id = $1 ORDER BY tier ASC",
+        Uuid,
+        DrawModel
+    );
+    
+    define_find_all_fns!(
+        find_all_by_lottery_ids,
+        "SELECT * FROM draw WHERE lottery_id = ANY($1) ORDER BY tier ASC",
+        Vec<Uuid>,
+        DrawModel
+    );
+    
+    define_find_optional_fns!(
+        find_pending_by_lottery_id,
+        try_find_pending_by_lottery_id,
+        "SELECT * FROM draw WHERE lottery_id = $1 AND status = 'PENDING' ORDER BY tier ASC LIMIT 1",
+        Uuid,
+        DrawModel
+    );
+    
+    define_find_all_fns!(
+        find_winners_by_lottery_id,
+        "SELECT * FROM draw WHERE lottery_id = $1 AND status = 'COMPLETED' ORDER BY tier ASC",
+        Uuid,
+        DrawModel
+    );
+        
         define_find_optional_fns!(
             find_by_transaction_hash,
             try_find_by_transaction_hash,
@@ -40,6 +69,36 @@ impl DrawStore {
         DrawModel
     );
         
+    /// Find the payouts of the lottery recorded from the transaction
+    #[allow(clippy::manual_async_fn)]
+    pub fn find_paid_by_lottery_id_and_transaction_hash<'a, 'c, Conn>(
+        conn: Conn,
+        lottery_id: Uuid,
+        transaction_hash: String,
+    ) -> impl Future<Output = Result<Vec<DrawModel>, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn.acquire().await.change_context(Error::Store)?;
+
+            let query = r#"
+                SELECT * FROM draw
+                WHERE lottery_id = $1 AND transaction_hash = $2 AND status = 'COMPLETED'
+                ORDER BY tier ASC
+            "#;
+
+            let draws = sqlx::query_as(query)
+                .bind(lottery_id)
+                .bind(transaction_hash)
+                .fetch_all(&mut *conn)
+                .await
+                .change_context(Error::Store)?;
+
+            Ok(draws)
+        }
+    }
+
     // Create a new draw
     #[allow(clippy::manual_async_fn)]
     pub fn create<'a, 'c, Conn>(
@@ -57,9 +116,9 @@ impl DrawStore {
 
             let query = r#"
                 INSERT INTO draw (
-                    id, lottery_id, status, created_at, updated_at
+                    id, lottery_id, status, tier, created_at, updated_at
                 )
-                VALUES ($1, $2, $3, $4, $5)
+                VALUES ($1, $2, $3, $4, $5, $6)
                 RETURNING *
             "#;
 
@@ -67,6 +126,7 @@ impl DrawStore {
                 .bind(Uuid::new_v4()) // Generate a new UUID for the draw
                 .bind(input.lottery_id) // Bind the lottery ID
                 .bind(input.status) // Bind the status
+                .bind(input.tier) // Bind the tier
                 .bind(Utc::now()) // Bind the created_at timestamp
                 .bind(Utc::now()) // Bind the updated_at timestamp
                 .fetch_one(conn.as_mut())
@@ -99,6 +159,11 @@ impl DrawStore {
                     winner = COALESCE($3, winner),
                     transaction_hash = COALESCE($4, transaction_hash),
                     status = COALESCE($5, status),
+                    winning_number = COALESCE($6, winning_number),
+                    winning_ticket = COALESCE($7, winning_ticket),
+                    amount = COALESCE($8, amount),
+                    share = COALESCE($9, share),
+                    log_index = COALESCE($10, log_index),
                     updated_at = NOW()
                 WHERE id = $1
                 RETURNING *
@@ -110,6 +175,11 @@ impl DrawStore {
                 .bind(input.winner) // Bind the optional winning ticket ID
                 .bind(input.transaction_hash) // Bind the optional transaction hash
                 .bind(input.status) // Bind the optional status
+                .bind(input.winning_number) // Bind the optional winning ticket number
+                .bind(input.winning_ticket) // Bind the optional winning purchase ID
+                .bind(input.amount) // Bind the optional paid amount
+                .bind(input.share) // Bind the optional share of the prize pool
+                .bind(input.log_index) // Bind the optional log index of the payout event
                 .fetch_one(conn.as_mut())
                 .await
                 .change_context(Error::StoreUpdateFailed)?;
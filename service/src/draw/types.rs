@@ -8,6 +8,7 @@ use uuid::Uuid;
 pub struct CreateDraw {
     pub lottery_id: Uuid,
     pub status: DrawStatus,
+    pub tier: i32,
 }
 
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
@@ -15,5 +16,10 @@ pub struct UpdateDraw {
     pub draw_date: Option<DateTime<Utc>>,
     pub winner: Option<Uuid>,
     pub transaction_hash: Option<String>,
+    pub log_index: Option<i32>,
     pub status: Option<DrawStatus>,
+    pub winning_number: Option<i32>,
+    pub winning_ticket: Option<Uuid>,
+    pub amount: Option<Decimal>,
+    pub share: Option<Decimal>,
 }
\ No newline at end of file
@@ -0,0 +1,408 @@
+pub mod store;
+pub mod types;
+
+use crate::asset::store::AssetStore;
+use crate::cache::service::CacheService;
+use crate::message_broker::store::{BrokerEventStore, OutboxStore};
+use crate::message_broker::{channels, BrokerEvent, Event};
+use crate::prelude::{ServiceProvider, StoreService};
+use crate::prize::store::PrizeStore;
+use crate::services::ServiceFactory;
+use chrono::{DateTime, Utc};
+use entity::draw::{DrawModel, DrawStatus};
+use entity::ticket::TicketModel;
+use error_stack::{Report, Result, ResultExt};
+use lib::error::Error;
+use redis::aio::ConnectionManager;
+use redis::{AsyncCommands, Script};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use serenity::async_trait;
+use sqlx::types::Decimal;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use store::LeaderboardStore;
+use types::{LeaderboardEntry, LeaderboardKind, LeaderboardPeriod};
+use uuid::Uuid;
+
+/// How long an applied event is remembered, so it is never counted twice
+const APPLIED_EVENT_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Held while the boards are rebuilt, events are not applied meanwhile
+const REBUILD_LOCK_KEY: &str = "leaderboard:rebuilding";
+
+/// Longest a rebuild can hold the lock, in case it stops without releasing it
+const REBUILD_LOCK_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Times a rebuild waits for the ticket and draw events still being published
+const REBUILD_ATTEMPTS: usize = 30;
+
+/// Channels the boards are computed from
+const CHANNELS: [&str; 2] = [channels::TICKET_BOUGHT.name(), channels::WINNER_DRAWN.name()];
+
+/// Apply the increments of an event atomically, unless the boards are being rebuilt (-1) or the
+/// event is already counted (0), either by a previous apply or by the last rebuild.
+///
+/// KEYS: rebuild lock, rebuilt event id, applied event, cursor, then the boards.
+/// ARGV: event id, applied event TTL, then member, score and TTL (0 for none) per board.
+const APPLY_SCRIPT: &str = r#"
+if redis.call('EXISTS', KEYS[1]) == 1 then
+    return -1
+end
+if tonumber(ARGV[1]) <= tonumber(redis.call('GET', KEYS[2]) or '0') then
+    return 0
+end
+if not redis.call('SET', KEYS[3], 1, 'NX', 'EX', ARGV[2]) then
+    return 0
+end
+if tonumber(ARGV[1]) > tonumber(redis.call('GET', KEYS[4]) or '0') then
+    redis.call('SET', KEYS[4], ARGV[1])
+end
+for i = 5, #KEYS do
+    local arg = (i - 5) * 3 + 3
+    redis.call('ZINCRBY', KEYS[i], ARGV[arg + 1], ARGV[arg])
+    if tonumber(ARGV[arg + 2]) > 0 then
+        redis.call('EXPIRE', KEYS[i], ARGV[arg + 2])
+    end
+end
+return 1
+"#;
+
+/// Key of the board, `None` asset ranking across every asset
+fn board_key(kind: LeaderboardKind, bucket: &str, asset_id: Option<Uuid>) -> String {
+    match asset_id {
+        Some(asset_id) => format!("leaderboard:board:{kind}:{bucket}:{asset_id}"),
+        None => format!("leaderboard:board:{kind}:{bucket}:all"),
+    }
+}
+
+fn cursor_key(channel: &str) -> String {
+    format!("leaderboard:cursor:{channel}")
+}
+
+/// Id of the last event of the channel counted by the last rebuild
+fn rebuilt_key(channel: &str) -> String {
+    format!("leaderboard:rebuilt:{channel}")
+}
+
+/// Score of an amount of the asset, in whole units so that it fits the precision of a score
+fn display_score(amount: Decimal, decimals: Option<i16>) -> f64 {
+    let decimals = decimals.unwrap_or_default().clamp(0, 28) as u32;
+    let unit = Decimal::from_i128_with_scale(10i128.pow(decimals), 0);
+
+    amount.checked_div(unit).and_then(|amount| amount.to_f64()).unwrap_or_default()
+}
+
+/// Score increment of a board
+struct Increment {
+    key: String,
+    account_id: Uuid,
+    score: f64,
+    ttl: Option<Duration>,
+}
+
+/// Increment the score of the account on the boards of every period containing the date
+fn increments(kind: LeaderboardKind, asset_id: Option<Uuid>, account_id: Uuid, score: f64, date: DateTime<Utc>) -> Vec<Increment> {
+    LeaderboardPeriod::ALL
+        .iter()
+        .map(|period| Increment {
+            key: board_key(kind, &period.bucket(date), asset_id),
+            account_id,
+            score,
+            ttl: period.ttl(),
+        })
+        .collect()
+}
+
+/// Rankings of the accounts per period, kept in Redis sorted sets.
+///
+/// Boards are updated from the broker events, and can be rebuilt from Postgres at any time.
+/// Amounts are scored in whole units of their asset.
+pub struct LeaderboardService {
+    store: Arc<StoreService>,
+    cache: Arc<CacheService>,
+}
+
+impl LeaderboardService {
+    pub fn new(store: Arc<StoreService>, cache: Arc<CacheService>) -> Self {
+        Self { store, cache }
+    }
+
+    /// Id of the last event of the channel applied to the boards
+    pub async fn cursor(&self, channel: &str) -> Result<Option<i64>, Error> {
+        let mut conn = self.cache.get_connection().await?;
+        conn.get(cursor_key(channel)).await.change_context(Error::Redis)
+    }
+
+    /// Apply the event to the boards, events already counted are skipped.
+    ///
+    /// Fails with `Error::LeaderboardRebuilding` while the boards are rebuilt.
+    pub async fn apply(&self, event: &BrokerEvent) -> Result<(), Error> {
+        let increments = match &event.event {
+            Event::TicketBought(ticket) => self.record_purchase(ticket).await?,
+            Event::WinnerDrawn(draw) => self.record_win(draw).await?,
+            _ => return Ok(()),
+        };
+
+        let channel = event.event.channel();
+        let script = Script::new(APPLY_SCRIPT);
+        let mut invocation = script.prepare_invoke();
+        invocation
+            .key(REBUILD_LOCK_KEY)
+            .key(rebuilt_key(channel))
+            .key(format!("leaderboard:applied:{}", event.id))
+            .key(cursor_key(channel))
+            .arg(event.id)
+            .arg(APPLIED_EVENT_TTL.as_secs());
+        for increment in increments {
+            invocation
+                .key(increment.key)
+                .arg(increment.account_id.to_string())
+                .arg(increment.score)
+                .arg(increment.ttl.map(|ttl| ttl.as_secs()).unwrap_or_default());
+        }
+
+        let mut conn = self.cache.get_connection().await?;
+        let applied: i64 = invocation.invoke_async(&mut conn).await.change_context(Error::Redis)?;
+        if applied < 0 {
+            return Err(Report::new(Error::LeaderboardRebuilding)
+                .attach_printable(format!("Event {} is applied once the rebuild completes", event.id)));
+        }
+
+        Ok(())
+    }
+
+    async fn record_purchase(&self, ticket: &TicketModel) -> Result<Vec<Increment>, Error> {
+        let asset = AssetStore::find_by_id(self.store.read(), ticket.ticket_asset).await?;
+        let spent = display_score(ticket.ticket_price * Decimal::from(ticket.amount), asset.decimals);
+        let tickets = ticket.amount as f64;
+
+        let mut all = increments(LeaderboardKind::TopSpenders, Some(ticket.ticket_asset), ticket.account_id, spent, ticket.purchased_at);
+        all.extend(increments(LeaderboardKind::MostTickets, Some(ticket.ticket_asset), ticket.account_id, tickets, ticket.purchased_at));
+        all.extend(increments(LeaderboardKind::MostTickets, None, ticket.account_id, tickets, ticket.purchased_at));
+
+        Ok(all)
+    }
+
+    async fn record_win(&self, draw: &DrawModel) -> Result<Vec<Increment>, Error> {
+        let (Some(winner), Some(amount)) = (draw.winner, draw.amount) else {
+            return Ok(Vec::new());
+        };
+        if draw.status != DrawStatus::Completed {
+            return Ok(Vec::new());
+        }
+
+        // Draws pay out of the ticket-funded prize, in its asset
+        let prize = PrizeStore::find_by_lottery_id(self.store.read(), draw.lottery_id).await?;
+        let asset = AssetStore::find_by_id(self.store.read(), prize.prize_asset).await?;
+        let date = draw.draw_date.unwrap_or(draw.updated_at);
+
+        Ok(increments(LeaderboardKind::BiggestWinners, Some(prize.prize_asset), winner, display_score(amount, asset.decimals), date))
+    }
+
+    /// Current ranking of the board, amounts are only ranked within an asset
+    pub async fn top(
+        &self,
+        kind: LeaderboardKind,
+        period: LeaderboardPeriod,
+        asset_id: Option<Uuid>,
+        limit: u32,
+    ) -> Result<Vec<LeaderboardEntry>, Error> {
+        if asset_id.is_none() && kind != LeaderboardKind::MostTickets {
+            return Err(Report::new(Error::LeaderboardAssetRequired));
+        }
+
+        let key = board_key(kind, &period.bucket(Utc::now()), asset_id);
+        let stop = limit.max(1) as isize - 1;
+
+        let mut conn = self.cache.get_connection().await?;
+        let scores: Vec<(String, f64)> = conn
+            .zrevrange_withscores(&key, 0, stop)
+            .await
+            .change_context(Error::Redis)?;
+
+        let entries = scores
+            .into_iter()
+            .filter_map(|(account_id, score)| Some((Uuid::from_str(&account_id).ok()?, score)))
+            .enumerate()
+            .map(|(index, (account_id, score))| LeaderboardEntry {
+                rank: index as u32 + 1,
+                account_id,
+                score: Decimal::from_f64(score).unwrap_or_default(),
+            })
+            .collect();
+
+        Ok(entries)
+    }
+
+    /// Replace the boards of the current periods with the totals stored in Postgres.
+    ///
+    /// Events are not applied meanwhile, and those counted by the totals are skipped afterwards.
+    /// Returns the number of scores written.
+    pub async fn rebuild(&self) -> Result<usize, Error> {
+        let mut conn = self.cache.get_connection().await?;
+
+        let locked: Option<String> = redis::cmd("SET")
+            .arg(REBUILD_LOCK_KEY)
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(REBUILD_LOCK_TTL.as_secs())
+            .query_async(&mut conn)
+            .await
+            .change_context(Error::Redis)?;
+        if locked.is_none() {
+            return Err(Report::new(Error::LeaderboardRebuilding));
+        }
+
+        let written = self.rebuild_locked(&mut conn).await;
+
+        // The lock expires anyway, should releasing it fail
+        let _: std::result::Result<(), _> = conn.del(REBUILD_LOCK_KEY).await;
+
+        written
+    }
+
+    async fn rebuild_locked(&self, conn: &mut ConnectionManager) -> Result<usize, Error> {
+        let now = Utc::now();
+        let (boards, last_event_ids) = self.snapshot(now).await?;
+
+        let mut keys: Vec<String> = Vec::new();
+        for pattern in ["leaderboard:board:*", "leaderboard:applied:*"] {
+            let mut iter = conn.scan_match::<_, String>(pattern).await.change_context(Error::Redis)?;
+            while let Some(key) = iter.next_item().await {
+                keys.push(key);
+            }
+        }
+
+        // Swap the boards at once, readers never see them partially rebuilt
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for key in keys {
+            pipe.del(key).ignore();
+        }
+
+        // Events up to the last stored ones are counted by the totals, the applied ones are forgotten
+        for channel in CHANNELS {
+            match last_event_ids.get(channel) {
+                Some(id) => {
+                    pipe.set(cursor_key(channel), *id).ignore();
+                    pipe.set(rebuilt_key(channel), *id).ignore();
+                }
+                None => {
+                    pipe.del(cursor_key(channel)).ignore();
+                    pipe.del(rebuilt_key(channel)).ignore();
+                }
+            }
+        }
+
+        let written = boards.len();
+        for increment in boards {
+            // Totals across assets are summed up
+            pipe.zincr(&increment.key, increment.account_id.to_string(), increment.score).ignore();
+            if let Some(ttl) = increment.ttl {
+                pipe.expire(&increment.key, ttl.as_secs() as usize).ignore();
+            }
+        }
+
+        pipe.query_async::<_, ()>(conn).await.change_context(Error::Redis)?;
+
+        Ok(written)
+    }
+
+    /// Scores of the current periods along with the id of the last event counted per channel.
+    ///
+    /// Read in a single snapshot once every ticket and draw event is stored, so that the scores
+    /// count exactly the events up to those ids.
+    async fn snapshot(&self, now: DateTime<Utc>) -> Result<(Vec<Increment>, HashMap<String, i64>), Error> {
+        let channels = CHANNELS.map(str::to_string).to_vec();
+
+        for _ in 0..REBUILD_ATTEMPTS {
+            let mut db_tx = self.store.begin_transaction().await?;
+            sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ READ ONLY")
+                .execute(db_tx.as_mut())
+                .await
+                .change_context(Error::Store)?;
+
+            if OutboxStore::has_unpersisted(db_tx.as_mut(), channels.clone()).await? {
+                self.store.rollback_transaction(db_tx).await?;
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                continue;
+            }
+
+            let decimals: HashMap<Uuid, Option<i16>> = AssetStore::find_all(db_tx.as_mut())
+                .await?
+                .into_iter()
+                .map(|asset| (asset.id, asset.decimals))
+                .collect();
+            let decimals = |asset_id: &Uuid| decimals.get(asset_id).copied().flatten();
+
+            let mut boards = Vec::new();
+            for period in LeaderboardPeriod::ALL {
+                let since = period.start(now);
+                let bucket = period.bucket(now);
+                let board = |kind, asset_id, account_id, score| Increment {
+                    key: board_key(kind, &bucket, asset_id),
+                    account_id,
+                    score,
+                    ttl: period.ttl(),
+                };
+
+                for total in LeaderboardStore::find_purchase_totals(db_tx.as_mut(), since).await? {
+                    let tickets = total.tickets as f64;
+                    boards.push(board(LeaderboardKind::TopSpenders, Some(total.asset_id), total.account_id, display_score(total.spent, decimals(&total.asset_id))));
+                    boards.push(board(LeaderboardKind::MostTickets, Some(total.asset_id), total.account_id, tickets));
+                    boards.push(board(LeaderboardKind::MostTickets, None, total.account_id, tickets));
+                }
+                for total in LeaderboardStore::find_winning_totals(db_tx.as_mut(), since).await? {
+                    boards.push(board(LeaderboardKind::BiggestWinners, Some(total.asset_id), total.account_id, display_score(total.amount, decimals(&total.asset_id))));
+                }
+            }
+
+            let last_event_ids = BrokerEventStore::find_last_ids(db_tx.as_mut(), channels).await?;
+            self.store.rollback_transaction(db_tx).await?;
+
+            return Ok((boards, last_event_ids.into_iter().collect()));
+        }
+
+        Err(Report::new(Error::LeaderboardEventsPending))
+    }
+}
+
+#[async_trait]
+impl ServiceFactory for LeaderboardService {
+    async fn factory(services: ServiceProvider) -> Result<Self, Error> {
+        let store = services.get_service_unchecked::<StoreService>().await;
+        let cache = services.get_service_unchecked::<CacheService>().await;
+
+        Ok(Self { store, cache })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amounts_are_scored_in_whole_units() {
+        assert_eq!(display_score(Decimal::from(1_500_000), Some(6)), 1.5);
+        assert_eq!(display_score(Decimal::from_i128_with_scale(2_000_000_000_000_000_000, 0), Some(18)), 2.0);
+    }
+
+    #[test]
+    fn amounts_without_decimals_are_scored_as_is() {
+        assert_eq!(display_score(Decimal::from(42), None), 42.0);
+        assert_eq!(display_score(Decimal::from(42), Some(0)), 42.0);
+    }
+
+    #[test]
+    fn every_period_is_incremented() {
+        let account_id = Uuid::new_v4();
+        let all = increments(LeaderboardKind::MostTickets, None, account_id, 3.0, Utc::now());
+
+        assert_eq!(all.len(), LeaderboardPeriod::ALL.len());
+        assert!(all.iter().all(|increment| increment.account_id == account_id && increment.score == 3.0));
+        assert_eq!(all.iter().filter(|increment| increment.ttl.is_none()).count(), 1);
+    }
+}
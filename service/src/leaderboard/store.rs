@@ -0,0 +1,75 @@
+use crate::leaderboard::types::{PurchaseTotal, WinningTotal};
+use chrono::{DateTime, Utc};
+use error_stack::{Result, ResultExt};
+use lib::error::Error;
+use sqlx::{Acquire, Postgres};
+use std::future::Future;
+
+pub struct LeaderboardStore;
+
+impl LeaderboardStore {
+    /// Tickets bought per account and asset, since the date if any
+    #[allow(clippy::manual_async_fn)]
+    pub fn find_purchase_totals<'a, 'c, Conn>(
+        conn: Conn,
+        since: Option<DateTime<Utc>>,
+    ) -> impl Future<Output = Result<Vec<PurchaseTotal>, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn.acquire().await.change_context(Error::Store)?;
+
+            let query = r#"
+                SELECT
+                    account_id,
+                    ticket_asset AS asset_id,
+                    SUM(ticket_price * amount) AS spent,
+                    SUM(amount)::BIGINT AS tickets
+                FROM ticket
+                WHERE $1::TIMESTAMPTZ IS NULL OR purchased_at >= $1
+                GROUP BY account_id, ticket_asset
+            "#;
+
+            sqlx::query_as(query)
+                .bind(since) // Bind the optional start of the period
+                .fetch_all(&mut *conn)
+                .await
+                .change_context(Error::Store)
+        }
+    }
+
+    /// Amount won in completed draws per account and prize asset, since the date if any
+    #[allow(clippy::manual_async_fn)]
+    pub fn find_winning_totals<'a, 'c, Conn>(
+        conn: Conn,
+        since: Option<DateTime<Utc>>,
+    ) -> impl Future<Output = Result<Vec<WinningTotal>, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn.acquire().await.change_context(Error::Store)?;
+
+            let query = r#"
+                SELECT
+                    draw.winner AS account_id,
+                    prize.prize_asset AS asset_id,
+                    SUM(draw.amount) AS amount
+                FROM draw
+                JOIN prize ON prize.lottery_id = draw.lottery_id AND prize.source = 'TICKETS'
+                WHERE draw.status = 'COMPLETED'
+                    AND draw.winner IS NOT NULL
+                    AND draw.amount IS NOT NULL
+                    AND ($1::TIMESTAMPTZ IS NULL OR draw.draw_date >= $1)
+                GROUP BY draw.winner, prize.prize_asset
+            "#;
+
+            sqlx::query_as(query)
+                .bind(since) // Bind the optional start of the period
+                .fetch_all(&mut *conn)
+                .await
+                .change_context(Error::Store)
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use async_graphql::Enum;
+use chrono::{DateTime, Datelike, Days, NaiveDate, Utc};
+use serde::Serialize;
+use sqlx::{prelude::FromRow, types::Decimal};
+use std::fmt::Display;
+use std::time::Duration;
+use uuid::Uuid;
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq, Debug, Serialize)]
+pub enum LeaderboardKind {
+    /// Amount spent on tickets
+    TopSpenders,
+    /// Number of tickets bought
+    MostTickets,
+    /// Amount won in completed draws
+    BiggestWinners,
+}
+
+impl Display for LeaderboardKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LeaderboardKind::TopSpenders => write!(f, "top_spenders"),
+            LeaderboardKind::MostTickets => write!(f, "most_tickets"),
+            LeaderboardKind::BiggestWinners => write!(f, "biggest_winners"),
+        }
+    }
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq, Debug, Serialize)]
+pub enum LeaderboardPeriod {
+    Daily,
+    Weekly,
+    Monthly,
+    AllTime,
+}
+
+impl LeaderboardPeriod {
+    pub const ALL: [LeaderboardPeriod; 4] = [
+        LeaderboardPeriod::Daily,
+        LeaderboardPeriod::Weekly,
+        LeaderboardPeriod::Monthly,
+        LeaderboardPeriod::AllTime,
+    ];
+
+    /// Name of the period containing the date, weeks being ISO weeks
+    pub fn bucket(&self, date: DateTime<Utc>) -> String {
+        match self {
+            LeaderboardPeriod::Daily => date.format("daily:%Y-%m-%d").to_string(),
+            LeaderboardPeriod::Weekly => date.format("weekly:%G-W%V").to_string(),
+            LeaderboardPeriod::Monthly => date.format("monthly:%Y-%m").to_string(),
+            LeaderboardPeriod::AllTime => "all_time".to_string(),
+        }
+    }
+
+    /// Start of the period containing the date, `None` for all-time
+    pub fn start(&self, date: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let day = date.date_naive();
+        let start = match self {
+            LeaderboardPeriod::Daily => day,
+            LeaderboardPeriod::Weekly => day - Days::new(day.weekday().num_days_from_monday() as u64),
+            LeaderboardPeriod::Monthly => NaiveDate::from_ymd_opt(day.year(), day.month(), 1)?,
+            LeaderboardPeriod::AllTime => return None,
+        };
+
+        start.and_hms_opt(0, 0, 0).map(|start| start.and_utc())
+    }
+
+    /// How long the board of a period is kept, the previous period stays readable meanwhile
+    pub fn ttl(&self) -> Option<Duration> {
+        const DAY: u64 = 24 * 60 * 60;
+
+        match self {
+            LeaderboardPeriod::Daily => Some(Duration::from_secs(2 * DAY)),
+            LeaderboardPeriod::Weekly => Some(Duration::from_secs(14 * DAY)),
+            LeaderboardPeriod::Monthly => Some(Duration::from_secs(62 * DAY)),
+            LeaderboardPeriod::AllTime => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LeaderboardEntry {
+    pub rank: u32,
+    pub account_id: Uuid,
+    pub score: Decimal,
+}
+
+/// Tickets bought by an account in an asset
+#[derive(Clone, Debug, FromRow)]
+pub struct PurchaseTotal {
+    pub account_id: Uuid,
+    pub asset_id: Uuid,
+    pub spent: Decimal,
+    pub tickets: i64,
+}
+
+/// Amount won by an account in an asset
+#[derive(Clone, Debug, FromRow)]
+pub struct WinningTotal {
+    pub account_id: Uuid,
+    pub asset_id: Uuid,
+    pub amount: Decimal,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn date(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 15, 30, 0).unwrap()
+    }
+
+    #[test]
+    fn buckets_name_the_period_of_the_date() {
+        let date = date(2025, 3, 12);
+
+        assert_eq!(LeaderboardPeriod::Daily.bucket(date), "daily:2025-03-12");
+        assert_eq!(LeaderboardPeriod::Weekly.bucket(date), "weekly:2025-W11");
+        assert_eq!(LeaderboardPeriod::Monthly.bucket(date), "monthly:2025-03");
+        assert_eq!(LeaderboardPeriod::AllTime.bucket(date), "all_time");
+    }
+
+    #[test]
+    fn weekly_bucket_uses_the_iso_year() {
+        // 2024-12-30 is the Monday of the first ISO week of 2025
+        assert_eq!(LeaderboardPeriod::Weekly.bucket(date(2024, 12, 30)), "weekly:2025-W01");
+        assert_eq!(LeaderboardPeriod::Weekly.bucket(date(2027, 1, 1)), "weekly:2026-W53");
+    }
+
+    #[test]
+    fn periods_start_at_midnight() {
+        let date = date(2025, 3, 12);
+        let midnight = |year, month, day| Some(Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).unwrap());
+
+        assert_eq!(LeaderboardPeriod::Daily.start(date), midnight(2025, 3, 12));
+        assert_eq!(LeaderboardPeriod::Weekly.start(date), midnight(2025, 3, 10));
+        assert_eq!(LeaderboardPeriod::Monthly.start(date), midnight(2025, 3, 1));
+        assert_eq!(LeaderboardPeriod::AllTime.start(date), None);
+    }
+
+    #[test]
+    fn weekly_period_starts_in_the_previous_month() {
+        let start = LeaderboardPeriod::Weekly.start(date(2025, 3, 1));
+
+        assert_eq!(start, Some(Utc.with_ymd_and_hms(2025, 2, 24, 0, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn date_falls_within_the_bucket_of_its_period() {
+        let date = date(2025, 3, 12);
+
+        for period in LeaderboardPeriod::ALL {
+            if let Some(start) = period.start(date) {
+                assert!(start <= date);
+                assert_eq!(period.bucket(start), period.bucket(date));
+            }
+        }
+    }
+}
@@ -1,5 +1,8 @@
 pub mod account;
+pub mod achievement;
+pub mod auth;
 pub mod asset;
+pub mod audit;
 pub mod cache;
 pub mod chain;
 pub mod chain_state;
@@ -12,8 +15,12 @@ pub mod store;
 pub mod telemetry;
 pub mod twitter;
 pub mod transaction;
+pub mod leaderboard;
 pub mod lottery;
 pub mod ticket;
 pub mod draw;
 pub mod prize;
 pub mod message_broker;
+pub mod reconciliation;
+pub mod referral;
+pub mod raw_log;
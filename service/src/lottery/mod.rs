@@ -5,46 +5,117 @@ pub mod types;
 use std::{fs, path::Path, sync::Arc};
 
 use chrono::Utc;
-use entity::{draw::{DrawModel, DrawStatus}, prelude::LotteryModel, prize::PrizeStatus};
+use entity::{draw::{DrawModel, DrawStatus}, lottery::LotteryStatus, prelude::LotteryModel, prize::{PrizeSource, PrizeStatus}};
 use lib::error::Error;
 use rand::Rng;
 use rust_decimal::Decimal;
 use serenity::async_trait;
-use error_stack::{Result, ResultExt};
-use store::LotteryStore;
-use types::{CreateLottery, UpdateLottery};
+use error_stack::{Report, Result, ResultExt};
+use store::{LotteryStatusHistoryStore, LotteryStore};
+use types::{CreateLottery, CreateLotteryStatusHistory, UpdateLottery};
 use uuid::Uuid;
-use crate::{chain::types::EventContext, draw::{store::DrawStore, types::{CreateDraw, UpdateDraw}, DrawService}, prelude::{ServiceProvider, StoreService}, prize::{store::PrizeStore, types::{CreatePrize, UpdatePrize}, PrizeService}, services::ServiceFactory, store::service::DatabaseTransaction, transaction::{service::TransactionService, types::{CreateTransaction, TransactionSideEffect}}};
+use crate::{audit::{store::AuditLogStore, types::{AuditAction, CreateAuditLog}}, chain::types::EventContext, message_broker::{channels, dedup_key, MessageBrokerService}, draw::{store::DrawStore, types::{CreateDraw, UpdateDraw}, DrawService}, prelude::{ServiceProvider, StoreService}, prize::{store::PrizeStore, types::{CreatePrize, UpdatePrize}, PrizeService}, services::ServiceFactory, store::service::DatabaseTransaction, transaction::{service::TransactionService, types::{CreateTransaction, TransactionSideEffect}}};
+
+const MAX_LOTTERY_TAGS: usize = 10;
+const MAX_LOTTERY_TAG_LENGTH: usize = 32;
 
 pub struct LotteryService {
    pub store: Arc<StoreService>,
    pub transaction_service: Arc<TransactionService>,
    pub draw_service: Arc<DrawService>,
    pub prize_service: Arc<PrizeService>,
+   pub message_broker: Arc<MessageBrokerService>,
 }
 
 impl LotteryService {
-    pub fn new(store: Arc<StoreService>, transaction_service: Arc<TransactionService>, draw_service: Arc<DrawService>, prize_service: Arc<PrizeService>) -> Self {
+    pub fn new(store: Arc<StoreService>, transaction_service: Arc<TransactionService>, draw_service: Arc<DrawService>, prize_service: Arc<PrizeService>, message_broker: Arc<MessageBrokerService>) -> Self {
         Self {
             store,
             transaction_service,
             draw_service,
             prize_service,
+            message_broker,
         }
     }
     
+    /// Move the lottery to the provided status, enforcing the lifecycle of [`LotteryStatus`].
+    ///
+    /// Moving to the current status is a no-op, an illegal transition fails with
+    /// [`Error::LotteryInvalidStatusTransition`] without touching the lottery.
+    pub async fn transition_status(
+        &self,
+        lottery_id: Uuid,
+        status: LotteryStatus,
+        context: Option<EventContext>,
+        db_tx: &mut DatabaseTransaction<'_>,
+    ) -> Result<LotteryModel, Error> {
+        let lottery = LotteryStore::find_by_id(db_tx.as_mut(), lottery_id).await?;
+
+        if lottery.status == status {
+            return Ok(lottery);
+        }
+
+        if !lottery.status.can_transition_to(status) {
+            return Err(Report::new(Error::LotteryInvalidStatusTransition).attach_printable(format!(
+                "Lottery {} cannot move from {} to {}",
+                lottery.uid,
+                lottery.status.to_string(),
+                status.to_string(),
+            )));
+        }
+
+        let dto = UpdateLottery {
+            status: Some(status),
+            ..Default::default()
+        };
+
+        let updated = LotteryStore::update(db_tx.as_mut(), lottery_id, dto).await?;
+
+        let history_dto = CreateLotteryStatusHistory {
+            lottery_id,
+            from_status: Some(lottery.status),
+            to_status: status,
+            context: context.clone(),
+        };
+
+        LotteryStatusHistoryStore::create(db_tx.as_mut(), history_dto).await?;
+
+        let channel = match status {
+            LotteryStatus::Scheduled => None,
+            LotteryStatus::Ongoing => Some(channels::LOTTERY_OPENED),
+            LotteryStatus::Completed => Some(channels::LOTTERY_CLOSED),
+            LotteryStatus::Cancelled => Some(channels::LOTTERY_CANCELED),
+        };
+
+        if let Some(channel) = channel {
+            let dedup_key = dedup_key(channel, lottery_id, context.as_ref());
+            self.message_broker.enqueue(channel, &updated, dedup_key, db_tx).await?;
+        }
+
+        let side_effects = vec![TransactionSideEffect::new(lottery_id, "lottery")];
+        self.transaction_service.record_side_effects(context, side_effects, db_tx).await?;
+
+        Ok(updated)
+    }
+
     pub async fn close_lottery(
         &self,
         lottery_id: Uuid,
-        input: UpdateLottery,
         context: Option<EventContext>,
         db_tx: &mut DatabaseTransaction<'_>,
     ) -> Result<LotteryModel, Error> {
-        let lottery = LotteryStore::update(db_tx.as_mut(), lottery_id, input).await?;
-        
-        Ok(lottery)
+        self.transition_status(lottery_id, LotteryStatus::Completed, context, db_tx).await
     }
-    
+
+    pub async fn cancel_lottery(
+        &self,
+        lottery_id: Uuid,
+        context: Option<EventContext>,
+        db_tx: &mut DatabaseTransaction<'_>,
+    ) -> Result<LotteryModel, Error> {
+        self.transition_status(lottery_id, LotteryStatus::Cancelled, context, db_tx).await
+    }
+
    
     pub async fn create_lottery(
         &self,
@@ -54,9 +125,25 @@ impl LotteryService {
     ) -> Result<LotteryModel, Error> {
         let lottery = LotteryStore::create(db_tx.as_mut(), input).await?;
         
+        let history_dto = CreateLotteryStatusHistory {
+            lottery_id: lottery.id,
+            from_status: None,
+            to_status: lottery.status,
+            context: context.clone(),
+        };
+        
+        LotteryStatusHistoryStore::create(db_tx.as_mut(), history_dto).await?;
+        
+        let side_effects = vec![TransactionSideEffect::new(lottery.id, "lottery")];
+        self.transaction_service.record_side_effects(context.clone(), side_effects, db_tx).await?;
+        
+        let dedup_key = dedup_key(channels::LOTTERY_OPENED, lottery.id, context.as_ref());
+        self.message_broker.enqueue(channels::LOTTERY_OPENED, &lottery, dedup_key, db_tx).await?;
+        
         let draw_dto = CreateDraw {
             lottery_id: lottery.id,
             status: DrawStatus::Pending,
+            tier: 1,
         };
         
         let draw = self.draw_service.create(draw_dto, context.clone(), db_tx).await?;
@@ -66,12 +153,182 @@ impl LotteryService {
             prize_asset: lottery.ticket_asset,
             value: Decimal::ZERO,
             status: PrizeStatus::Active,
+            source: PrizeSource::Tickets,
+            sponsor: None,
         };
         
         let prize = self.prize_service.create(prize_dto, context, db_tx).await?;
         
         Ok(lottery)
     }
+
+    /// Apply a change requested through the admin API, recording its changed fields in the audit log.
+    ///
+    /// Tags are normalized to unique lowercase labels. Giving a taken featured slot moves the
+    /// lotteries from that slot on one slot down.
+    pub async fn curate(
+        &self,
+        lottery_id: Uuid,
+        action: AuditAction,
+        mut dto: UpdateLottery,
+        account_id: Uuid,
+        db_tx: &mut DatabaseTransaction<'_>,
+    ) -> Result<LotteryModel, Error> {
+        dto.tags = dto.tags.map(normalize_tags).transpose()?;
+
+        let lottery = LotteryStore::find_by_id(db_tx.as_mut(), lottery_id).await?;
+        if let Some(Some(position)) = dto.featured_position {
+            if lottery.featured_position != Some(position) {
+                LotteryStore::shift_featured_positions(db_tx.as_mut(), position).await?;
+            }
+        }
+
+        let updated = LotteryStore::update(db_tx.as_mut(), lottery_id, dto).await?;
+        self.audit_curation(&lottery, &updated, action, account_id, db_tx).await?;
+
+        Ok(updated)
+    }
+
+    async fn audit_curation(
+        &self,
+        before: &LotteryModel,
+        after: &LotteryModel,
+        action: AuditAction,
+        account_id: Uuid,
+        db_tx: &mut DatabaseTransaction<'_>,
+    ) -> Result<(), Error> {
+        let (changed_before, changed_after) = curation_changes(before, after);
+        let audit_dto = CreateAuditLog {
+            account_id,
+            action,
+            entity_type: "lottery".to_string(),
+            entity_id: after.id,
+            changes: serde_json::json!({ "before": changed_before, "after": changed_after }),
+        };
+
+        AuditLogStore::create(db_tx.as_mut(), audit_dto).await?;
+
+        Ok(())
+    }
+
+    /// Give the featured lotteries their slots, the listed ones first in the provided order and
+    /// the others after them in their current order
+    pub async fn reorder_featured(
+        &self,
+        lottery_ids: Vec<Uuid>,
+        account_id: Uuid,
+        db_tx: &mut DatabaseTransaction<'_>,
+    ) -> Result<Vec<LotteryModel>, Error> {
+        let featured = LotteryStore::lock_featured(db_tx.as_mut()).await?;
+        let ordered = featured_order(&featured, &lottery_ids)?;
+
+        LotteryStore::clear_featured_positions(db_tx.as_mut()).await?;
+
+        let mut lotteries = Vec::with_capacity(ordered.len());
+        for (position, lottery) in ordered.into_iter().enumerate() {
+            let dto = UpdateLottery {
+                featured_position: Some(Some(position as i32 + 1)),
+                ..Default::default()
+            };
+
+            let updated = LotteryStore::update(db_tx.as_mut(), lottery.id, dto).await?;
+            if updated.featured_position != lottery.featured_position {
+                self.audit_curation(lottery, &updated, AuditAction::LotteryReordered, account_id, db_tx)
+                    .await?;
+            }
+            lotteries.push(updated);
+        }
+
+        Ok(lotteries)
+    }
+}
+
+/// Featured lotteries in their new slot order, the listed ones first and the others after them.
+///
+/// Every listed lottery must be featured and listed once.
+fn featured_order<'a>(featured: &'a [LotteryModel], lottery_ids: &[Uuid]) -> Result<Vec<&'a LotteryModel>, Error> {
+    let mut ordered = Vec::with_capacity(featured.len());
+
+    for lottery_id in lottery_ids {
+        if ordered.iter().any(|lottery: &&LotteryModel| lottery.id == *lottery_id) {
+            return Err(Report::new(Error::LotteryDuplicated)
+                .attach_printable(format!("Lottery {lottery_id} is listed more than once")));
+        }
+
+        let lottery = featured
+            .iter()
+            .find(|lottery| lottery.id == *lottery_id)
+            .ok_or_else(|| Report::new(Error::LotteryNotFeatured).attach_printable(format!("Lottery {lottery_id} is not featured")))?;
+        ordered.push(lottery);
+    }
+
+    for lottery in featured {
+        if !lottery_ids.contains(&lottery.id) {
+            ordered.push(lottery);
+        }
+    }
+
+    Ok(ordered)
+}
+
+/// Trim and lowercase the tags, dropping duplicates.
+///
+/// Tags are made of letters, digits and dashes, a lottery has at most `MAX_LOTTERY_TAGS` of them.
+fn normalize_tags(tags: Vec<String>) -> Result<Vec<String>, Error> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        let valid = !tag.is_empty()
+            && tag.len() <= MAX_LOTTERY_TAG_LENGTH
+            && tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+        if !valid {
+            return Err(Report::new(Error::LotteryInvalidTag).attach_printable(format!("Invalid tag {tag:?}")));
+        }
+
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+
+    if normalized.len() > MAX_LOTTERY_TAGS {
+        return Err(Report::new(Error::LotteryInvalidTag)
+            .attach_printable(format!("A lottery has at most {MAX_LOTTERY_TAGS} tags")));
+    }
+
+    Ok(normalized)
+}
+
+/// Curated fields of the lottery which differ between `before` and `after`, as JSON objects
+fn curation_changes(before: &LotteryModel, after: &LotteryModel) -> (serde_json::Value, serde_json::Value) {
+    let fields = |lottery: &LotteryModel| {
+        serde_json::json!({
+            "name": lottery.name,
+            "featured": lottery.featured,
+            "featured_position": lottery.featured_position,
+            "hidden": lottery.hidden,
+            "description": lottery.description,
+            "cover_image_url": lottery.cover_image_url,
+            "category": lottery.category,
+            "tags": lottery.tags,
+        })
+    };
+
+    let (mut before, mut after) = (fields(before), fields(after));
+    if let (Some(before), Some(after)) = (before.as_object_mut(), after.as_object_mut()) {
+        let unchanged = before
+            .iter()
+            .filter(|(key, value)| after.get(*key) == Some(*value))
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+
+        for key in unchanged {
+            before.remove(&key);
+            after.remove(&key);
+        }
+    }
+
+    (before, after)
 }
 
 #[async_trait]
@@ -81,12 +338,104 @@ impl ServiceFactory for LotteryService {
         let draw_service = services.get_service_unchecked::<DrawService>().await;
         let prize_service = services.get_service_unchecked::<PrizeService>().await;
         let transaction_service = services.get_service_unchecked::<TransactionService>().await;
+        let message_broker = services.get_service_unchecked::<MessageBrokerService>().await;
         
         Ok(Self {
             store,
             transaction_service,
             draw_service,
             prize_service,
+            message_broker,
         })
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn featured(position: Option<i32>) -> LotteryModel {
+        LotteryModel {
+            id: Uuid::new_v4(),
+            featured: true,
+            uid: String::new(),
+            name: String::new(),
+            start_date: Utc::now(),
+            end_date: Utc::now(),
+            ticket_asset: Uuid::new_v4(),
+            ticket_price: Decimal::ONE,
+            fee_ticket_amount: Decimal::ZERO,
+            max_tickets: None,
+            status: LotteryStatus::Ongoing,
+            featured_position: position,
+            hidden: false,
+            description: None,
+            cover_image_url: None,
+            category: None,
+            tags: Vec::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn ids(lotteries: Vec<&LotteryModel>) -> Vec<Uuid> {
+        lotteries.into_iter().map(|lottery| lottery.id).collect()
+    }
+
+    #[test]
+    fn tags_are_trimmed_lowercased_and_deduplicated() {
+        let tags = vec![" Weekly ".to_string(), "weekly".to_string(), "NFT-drop".to_string(), "WEEKLY".to_string()];
+
+        assert_eq!(normalize_tags(tags).unwrap(), vec!["weekly", "nft-drop"]);
+    }
+
+    #[test]
+    fn tags_with_invalid_characters_are_rejected() {
+        for tag in ["", "   ", "two words", "émoji", "under_score"] {
+            let result = normalize_tags(vec![tag.to_string()]);
+
+            assert!(matches!(result.unwrap_err().current_context(), Error::LotteryInvalidTag), "{tag:?}");
+        }
+    }
+
+    #[test]
+    fn tags_are_limited_in_length() {
+        assert!(normalize_tags(vec!["a".repeat(MAX_LOTTERY_TAG_LENGTH)]).is_ok());
+        assert!(normalize_tags(vec!["a".repeat(MAX_LOTTERY_TAG_LENGTH + 1)]).is_err());
+    }
+
+    #[test]
+    fn tags_are_limited_in_number_once_deduplicated() {
+        let distinct = (0..=MAX_LOTTERY_TAGS).map(|i| format!("tag-{i}")).collect::<Vec<_>>();
+        assert!(normalize_tags(distinct).is_err());
+
+        let duplicated = (0..=MAX_LOTTERY_TAGS).map(|_| "tag".to_string()).collect::<Vec<_>>();
+        assert_eq!(normalize_tags(duplicated).unwrap(), vec!["tag"]);
+    }
+
+    #[test]
+    fn listed_lotteries_come_first_and_others_keep_their_order() {
+        let lotteries = vec![featured(Some(1)), featured(Some(2)), featured(None)];
+
+        let ordered = featured_order(&lotteries, &[lotteries[2].id]).unwrap();
+
+        assert_eq!(ids(ordered), vec![lotteries[2].id, lotteries[0].id, lotteries[1].id]);
+    }
+
+    #[test]
+    fn duplicated_lottery_is_rejected() {
+        let lotteries = vec![featured(Some(1)), featured(Some(2))];
+
+        let result = featured_order(&lotteries, &[lotteries[1].id, lotteries[1].id]);
+
+        assert!(matches!(result.unwrap_err().current_context(), Error::LotteryDuplicated));
+    }
+
+    #[test]
+    fn lottery_which_is_not_featured_is_rejected() {
+        let lotteries = vec![featured(Some(1))];
+
+        let result = featured_order(&lotteries, &[Uuid::new_v4()]);
+
+        assert!(matches!(result.unwrap_err().current_context(), Error::LotteryNotFeatured));
+    }
+}
@@ -1,14 +1,16 @@
-use crate::{define_find_all_fns, define_find_optional_fns, lottery::types::{ UpdateLottery}};
+use crate::{common::types::{KeysetCursor, KeysetKey}, define_find_all_fns, define_find_optional_fns, lottery::types::{ UpdateLottery}};
 use chrono::{DateTime, Utc};
-use entity::lottery::{LotteryModel, LotteryStatus};
-use error_stack::{Result, ResultExt};
+use entity::lottery::{LotteryCategory, LotteryModel, LotteryStatus};
+use entity::lottery_status_history::LotteryStatusHistoryModel;
+use crate::chain::traits::string::ToHexString;
+use error_stack::{Report, Result, ResultExt};
 use lib::error::Error;
 use sqlx::{types::Decimal, Acquire, PgPool, Pool, Postgres, QueryBuilder};
 use tracing::info;
 use std::future::Future;
 use uuid::Uuid;
 
-use super::types::CreateLottery;
+use super::types::{CreateLottery, CreateLotteryStatusHistory, LotteryWithCursor};
 
 pub struct LotteryStore;
 
@@ -31,6 +33,88 @@ impl LotteryStore {
             LotteryModel
     );
 
+    // Lotteries which are not cancelled and still wait for the draw to complete
+    define_find_all_fns!(
+        find_all_non_final,
+        r#"
+            SELECT * FROM lottery
+            WHERE status <> 'CANCELLED' AND EXISTS (
+                SELECT 1 FROM draw WHERE draw.lottery_id = lottery.id AND draw.status = 'PENDING'
+            )
+            ORDER BY created_at ASC
+        "#,
+        LotteryModel
+    );
+
+    /// Lock the featured lotteries until the end of the transaction, in their slot order
+    #[allow(clippy::manual_async_fn)]
+    pub fn lock_featured<'a, 'c, Conn>(conn: Conn) -> impl Future<Output = Result<Vec<LotteryModel>, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn.acquire().await.change_context(Error::Store)?;
+
+            let query = format!(
+                "SELECT * FROM lottery WHERE featured ORDER BY {LOTTERY_FEATURED_POSITION} ASC, created_at ASC FOR UPDATE"
+            );
+
+            let lotteries = sqlx::query_as(&query)
+                .fetch_all(&mut *conn)
+                .await
+                .change_context(Error::Store)?;
+
+            Ok(lotteries)
+        }
+    }
+
+    /// Free the slots of every lottery, so that they can be given again without colliding
+    #[allow(clippy::manual_async_fn)]
+    pub fn clear_featured_positions<'a, 'c, Conn>(conn: Conn) -> impl Future<Output = Result<(), Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn.acquire().await.change_context(Error::Store)?;
+
+            sqlx::query("UPDATE lottery SET featured_position = NULL WHERE featured_position IS NOT NULL")
+                .execute(&mut *conn)
+                .await
+                .change_context(Error::StoreUpdateFailed)?;
+
+            Ok(())
+        }
+    }
+
+    /// Move the lotteries in slot `position` and after one slot down, freeing `position`.
+    ///
+    /// Slots are unique, so they are first negated then restored shifted.
+    #[allow(clippy::manual_async_fn)]
+    pub fn shift_featured_positions<'a, 'c, Conn>(
+        conn: Conn,
+        position: i32,
+    ) -> impl Future<Output = Result<(), Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn.acquire().await.change_context(Error::Store)?;
+
+            sqlx::query("UPDATE lottery SET featured_position = -featured_position - 1 WHERE featured_position >= $1")
+                .bind(position) // Bind the slot to free
+                .execute(&mut *conn)
+                .await
+                .change_context(Error::StoreUpdateFailed)?;
+
+            sqlx::query("UPDATE lottery SET featured_position = -featured_position WHERE featured_position < 0")
+                .execute(&mut *conn)
+                .await
+                .change_context(Error::StoreUpdateFailed)?;
+
+            Ok(())
+        }
+    }
+
     // Create a new lottery
     #[allow(clippy::manual_async_fn)]
     pub fn create<'a, 'c, Conn>(
@@ -90,6 +174,25 @@ impl LotteryStore {
                 .await
                 .change_context(Error::StoreTransactionFailed)?;
 
+            // Every writer goes through the lifecycle of the lottery, whatever the caller
+            if let Some(status) = input.status {
+                let query = "SELECT status FROM lottery WHERE id = $1 FOR UPDATE";
+
+                let current: LotteryStatus = sqlx::query_scalar(query)
+                    .bind(id)
+                    .fetch_one(conn.as_mut())
+                    .await
+                    .change_context(Error::Store)?;
+
+                if current != status && !current.can_transition_to(status) {
+                    return Err(Report::new(Error::LotteryInvalidStatusTransition).attach_printable(format!(
+                        "Lottery {id} cannot move from {} to {}",
+                        current.to_string(),
+                        status.to_string(),
+                    )));
+                }
+            }
+
             let query = r#"
                 UPDATE lottery
                 SET
@@ -101,6 +204,13 @@ impl LotteryStore {
                     ticket_asset = COALESCE($7, ticket_asset),
                     max_tickets = COALESCE($8, max_tickets),
                     status = COALESCE($9, status),
+                    featured = COALESCE($10, featured),
+                    hidden = COALESCE($11, hidden),
+                    featured_position = CASE WHEN $12 THEN $13 ELSE featured_position END,
+                    description = CASE WHEN $14 THEN $15 ELSE description END,
+                    cover_image_url = CASE WHEN $16 THEN $17 ELSE cover_image_url END,
+                    category = CASE WHEN $18 THEN $19 ELSE category END,
+                    tags = COALESCE($20, tags),
                     updated_at = NOW()
                 WHERE id = $1
                 RETURNING *
@@ -116,6 +226,17 @@ impl LotteryStore {
                 .bind(input.ticket_asset) // Bind the optional ticket asset
                 .bind(input.max_tickets) // Bind the optional max tickets
                 .bind(input.status) // Bind the optional status
+                .bind(input.featured) // Bind the optional featured flag
+                .bind(input.hidden) // Bind the optional hidden flag
+                .bind(input.featured_position.is_some()) // Bind whether the featured position is updated
+                .bind(input.featured_position.flatten()) // Bind the featured position, empty to clear it
+                .bind(input.description.is_some()) // Bind whether the description is updated
+                .bind(input.description.flatten()) // Bind the description, empty to clear it
+                .bind(input.cover_image_url.is_some()) // Bind whether the cover image is updated
+                .bind(input.cover_image_url.flatten()) // Bind the cover image URL, empty to clear it
+                .bind(input.category.is_some()) // Bind whether the category is updated
+                .bind(input.category.flatten()) // Bind the category, empty to clear it
+                .bind(input.tags) // Bind the optional tags
                 .fetch_one(conn.as_mut())
                 .await
                 .change_context(Error::StoreUpdateFailed)?;
@@ -153,33 +274,300 @@ impl LotteryStore {
         Ok(lotteries)
     }
     
-    pub async fn find_by_filter(pool: &PgPool, filter: LotteryFilter) -> Result<Vec<LotteryModel>, Error> {
-        let mut query_builder = QueryBuilder::new("SELECT * FROM lottery");
+    /// Find lotteries matching the filter in the sort order, starting after the cursor.
+    ///
+    /// Returns the lotteries along with the number of lotteries matching the filter.
+    pub async fn find_by_filter(
+        pool: &PgPool,
+        filter: LotteryFilter,
+        sort: LotterySort,
+        after: Option<KeysetCursor>,
+        limit: i64,
+    ) -> Result<(Vec<LotteryWithCursor>, i64), Error> {
+        // Each sort pages on its own key, with the id breaking ties
+        let (key_type, comparison, order) = match sort {
+            LotterySort::EndingSoon => (KeysetKey::Timestamp, ">", "ASC"),
+            LotterySort::LargestPool => (KeysetKey::Numeric, "<", "DESC"),
+            LotterySort::Newest => (KeysetKey::Timestamp, "<", "DESC"),
+            LotterySort::FeaturedPosition => (KeysetKey::Integer, ">", "ASC"),
+        };
 
-        if let Some(featured) = filter.featured {
-            query_builder.push(" WHERE featured = ");
-            query_builder.push_bind(featured);
+        if let Some(after) = &after {
+            after.check(sort.name(), key_type)?;
         }
 
-        if let Some(uid) = filter.uid {
-            query_builder.push(" AND uid = ");
-            query_builder.push_bind(uid);
-        }
+        let mut count_builder = QueryBuilder::new("SELECT COUNT(*) FROM lottery");
+        push_lottery_filter(&mut count_builder, &filter);
 
-        if let Some(status) = filter.status {
-            query_builder.push(" AND status = ");
-            query_builder.push_bind(status);
+        let count = count_builder
+            .build_query_scalar::<i64>()
+            .fetch_one(pool)
+            .await
+            .change_context(Error::Store)?;
+
+        // Timestamps are written as RFC 3339, like the other keyset cursors
+        let mut query_builder = QueryBuilder::new("SELECT *, ");
+        let sql_type = match key_type {
+            KeysetKey::Timestamp => {
+                query_builder.push("to_char(");
+                push_sort_key(&mut query_builder, sort, filter.prize_asset);
+                query_builder.push(r#" AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"Z"')"#);
+                "TIMESTAMPTZ"
+            }
+            KeysetKey::Numeric | KeysetKey::Integer => {
+                query_builder.push("CAST(");
+                push_sort_key(&mut query_builder, sort, filter.prize_asset);
+                query_builder.push(" AS TEXT)");
+                if key_type == KeysetKey::Numeric { "NUMERIC" } else { "INT" }
+            }
+        };
+        query_builder.push(" AS cursor_key FROM lottery");
+        let has_condition = push_lottery_filter(&mut query_builder, &filter);
+
+        if let Some(after) = after {
+            query_builder.push(if has_condition { " AND (" } else { " WHERE (" });
+            push_sort_key(&mut query_builder, sort, filter.prize_asset);
+            query_builder.push(format!(", id) {comparison} (CAST("));
+            query_builder.push_bind(after.key);
+            query_builder.push(format!(" AS {sql_type}), "));
+            query_builder.push_bind(after.id);
+            query_builder.push(")");
         }
 
-        query_builder.push(" ORDER BY created_at DESC");
+        query_builder.push(" ORDER BY ");
+        push_sort_key(&mut query_builder, sort, filter.prize_asset);
+        query_builder.push(format!(" {order}, id {order} LIMIT "));
+        query_builder.push_bind(limit);
 
         let lotteries = query_builder
-            .build_query_as::<LotteryModel>()
+            .build_query_as::<LotteryWithCursor>()
             .fetch_all(pool)
             .await
             .change_context(Error::Store)?;
 
-        Ok(lotteries)
+        Ok((lotteries, count))
+    }
+}
+
+/// Append the prize value of a lottery, amounts of different assets are never added up.
+///
+/// Sums the prizes in the asset when provided, the largest pool of a single asset otherwise.
+fn push_lottery_pool_value(query_builder: &mut QueryBuilder<'_, Postgres>, prize_asset: Option<Uuid>) {
+    match prize_asset {
+        Some(prize_asset) => {
+            query_builder.push(
+                "(SELECT COALESCE(SUM(value), 0) FROM prize WHERE prize.lottery_id = lottery.id AND prize.prize_asset = ",
+            );
+            query_builder.push_bind(prize_asset);
+            query_builder.push(")");
+        }
+        None => {
+            query_builder.push(
+                "(SELECT COALESCE(MAX(pool), 0) FROM (\
+                SELECT SUM(value) AS pool FROM prize WHERE prize.lottery_id = lottery.id GROUP BY prize.prize_asset\
+                ) AS pools)",
+            );
+        }
+    }
+}
+
+/// Append the key the lotteries are sorted on
+fn push_sort_key(query_builder: &mut QueryBuilder<'_, Postgres>, sort: LotterySort, prize_asset: Option<Uuid>) {
+    match sort {
+        LotterySort::EndingSoon => {
+            query_builder.push("end_date");
+        }
+        LotterySort::LargestPool => push_lottery_pool_value(query_builder, prize_asset),
+        LotterySort::Newest => {
+            query_builder.push("created_at");
+        }
+        LotterySort::FeaturedPosition => {
+            query_builder.push(LOTTERY_FEATURED_POSITION);
+        }
+    }
+}
+
+/// Escape the `LIKE` wildcards, so the value is matched literally with `ESCAPE '\'`
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// Lotteries without featured slot come after the others
+const LOTTERY_FEATURED_POSITION: &str = "COALESCE(featured_position, 2147483647)";
+
+/// Append the `WHERE` clause matching the filter, every provided field narrows the result.
+///
+/// Returns whether a condition was added.
+fn push_lottery_filter(query_builder: &mut QueryBuilder<'_, Postgres>, filter: &LotteryFilter) -> bool {
+    let mut has_condition = false;
+    let mut push_condition = |query_builder: &mut QueryBuilder<'_, Postgres>, condition: &str| {
+        query_builder.push(if has_condition { " AND " } else { " WHERE " });
+        query_builder.push(condition);
+        has_condition = true;
+    };
+
+    if !filter.include_hidden {
+        push_condition(query_builder, "hidden = FALSE");
+    }
+
+    if let Some(featured) = filter.featured {
+        push_condition(query_builder, "featured = ");
+        query_builder.push_bind(featured);
+    }
+
+    if let Some(category) = filter.category {
+        push_condition(query_builder, "category = ");
+        query_builder.push_bind(category.to_string());
+    }
+
+    if !filter.tags.is_empty() {
+        push_condition(query_builder, "tags && ");
+        query_builder.push_bind(filter.tags.clone());
+    }
+
+    if let Some(uid) = filter.uid.clone() {
+        push_condition(query_builder, "uid = ");
+        query_builder.push_bind(uid);
+    }
+
+    if !filter.statuses.is_empty() {
+        let statuses = filter.statuses.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        push_condition(query_builder, "status = ANY(");
+        query_builder.push_bind(statuses);
+        query_builder.push(")");
+    }
+
+    if let Some(ticket_asset) = filter.ticket_asset {
+        push_condition(query_builder, "ticket_asset = ");
+        query_builder.push_bind(ticket_asset);
+    }
+
+    if let Some(start_date_from) = filter.start_date_from {
+        push_condition(query_builder, "start_date >= ");
+        query_builder.push_bind(start_date_from);
+    }
+
+    if let Some(start_date_to) = filter.start_date_to {
+        push_condition(query_builder, "start_date <= ");
+        query_builder.push_bind(start_date_to);
+    }
+
+    if let Some(end_date_from) = filter.end_date_from {
+        push_condition(query_builder, "end_date >= ");
+        query_builder.push_bind(end_date_from);
+    }
+
+    if let Some(end_date_to) = filter.end_date_to {
+        push_condition(query_builder, "end_date <= ");
+        query_builder.push_bind(end_date_to);
+    }
+
+    if let Some(prize_asset) = filter.prize_asset {
+        push_condition(query_builder, "EXISTS (SELECT 1 FROM prize WHERE prize.lottery_id = lottery.id AND prize.prize_asset = ");
+        query_builder.push_bind(prize_asset);
+        query_builder.push(")");
+    }
+
+    if let Some(min_prize_value) = filter.min_prize_value {
+        push_condition(query_builder, "");
+        push_lottery_pool_value(query_builder, filter.prize_asset);
+        query_builder.push(" >= ");
+        query_builder.push_bind(min_prize_value);
+    }
+
+    if let Some(max_prize_value) = filter.max_prize_value {
+        push_condition(query_builder, "");
+        push_lottery_pool_value(query_builder, filter.prize_asset);
+        query_builder.push(" <= ");
+        query_builder.push_bind(max_prize_value);
+    }
+
+    if let Some(participant) = filter.participant.clone() {
+        push_condition(
+            query_builder,
+            r#"EXISTS (
+                SELECT 1 FROM ticket
+                JOIN account ON account.id = ticket.account_id
+                WHERE ticket.lottery_id = lottery.id AND LOWER(account.address) = LOWER("#,
+        );
+        query_builder.push_bind(participant);
+        query_builder.push("))");
+    }
+
+    if let Some(search) = filter.search.clone() {
+        push_condition(query_builder, "name ILIKE '%' || ");
+        query_builder.push_bind(escape_like(&search));
+        query_builder.push(" || '%' ESCAPE '\\'");
+    }
+
+    has_condition
+}
+
+pub struct LotteryStatusHistoryStore;
+
+impl LotteryStatusHistoryStore {
+    define_find_all_fns!(
+        find_by_lottery_id,
+        "SELECT * FROM lottery_status_history WHERE lottery_id = $1 ORDER BY created_at ASC",
+        Uuid,
+        LotteryStatusHistoryModel
+    );
+
+    define_find_all_fns!(
+        find_by_lottery_ids,
+        "SELECT * FROM lottery_status_history WHERE lottery_id = ANY($1) ORDER BY created_at ASC",
+        Vec<Uuid>,
+        LotteryStatusHistoryModel
+    );
+
+    // Record a status transition of a lottery
+    #[allow(clippy::manual_async_fn)]
+    pub fn create<'a, 'c, Conn>(
+        conn: Conn,
+        input: CreateLotteryStatusHistory,
+    ) -> impl Future<Output = Result<LotteryStatusHistoryModel, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn
+                .acquire()
+                .await
+                .change_context(Error::StoreTransactionFailed)?;
+
+            let query = r#"
+                INSERT INTO lottery_status_history (
+                    id, lottery_id, from_status, to_status, chain, block_number, transaction_hash, log_index, created_at
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                RETURNING *
+            "#;
+
+            let context = input.context;
+            let history = sqlx::query_as(query)
+                .bind(Uuid::new_v4()) // Generate a new UUID for the entry
+                .bind(input.lottery_id) // Bind the lottery ID
+                .bind(input.from_status) // Bind the optional previous status
+                .bind(input.to_status) // Bind the new status
+                .bind(context.as_ref().map(|context| context.chain.clone())) // Bind the optional chain
+                .bind(context.as_ref().map(|context| context.block_number.as_u64() as i64)) // Bind the optional block number
+                .bind(context.as_ref().map(|context| context.transaction_hash.to_hex_string())) // Bind the optional transaction hash
+                .bind(context.as_ref().map(|context| context.log_index.as_u32() as i32)) // Bind the optional log index
+                .bind(Utc::now()) // Bind the created_at timestamp
+                .fetch_one(conn.as_mut())
+                .await
+                .change_context(Error::StoreInsertFailed)?;
+
+            Ok(history)
+        }
     }
 }
 
@@ -187,5 +575,64 @@ impl LotteryStore {
 pub struct LotteryFilter {
     pub featured: Option<bool>,
     pub uid: Option<String>,
-    pub status: Option<LotteryStatus>,
-}
\ No newline at end of file
+    pub statuses: Vec<LotteryStatus>,
+    pub ticket_asset: Option<Uuid>,
+    pub start_date_from: Option<DateTime<Utc>>,
+    pub start_date_to: Option<DateTime<Utc>>,
+    pub end_date_from: Option<DateTime<Utc>>,
+    pub end_date_to: Option<DateTime<Utc>>,
+    pub prize_asset: Option<Uuid>, // Asset the prize values are compared in
+    pub min_prize_value: Option<Decimal>,
+    pub max_prize_value: Option<Decimal>,
+    pub participant: Option<String>, // Address of an account holding tickets
+    pub search: Option<String>, // Part of the lottery name
+    pub include_hidden: bool, // Hidden lotteries are left out unless set
+    pub category: Option<LotteryCategory>,
+    pub tags: Vec<String>, // Lotteries having any of the tags
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LotterySort {
+    EndingSoon,
+    LargestPool,
+    #[default]
+    Newest,
+    FeaturedPosition,
+}
+
+impl LotterySort {
+    /// Name of the sort, carried by the cursors issued for it
+    pub fn name(self) -> &'static str {
+        match self {
+            LotterySort::EndingSoon => "ending_soon",
+            LotterySort::LargestPool => "largest_pool",
+            LotterySort::Newest => "newest",
+            LotterySort::FeaturedPosition => "featured_position",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_like() {
+        assert_eq!(escape_like("lucky draw"), "lucky draw");
+        assert_eq!(escape_like("100%"), "100\\%");
+        assert_eq!(escape_like("big_pot"), "big\\_pot");
+        assert_eq!(escape_like("a\\b"), "a\\\\b");
+    }
+
+    #[test]
+    fn test_lottery_pool_value_is_scoped_to_the_asset() {
+        let mut query_builder = QueryBuilder::<Postgres>::new("");
+        push_lottery_pool_value(&mut query_builder, Some(Uuid::new_v4()));
+        assert!(query_builder.sql().ends_with("prize.prize_asset = $1)"));
+
+        // Without asset, pools are grouped per asset rather than summed
+        let mut query_builder = QueryBuilder::<Postgres>::new("");
+        push_lottery_pool_value(&mut query_builder, None);
+        assert!(query_builder.sql().contains("GROUP BY prize.prize_asset"));
+    }
+}
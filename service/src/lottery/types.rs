@@ -1,5 +1,6 @@
+use crate::chain::types::EventContext;
 use chrono::{DateTime, Utc};
-use entity::prelude::LotteryStatus;
+use entity::prelude::{LotteryCategory, LotteryModel, LotteryStatus};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -27,4 +28,28 @@ pub struct UpdateLottery {
     pub ticket_asset: Option<Uuid>,
     pub max_tickets: Option<i32>,
     pub status: Option<LotteryStatus>,
-}
\ No newline at end of file
+    pub featured: Option<bool>,
+    pub hidden: Option<bool>,
+    // Nullable fields are only updated when the outer option is set, so they can be cleared
+    pub featured_position: Option<Option<i32>>,
+    pub description: Option<Option<String>>,
+    pub cover_image_url: Option<Option<String>>,
+    pub category: Option<Option<LotteryCategory>>,
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CreateLotteryStatusHistory {
+    pub lottery_id: Uuid,
+    pub from_status: Option<LotteryStatus>,
+    pub to_status: LotteryStatus,
+    pub context: Option<EventContext>,
+}
+
+/// Lottery along with the text representation of the key it was sorted on
+#[derive(Clone, Debug, sqlx::FromRow)]
+pub struct LotteryWithCursor {
+    #[sqlx(flatten)]
+    pub lottery: LotteryModel,
+    pub cursor_key: String,
+}
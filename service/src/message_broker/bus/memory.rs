@@ -0,0 +1,57 @@
+use super::MessageBus;
+use crate::message_broker::{BrokerEvent, Event};
+use entity::broker_event::BrokerEventModel;
+use error_stack::Result;
+use lib::error::Error;
+use serenity::async_trait;
+use tokio::sync::broadcast::{error::RecvError, Sender};
+use tracing::warn;
+
+/// Bus delivering the events within the process, for tests and single binary deployments
+pub struct InMemoryMessageBus {
+    sender: Sender<BrokerEvent>,
+}
+
+impl InMemoryMessageBus {
+    pub fn new() -> Self {
+        let (sender, _) = tokio::sync::broadcast::channel(10_000);
+
+        Self { sender }
+    }
+}
+
+impl Default for InMemoryMessageBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl MessageBus for InMemoryMessageBus {
+    async fn publish(&self, event: &BrokerEventModel) -> Result<(), Error> {
+        match Event::decode(&event.channel, event.payload.clone()) {
+            Some(Ok(decoded)) => {
+                // Nobody listening yet is not an error
+                let _ = self.sender.send(BrokerEvent { id: event.id, event: decoded });
+            }
+            Some(Err(e)) => warn!("Failed to decode event {}: {e:?}", event.id),
+            None => warn!("Event {} has not supported channel {}", event.id, event.channel),
+        }
+
+        Ok(())
+    }
+
+    async fn consume(&self, sender: Sender<BrokerEvent>) {
+        let mut receiver = self.sender.subscribe();
+
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    let _ = sender.send(event);
+                }
+                Err(RecvError::Lagged(skipped)) => warn!("In-memory bus consumer lagged behind, {skipped} events skipped"),
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+}
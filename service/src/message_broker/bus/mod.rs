@@ -0,0 +1,129 @@
+use super::BrokerEvent;
+use entity::broker_event::BrokerEventModel;
+use error_stack::Result;
+use lib::error::Error;
+use serenity::async_trait;
+use tokio::sync::broadcast::Sender;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+pub mod memory;
+pub mod postgres;
+pub mod redis;
+
+pub use memory::InMemoryMessageBus;
+pub use postgres::PostgresMessageBus;
+pub use redis::RedisMessageBus;
+
+/// Transport delivering the persisted broker events to every process subscribing to them.
+///
+/// Only the broker events go through the bus: the cache, the sessions and the leaderboards are
+/// kept in Redis whatever the backend, so `redis.url` is always required.
+#[async_trait]
+pub trait MessageBus: Send + Sync {
+    /// Publish the persisted event to the consumers of the bus
+    async fn publish(&self, event: &BrokerEventModel) -> Result<(), Error>;
+
+    /// Forward the events published on the bus to `sender`, until the process stops
+    async fn consume(&self, sender: Sender<BrokerEvent>);
+
+    /// Forward the events of the channel published on the bus to `sender` as a member of the
+    /// consumer group, until the process stops. Every group receives every event, shared by its members.
+    ///
+    /// Events are acknowledged once their id, or a later one, is received on `acks`, the events
+    /// left unacknowledged are delivered again. Backends without consumer groups deliver every
+    /// event to every consumer and ignore the acknowledgements.
+    async fn consume_group(
+        &self,
+        group: &str,
+        channel: &'static str,
+        sender: Sender<BrokerEvent>,
+        acks: UnboundedReceiver<i64>,
+    ) {
+        let _ = (group, channel, acks);
+        self.consume(sender).await
+    }
+}
+
+/// Behavior every bus backend must provide, checked against the in-memory backend
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message_broker::{channels, Event};
+    use chrono::Utc;
+    use entity::prelude::AccountAchievementModel;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::broadcast::{self, Receiver};
+    use uuid::Uuid;
+
+    fn event(id: i64, channel: &str) -> BrokerEventModel {
+        let unlock = AccountAchievementModel {
+            id: Uuid::new_v4(),
+            account_id: Uuid::new_v4(),
+            achievement: "first_ticket".to_string(),
+            context: None,
+            unlocked_at: Utc::now(),
+        };
+
+        BrokerEventModel {
+            id,
+            channel: channel.to_string(),
+            payload: serde_json::to_value(unlock).unwrap(),
+            dedup_key: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Start consuming the bus, returning the receiving end of the forwarded events
+    async fn consumer(bus: Arc<dyn MessageBus>) -> Receiver<BrokerEvent> {
+        let (sender, receiver) = broadcast::channel(16);
+        tokio::spawn(async move { bus.consume(sender).await });
+
+        // Let the consumer subscribe before anything is published
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        receiver
+    }
+
+    async fn next(receiver: &mut Receiver<BrokerEvent>) -> Option<BrokerEvent> {
+        tokio::time::timeout(Duration::from_millis(200), receiver.recv()).await.ok()?.ok()
+    }
+
+    #[tokio::test]
+    async fn test_memory_bus_delivers_published_events_in_order() {
+        let bus: Arc<dyn MessageBus> = Arc::new(InMemoryMessageBus::new());
+        let mut receiver = consumer(bus.clone()).await;
+
+        bus.publish(&event(1, channels::ACHIEVEMENT_UNLOCKED.name())).await.unwrap();
+        bus.publish(&event(2, channels::ACHIEVEMENT_UNLOCKED.name())).await.unwrap();
+
+        let first = next(&mut receiver).await.unwrap();
+        assert_eq!(first.id, 1);
+        assert!(matches!(first.event, Event::AchievementUnlocked(_)));
+        assert_eq!(next(&mut receiver).await.unwrap().id, 2);
+    }
+
+    #[tokio::test]
+    async fn test_memory_bus_delivers_every_event_to_every_consumer() {
+        let bus: Arc<dyn MessageBus> = Arc::new(InMemoryMessageBus::new());
+        let mut first = consumer(bus.clone()).await;
+        let mut second = consumer(bus.clone()).await;
+
+        bus.publish(&event(1, channels::ACHIEVEMENT_UNLOCKED.name())).await.unwrap();
+
+        assert_eq!(next(&mut first).await.unwrap().id, 1);
+        assert_eq!(next(&mut second).await.unwrap().id, 1);
+    }
+
+    #[tokio::test]
+    async fn test_memory_bus_skips_undecodable_events() {
+        let bus: Arc<dyn MessageBus> = Arc::new(InMemoryMessageBus::new());
+        let mut receiver = consumer(bus.clone()).await;
+
+        // Neither an unknown channel nor a payload of another channel fails the publication
+        bus.publish(&event(1, "not_a_channel")).await.unwrap();
+        bus.publish(&event(2, channels::TICKET_BOUGHT.name())).await.unwrap();
+        bus.publish(&event(3, channels::ACHIEVEMENT_UNLOCKED.name())).await.unwrap();
+
+        assert_eq!(next(&mut receiver).await.unwrap().id, 3);
+    }
+}
@@ -0,0 +1,77 @@
+use super::MessageBus;
+use crate::message_broker::{store::BrokerEventStore, BrokerEvent, Event};
+use crate::prelude::StoreService;
+use entity::broker_event::BrokerEventModel;
+use error_stack::{Result, ResultExt};
+use lib::error::Error;
+use serenity::async_trait;
+use sqlx::postgres::PgListener;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::Sender;
+use tracing::{error, info, warn};
+
+/// Postgres channel the ids of the published events are notified on
+const NOTIFY_CHANNEL: &str = "broker_event";
+
+/// Bus relying on Postgres `LISTEN/NOTIFY`, for setups without Redis.
+///
+/// Only the event id is notified, as payloads are limited in size, consumers
+/// load the event from the `broker_event` table.
+pub struct PostgresMessageBus {
+    store: Arc<StoreService>,
+}
+
+impl PostgresMessageBus {
+    pub fn new(store: Arc<StoreService>) -> Self {
+        Self { store }
+    }
+
+    async fn listen(&self, sender: &Sender<BrokerEvent>) -> Result<(), Error> {
+        let mut listener = PgListener::connect_with(self.store.write())
+            .await
+            .change_context(Error::Store)?;
+        listener.listen(NOTIFY_CHANNEL).await.change_context(Error::Store)?;
+
+        info!("Listening to {NOTIFY_CHANNEL} notifications");
+
+        loop {
+            let notification = listener.recv().await.change_context(Error::Store)?;
+
+            let Ok(id) = notification.payload().parse::<i64>() else {
+                warn!("Received invalid {NOTIFY_CHANNEL} notification: {}", notification.payload());
+                continue;
+            };
+
+            // Read from the primary, replicas may not have the event yet
+            let Some(event) = BrokerEventStore::try_find_by_id(self.store.write(), id).await? else {
+                warn!("Notified event {id} doesn't exist");
+                continue;
+            };
+
+            match Event::decode(&event.channel, event.payload) {
+                Some(Ok(decoded)) => {
+                    let _ = sender.send(BrokerEvent { id, event: decoded });
+                }
+                Some(Err(e)) => warn!("Failed to decode event {id}: {e:?}"),
+                None => warn!("Event {id} has not supported channel {}", event.channel),
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl MessageBus for PostgresMessageBus {
+    async fn publish(&self, event: &BrokerEventModel) -> Result<(), Error> {
+        BrokerEventStore::notify(self.store.write(), NOTIFY_CHANNEL.to_string(), event.id).await
+    }
+
+    async fn consume(&self, sender: Sender<BrokerEvent>) {
+        loop {
+            if let Err(e) = self.listen(&sender).await {
+                error!("Postgres bus consumer failed, restarting: {e:?}");
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        }
+    }
+}
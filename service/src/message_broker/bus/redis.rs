@@ -0,0 +1,434 @@
+use super::MessageBus;
+use crate::config::service::{RedisConfig, RedisStreamsConfig};
+use crate::message_broker::{channels, BrokerEvent, Event};
+use entity::broker_event::BrokerEventModel;
+use error_stack::{Report, Result, ResultExt};
+use lib::error::Error;
+use redis::aio::ConnectionManager;
+use redis::streams::{StreamId, StreamMaxlen, StreamReadOptions, StreamReadReply};
+use redis::AsyncCommands;
+use redis::Client;
+use serenity::async_trait;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::sync::broadcast::Sender;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+/// Bus appending the events to Redis Streams, one per channel, read through consumer groups
+pub struct RedisMessageBus {
+    client: Client,
+    connection: ConnectionManager,
+    config: RedisStreamsConfig,
+}
+
+impl RedisMessageBus {
+    pub async fn new(config: RedisConfig) -> Result<Self, Error> {
+        info!(url = config.url, "Connecting to redis");
+
+        let client = Client::open(config.url.clone()).change_context(Error::Redis)?;
+
+        let connection = client
+            .get_tokio_connection_manager()
+            .await
+            .change_context(Error::RedisConnect)?;
+
+        Ok(Self {
+            client,
+            connection,
+            config: config.streams,
+        })
+    }
+}
+
+#[async_trait]
+impl MessageBus for RedisMessageBus {
+    async fn publish(&self, event: &BrokerEventModel) -> Result<(), Error> {
+        let items = [
+            ("id", event.id.to_string()),
+            ("payload", event.payload.to_string()),
+        ];
+
+        let mut conn = self.connection.clone();
+        let _: String = conn
+            .xadd_maxlen(stream_key(&event.channel), StreamMaxlen::Approx(self.config.max_len), "*", &items)
+            .await
+            .change_context(Error::Redis)?;
+
+        Ok(())
+    }
+
+    async fn consume(&self, sender: Sender<BrokerEvent>) {
+        consume(self.client.clone(), self.config.clone(), sender).await
+    }
+
+    async fn consume_group(
+        &self,
+        group: &str,
+        channel: &'static str,
+        sender: Sender<BrokerEvent>,
+        mut acks: UnboundedReceiver<i64>,
+    ) {
+        let key = stream_key(channel);
+        let consumer = self.config.consumer.clone().unwrap_or_else(unique_consumer_name);
+        let mut acked = Acked::default();
+
+        loop {
+            let consumed = consume_acked(&self.client, &self.config, group, &consumer, &key, &sender, &mut acks, &mut acked).await;
+
+            if let Err(e) = consumed {
+                error!("Stream consumer of {key} failed, restarting: {e:?}");
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        }
+    }
+}
+
+/// Redis stream the events of the channel are appended to
+fn stream_key(channel: &str) -> String {
+    format!("events:{channel}")
+}
+
+/// Decode a stream entry appended by `RedisMessageBus::publish`
+fn decode_entry(key: &str, entry: &StreamId) -> Option<BrokerEvent> {
+    let channel = key.strip_prefix("events:").unwrap_or(key);
+
+    let (Some(id), Some(payload)) = (entry.get::<i64>("id"), entry.get::<String>("payload")) else {
+        warn!("Stream entry {} of {key} is missing fields", entry.id);
+        return None;
+    };
+
+    let payload = match serde_json::from_str(&payload) {
+        Ok(payload) => payload,
+        Err(e) => {
+            warn!("Failed to decode stream entry {} of {key}: {e:?}", entry.id);
+            return None;
+        }
+    };
+
+    match Event::decode(channel, payload) {
+        Some(Ok(event)) => Some(BrokerEvent { id, event }),
+        Some(Err(e)) => {
+            warn!("Failed to decode stream entry {} of {key}: {e:?}", entry.id);
+            None
+        }
+        None => {
+            warn!("Received entry from not supported stream {key}");
+            None
+        }
+    }
+}
+
+/// Name of this process within a consumer group, unique so two processes never share their pending entries
+fn unique_consumer_name() -> String {
+    let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "unknown".to_string());
+    format!("{host}-{}-{}", std::process::id(), Uuid::new_v4().simple())
+}
+
+/// Read the streams of every channel, as a member of the configured consumer group if any,
+/// and broadcast the events to the subscribers of this process.
+async fn consume(client: Client, config: RedisStreamsConfig, sender: Sender<BrokerEvent>) {
+    let keys: Vec<String> = channels::REGISTRY.iter().map(|(name, _)| stream_key(name)).collect();
+    let consumer = config.consumer.clone().unwrap_or_else(unique_consumer_name);
+
+    // Without group, the position is kept across reconnections so no event is read twice
+    let mut positions = vec!["$".to_string(); keys.len()];
+
+    loop {
+        let consumed = match &config.group {
+            Some(group) => consume_group(&client, &config, group, &consumer, &keys, &sender).await,
+            None => consume_streams(&client, &config, &keys, &mut positions, &sender).await,
+        };
+
+        if let Err(e) = consumed {
+            error!("Stream consumer failed, restarting: {e:?}");
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    }
+}
+
+/// Broadcast the events of the stream entries read to the subscribers of this process
+fn broadcast(reply: &StreamReadReply, sender: &Sender<BrokerEvent>) {
+    for stream in &reply.keys {
+        for entry in &stream.ids {
+            // Nobody listening in this process is not an error
+            if let Some(event) = decode_entry(&stream.key, entry) {
+                let _ = sender.send(event);
+            }
+        }
+    }
+}
+
+/// Read every entry appended to the streams from `positions` on, without consumer group
+async fn consume_streams(
+    client: &Client,
+    config: &RedisStreamsConfig,
+    keys: &[String],
+    positions: &mut [String],
+    sender: &Sender<BrokerEvent>,
+) -> Result<(), Error> {
+    let mut conn = client.get_tokio_connection().await.change_context(Error::RedisConnect)?;
+
+    info!("Consuming event streams");
+
+    loop {
+        let options = StreamReadOptions::default()
+            .count(config.batch_size)
+            .block(config.block_ms);
+
+        let reply: StreamReadReply = conn
+            .xread_options(keys, positions, &options)
+            .await
+            .change_context(Error::Redis)?;
+
+        broadcast(&reply, sender);
+
+        for stream in &reply.keys {
+            let (Some(index), Some(last)) = (keys.iter().position(|key| *key == stream.key), stream.ids.last()) else {
+                continue;
+            };
+            positions[index] = last.id.clone();
+        }
+    }
+}
+
+/// Read the streams as `consumer` of the consumer group, acknowledging the entries once broadcast.
+///
+/// Subscribers of the process resume from the stored events on their own, see `MessageBrokerService::subscribe`.
+async fn consume_group(
+    client: &Client,
+    config: &RedisStreamsConfig,
+    group: &str,
+    consumer: &str,
+    keys: &[String],
+    sender: &Sender<BrokerEvent>,
+) -> Result<(), Error> {
+    let mut conn = client.get_tokio_connection().await.change_context(Error::RedisConnect)?;
+
+    // New groups only receive the events appended from now on
+    for key in keys {
+        let created: redis::RedisResult<()> = conn.xgroup_create_mkstream(key, group, "$").await;
+        if let Err(e) = created {
+            if e.code() != Some("BUSYGROUP") {
+                return Err(Report::new(e).change_context(Error::Redis));
+            }
+        }
+    }
+
+    info!(group, consumer, "Consuming event streams");
+
+    // Entries delivered before a restart but never acknowledged are read first
+    let mut cursor = "0";
+
+    loop {
+        let options = StreamReadOptions::default()
+            .group(group, consumer)
+            .count(config.batch_size)
+            .block(config.block_ms);
+        let ids = vec![cursor; keys.len()];
+
+        let reply: StreamReadReply = conn
+            .xread_options(keys, &ids, &options)
+            .await
+            .change_context(Error::Redis)?;
+
+        broadcast(&reply, sender);
+
+        let mut received = 0;
+        for stream in reply.keys {
+            if stream.ids.is_empty() {
+                continue;
+            }
+
+            // Undecodable entries are acknowledged too, they would never succeed
+            let acked: Vec<&str> = stream.ids.iter().map(|entry| entry.id.as_str()).collect();
+            let _: i64 = conn
+                .xack(&stream.key, group, &acked)
+                .await
+                .change_context(Error::Redis)?;
+
+            received += acked.len();
+        }
+
+        if cursor == "0" && received == 0 {
+            cursor = ">";
+        }
+    }
+}
+
+/// Stream entries delivered to the consumer, waiting for it to acknowledge their event
+#[derive(Default)]
+struct Acked {
+    /// Highest event id acknowledged by the consumer
+    up_to: i64,
+    /// Entry ids of the delivered events by event id
+    pending: BTreeMap<i64, String>,
+}
+
+impl Acked {
+    /// Record the acknowledgement, returns the entries it acknowledges
+    fn ack(&mut self, event_id: i64) -> Vec<String> {
+        self.up_to = self.up_to.max(event_id);
+
+        let pending = self.pending.split_off(&(self.up_to + 1));
+        std::mem::replace(&mut self.pending, pending).into_values().collect()
+    }
+
+    /// Whether the entry of the event must be delivered, events already acknowledged are not
+    fn deliver(&mut self, event_id: i64, entry_id: &str) -> bool {
+        if event_id <= self.up_to {
+            return false;
+        }
+
+        self.pending.insert(event_id, entry_id.to_string());
+        true
+    }
+}
+
+/// Read the stream as `consumer` of the consumer group, acknowledging the entries once the
+/// consumer acknowledged their event.
+///
+/// Entries left pending by stopped consumers are claimed first, then the entries pending with
+/// this consumer are delivered again before the new ones.
+#[allow(clippy::too_many_arguments)]
+async fn consume_acked(
+    client: &Client,
+    config: &RedisStreamsConfig,
+    group: &str,
+    consumer: &str,
+    key: &str,
+    sender: &Sender<BrokerEvent>,
+    acks: &mut UnboundedReceiver<i64>,
+    acked: &mut Acked,
+) -> Result<(), Error> {
+    let mut conn = client.get_tokio_connection().await.change_context(Error::RedisConnect)?;
+
+    // New groups only receive the events appended from now on
+    let created: redis::RedisResult<()> = conn.xgroup_create_mkstream(key, group, "$").await;
+    if let Err(e) = created {
+        if e.code() != Some("BUSYGROUP") {
+            return Err(Report::new(e).change_context(Error::Redis));
+        }
+    }
+
+    let mut start = "0-0".to_string();
+    loop {
+        let reply: Vec<redis::Value> = redis::cmd("XAUTOCLAIM")
+            .arg(key)
+            .arg(group)
+            .arg(consumer)
+            .arg(config.claim_idle_ms)
+            .arg(&start)
+            .arg("COUNT")
+            .arg(config.batch_size)
+            .arg("JUSTID")
+            .query_async(&mut conn)
+            .await
+            .change_context(Error::Redis)?;
+
+        start = reply
+            .first()
+            .map(redis::from_redis_value)
+            .transpose()
+            .change_context(Error::Redis)?
+            .unwrap_or_else(|| "0-0".to_string());
+
+        if start == "0-0" {
+            break;
+        }
+    }
+
+    info!(group, consumer, key, "Consuming event stream");
+
+    // The entries pending with this consumer are read first, then the new ones
+    let mut cursor = "0".to_string();
+    acked.pending.clear();
+
+    loop {
+        let mut entries = Vec::new();
+        while let Ok(event_id) = acks.try_recv() {
+            entries.extend(acked.ack(event_id));
+        }
+        if !entries.is_empty() {
+            let _: i64 = conn.xack(key, group, &entries).await.change_context(Error::Redis)?;
+        }
+
+        let options = StreamReadOptions::default()
+            .group(group, consumer)
+            .count(config.batch_size)
+            .block(config.block_ms);
+
+        let reply: StreamReadReply = conn
+            .xread_options(&[key], &[cursor.as_str()], &options)
+            .await
+            .change_context(Error::Redis)?;
+
+        let mut received = 0;
+        let mut settled = Vec::new();
+        for stream in &reply.keys {
+            for entry in &stream.ids {
+                received += 1;
+                if cursor != ">" {
+                    cursor = entry.id.clone();
+                }
+
+                // Undecodable entries would never succeed, they are acknowledged right away
+                match decode_entry(&stream.key, entry) {
+                    Some(event) if acked.deliver(event.id, &entry.id) => {
+                        let _ = sender.send(event);
+                    }
+                    _ => settled.push(entry.id.clone()),
+                }
+            }
+        }
+
+        if !settled.is_empty() {
+            let _: i64 = conn.xack(key, group, &settled).await.change_context(Error::Redis)?;
+        }
+
+        if cursor != ">" && received == 0 {
+            cursor = ">".to_string();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unique_consumer_name() {
+        assert_ne!(unique_consumer_name(), unique_consumer_name());
+    }
+
+    #[test]
+    fn test_streams_are_read_without_group_by_default() {
+        let config = RedisStreamsConfig::default();
+        assert!(config.group.is_none());
+        assert!(config.consumer.is_none());
+    }
+
+    #[test]
+    fn test_acks_settle_the_entries_up_to_the_event() {
+        let mut acked = Acked::default();
+        assert!(acked.deliver(1, "1-0"));
+        assert!(acked.deliver(2, "2-0"));
+        assert!(acked.deliver(4, "4-0"));
+
+        assert_eq!(acked.ack(2), vec!["1-0".to_string(), "2-0".to_string()]);
+        assert_eq!(acked.ack(1), Vec::<String>::new());
+        assert_eq!(acked.pending.len(), 1);
+    }
+
+    #[test]
+    fn test_events_already_acked_are_not_delivered() {
+        let mut acked = Acked::default();
+
+        // Replayed events can be acknowledged before their entry is read
+        acked.ack(3);
+
+        assert!(!acked.deliver(3, "3-0"));
+        assert!(acked.deliver(5, "5-0"));
+    }
+}
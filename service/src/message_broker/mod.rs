@@ -1,23 +1,54 @@
-use crate::config::service::{ConfigService, RedisConfig};
-use crate::prelude::ServiceProvider;
+use crate::config::service::{ConfigService, MessageBusBackend};
+use crate::prelude::{ServiceProvider, StoreService};
 use crate::services::ServiceFactory;
-use entity::prelude::{TicketModel, PrizeModel};
+use crate::store::service::DatabaseTransaction;
+use crate::chain::types::EventContext;
+use chrono::{Duration, Utc};
+use entity::broker_event::BrokerEventModel;
+use entity::prelude::{AccountAchievementModel, DrawModel, LotteryModel, TicketModel, PrizeModel};
 use error_stack::{Result, ResultExt};
 use futures::stream::StreamExt;
 use lib::error::Error;
-use redis::aio::ConnectionManager;
-use redis::AsyncCommands;
-use redis::Client;
+use bus::{InMemoryMessageBus, MessageBus, PostgresMessageBus, RedisMessageBus};
 use serde::Serialize;
 use serenity::async_trait;
+use std::marker::PhantomData;
 use std::sync::Arc;
-use tokio::sync::broadcast::Receiver;
-use tokio_stream::wrappers::BroadcastStream;
-use tracing::{error, info, warn};
+use store::{BrokerEventStore, OutboxStore};
+use std::collections::HashMap;
+use tokio::sync::broadcast::{Receiver, Sender};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::{Mutex, OnceCell};
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
+use tracing::warn;
+use uuid::Uuid;
+
+pub mod bus;
+pub mod relay;
+pub mod store;
+
+/// Maximum number of events replayed to a resuming subscriber at once
+const REPLAY_LIMIT: i64 = 1_000;
+
+/// Events buffered per subscriber before it lags behind
+const BROADCAST_CAPACITY: usize = 10_000;
+
+/// Seconds a relay has to publish the outbox messages it claimed, before another relay takes them over
+const OUTBOX_CLAIM_SECS: i64 = 60;
 
 struct MessageBrokerServiceInner {
-    connection: ConnectionManager,
-    channel: Receiver<Event>,
+    store: Arc<StoreService>,
+    bus: Arc<dyn MessageBus>,
+    retention: Duration, // How long events stay replayable
+    sender: Sender<BrokerEvent>,
+    consumer: OnceCell<()>,
+    groups: Mutex<HashMap<(String, &'static str), GroupConsumer>>, // Consumers of the dedicated groups, per channel
+}
+
+/// Consumer of a channel as a member of a dedicated consumer group
+struct GroupConsumer {
+    sender: Sender<BrokerEvent>,
+    acks: UnboundedSender<i64>,
 }
 
 // TODO: Need to rename to something like MessageBus or MessageBroker, or even maybe Publisher
@@ -29,85 +60,352 @@ pub struct MessageBrokerService(Arc<MessageBrokerServiceInner>);
 pub enum Event {
     TicketBought(TicketModel),
     PrizePoolUpdated(PrizeModel),
+    LotteryOpened(LotteryModel),
+    LotteryClosed(LotteryModel),
+    LotteryCanceled(LotteryModel),
+    WinnerDrawn(DrawModel),
+    AchievementUnlocked(AccountAchievementModel),
+}
+
+impl Event {
+    /// Decode the payload published on the channel, `None` if the channel is not registered
+    fn decode(channel: &str, payload: serde_json::Value) -> Option<serde_json::Result<Self>> {
+        channels::REGISTRY
+            .iter()
+            .find(|(name, _)| *name == channel)
+            .map(|(_, decode)| decode(payload))
+    }
+
+    pub fn channel(&self) -> &'static str {
+        match self {
+            Event::TicketBought(_) => channels::TICKET_BOUGHT.name(),
+            Event::PrizePoolUpdated(_) => channels::PRIZE_POOL_UPDATED.name(),
+            Event::LotteryOpened(_) => channels::LOTTERY_OPENED.name(),
+            Event::LotteryClosed(_) => channels::LOTTERY_CLOSED.name(),
+            Event::LotteryCanceled(_) => channels::LOTTERY_CANCELED.name(),
+            Event::WinnerDrawn(_) => channels::WINNER_DRAWN.name(),
+            Event::AchievementUnlocked(_) => channels::ACHIEVEMENT_UNLOCKED.name(),
+        }
+    }
+}
+
+/// A broker channel, carrying payloads of type `T`
+pub struct Channel<T> {
+    name: &'static str,
+    payload: PhantomData<fn() -> T>,
+}
+
+impl<T> Channel<T> {
+    const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            payload: PhantomData,
+        }
+    }
+
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl<T> Clone for Channel<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Channel<T> {}
+
+/// Deduplication key of the message announcing a change of the entity.
+///
+/// Changes made while handling a chain event are keyed by the event, so handling it again
+/// doesn't publish twice. Other changes are always published.
+pub fn dedup_key<T>(channel: Channel<T>, entity_id: Uuid, context: Option<&EventContext>) -> String {
+    let channel = channel.name();
+
+    match context {
+        Some(context) => format!(
+            "{channel}:{entity_id}:{}:{:?}:{}",
+            context.chain, context.transaction_hash, context.log_index
+        ),
+        None => format!("{channel}:{entity_id}:{}", Uuid::new_v4()),
+    }
+}
+
+/// An event along with its id, ids increase in publication order
+#[derive(Clone, Debug)]
+pub struct BrokerEvent {
+    pub id: i64,
+    pub event: Event,
+}
+
+/// What a subscriber receives from the broker
+#[derive(Clone, Debug)]
+pub enum Delivery {
+    Event(Box<BrokerEvent>),
+    /// The subscriber fell behind and missed events, it must resync from its last event id
+    Lagged,
+}
+
+/// Registry of the channels events are published on
+pub mod channels {
+    use super::{Channel, Event};
+    use entity::prelude::{AccountAchievementModel, DrawModel, LotteryModel, PrizeModel, TicketModel};
+
+    pub const TICKET_BOUGHT: Channel<TicketModel> = Channel::new("ticket_bought");
+    pub const PRIZE_POOL_UPDATED: Channel<PrizeModel> = Channel::new("prize_pool_updated");
+    pub const LOTTERY_OPENED: Channel<LotteryModel> = Channel::new("lottery_opened");
+    pub const LOTTERY_CLOSED: Channel<LotteryModel> = Channel::new("lottery_closed");
+    pub const LOTTERY_CANCELED: Channel<LotteryModel> = Channel::new("lottery_canceled");
+    pub const WINNER_DRAWN: Channel<DrawModel> = Channel::new("winner_drawn");
+    pub const ACHIEVEMENT_UNLOCKED: Channel<AccountAchievementModel> = Channel::new("achievement_unlocked");
+
+    type Decoder = fn(serde_json::Value) -> serde_json::Result<Event>;
+
+    /// Every channel along with the decoder of its payloads
+    pub(super) const REGISTRY: [(&str, Decoder); 7] = [
+        (TICKET_BOUGHT.name(), |payload| serde_json::from_value(payload).map(Event::TicketBought)),
+        (PRIZE_POOL_UPDATED.name(), |payload| serde_json::from_value(payload).map(Event::PrizePoolUpdated)),
+        (LOTTERY_OPENED.name(), |payload| serde_json::from_value(payload).map(Event::LotteryOpened)),
+        (LOTTERY_CLOSED.name(), |payload| serde_json::from_value(payload).map(Event::LotteryClosed)),
+        (LOTTERY_CANCELED.name(), |payload| serde_json::from_value(payload).map(Event::LotteryCanceled)),
+        (WINNER_DRAWN.name(), |payload| serde_json::from_value(payload).map(Event::WinnerDrawn)),
+        (ACHIEVEMENT_UNLOCKED.name(), |payload| serde_json::from_value(payload).map(Event::AchievementUnlocked)),
+    ];
 }
 
 impl MessageBrokerService {
-    pub async fn new(config: RedisConfig) -> Result<Self, Error> {
-        info!(url = config.url, "Connecting to redis");
+    pub fn new(bus: Arc<dyn MessageBus>, store: Arc<StoreService>, retention: Duration) -> Self {
+        let (sender, _) = tokio::sync::broadcast::channel(BROADCAST_CAPACITY);
 
-        let client = Client::open(config.url.clone()).change_context(Error::Redis)?;
+        MessageBrokerService(Arc::new(MessageBrokerServiceInner {
+            store,
+            bus,
+            retention,
+            sender,
+            consumer: OnceCell::new(),
+            groups: Mutex::new(HashMap::new()),
+        }))
+    }
 
-        let connection = client
-            .get_tokio_connection_manager()
-            .await
-            .change_context(Error::RedisConnect)?;
+    /// Enqueue the message in the outbox as part of the transaction.
+    ///
+    /// It is published by the outbox relay once the transaction is committed, see `relay_outbox`.
+    pub async fn enqueue<T>(
+        &self,
+        channel: Channel<T>,
+        msg: &T,
+        dedup_key: String,
+        db_tx: &mut DatabaseTransaction<'_>,
+    ) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        let payload = serde_json::to_value(msg).change_context(Error::SerdeSerialize)?;
+        OutboxStore::create(db_tx.as_mut(), channel.name().to_string(), payload, dedup_key).await
+    }
 
-        let (tx, rx) = tokio::sync::broadcast::channel(10_000);
+    /// Publish a batch of committed outbox messages, returns how many were published.
+    ///
+    /// Messages are claimed in a transaction of their own, so no lock is held while publishing.
+    /// Delivery is at-least-once: a message published but not marked as such is published
+    /// again once its claim expires, with the same event id thanks to its deduplication key.
+    pub async fn relay_outbox(&self, limit: i64) -> Result<usize, Error> {
+        let messages = OutboxStore::claim_pending(self.0.store.write(), limit, OUTBOX_CLAIM_SECS).await?;
 
-        let conn = client.get_tokio_connection().await.unwrap();
-        let mut pubsub = conn.into_pubsub();
-        pubsub.subscribe("ticket_bought").await.unwrap();
+        let mut published = 0;
+        let mut messages = messages.into_iter();
+        while let Some(message) = messages.next() {
+            let id = message.id;
+            let result = async {
+                let mut db_tx = self.0.store.begin_transaction().await?;
+                let event = self.persist(message.channel, message.payload, Some(message.dedup_key), &mut db_tx).await?;
+                self.0.store.commit_transaction(db_tx).await?;
 
-        tokio::spawn(async move {
-            let mut stream = pubsub.into_on_message();
+                self.0.bus.publish(&event).await
+            }
+            .await;
 
-            while let Some(msg) = stream.next().await {
-                let payload = &msg.get_payload::<String>();
-                if let Err(e) = payload {
-                    warn!("Failed to get payload: {e:?}");
-                    continue;
-                }
+            if let Err(e) = result {
+                warn!("Failed to publish outbox message {id}: {e:?}");
+                OutboxStore::mark_failed(self.0.store.write(), id, format!("{e:?}")).await?;
 
-                let payload = payload.as_ref().unwrap();
-
-                let event = match msg.get_channel_name() {
-                    "ticket_bought" => Some(Event::TicketBought(
-                        serde_json::from_str::<TicketModel>(payload).unwrap(),
-                    )),
-                    _ => None,
-                };
-
-                if let Some(event) = event {
-                    if let Err(e) = tx.send(event.clone()) {
-                        error!("Failed to broadcast event: {}", e);
-                        error!("Event: {:?}", event);
-                    }
-                } else {
-                    warn!(
-                        "Received message from not supported channel {}",
-                        msg.get_channel_name()
-                    );
-                }
+                // Keep the remaining messages pending, so they are published in order
+                OutboxStore::release(self.0.store.write(), messages.map(|message| message.id).collect()).await?;
+                break;
             }
-        });
 
-        Ok(MessageBrokerService(Arc::new(MessageBrokerServiceInner {
-            connection,
-            channel: rx,
-        })))
+            OutboxStore::mark_published(self.0.store.write(), id).await?;
+            published += 1;
+        }
+
+        Ok(published)
     }
 
-    pub async fn send<T>(&self, channel: String, msg: T) -> Result<(), Error>
+    /// Delete the published outbox messages and the events older than the retention period,
+    /// returns how many rows were deleted.
+    ///
+    /// Subscribers resuming from a deleted event receive a `Delivery::Lagged`.
+    pub async fn prune(&self, limit: i64) -> Result<u64, Error> {
+        let before = Utc::now() - self.0.retention;
+        let messages = OutboxStore::delete_published_before(self.0.store.write(), before, limit).await?;
+        let events = BrokerEventStore::delete_before(self.0.store.write(), before, limit).await?;
+
+        Ok(messages + events)
+    }
+
+    /// Persist the event as part of the transaction, which assigns its id.
+    ///
+    /// Other relays wait for the transaction to end before persisting theirs, subscribers
+    /// resuming after an event id never miss an event committed later with a lower id.
+    async fn persist(
+        &self,
+        channel: String,
+        payload: serde_json::Value,
+        dedup_key: Option<String>,
+        db_tx: &mut DatabaseTransaction<'_>,
+    ) -> Result<BrokerEventModel, Error> {
+        BrokerEventStore::lock(db_tx.as_mut()).await?;
+        BrokerEventStore::create(db_tx.as_mut(), channel, payload, dedup_key).await
+    }
+
+    /// Start consuming the bus, once per process and only when someone subscribes,
+    /// so publishers never join a consumer group
+    async fn start_consumer(&self) {
+        self.0
+            .consumer
+            .get_or_init(|| async {
+                let bus = self.0.bus.clone();
+                let sender = self.0.sender.clone();
+                tokio::spawn(async move { bus.consume(sender).await });
+            })
+            .await;
+    }
+
+    /// Subscribe to the events of the channel.
+    ///
+    /// With `after_event_id` the events published after it are replayed first, the live
+    /// events already replayed are skipped. When more events were missed than can be replayed
+    /// at once, or the subscriber lags behind the live events, a `Delivery::Lagged` is emitted.
+    pub async fn subscribe<T>(
+        &self,
+        channel: Channel<T>,
+        after_event_id: Option<i64>,
+    ) -> Result<impl futures::Stream<Item = Delivery>, Error>
     where
-        T: Serialize,
+        T: 'static,
     {
-        let mut conn = self.0.connection.clone();
-        conn.publish(channel, serde_json::to_string(&msg).unwrap())
-            .await
-            .change_context(Error::Redis)?;
+        self.start_consumer().await;
 
-        Ok(())
+        self.subscribe_to(self.0.sender.subscribe(), channel, after_event_id).await
     }
 
-    pub async fn subscribe(&self) -> impl futures::Stream<Item = Event> {
-        let stream = BroadcastStream::from(self.0.channel.resubscribe());
+    /// Subscribe to the events of the channel read by the consumer group, see `subscribe`.
+    ///
+    /// Workers handling each event once subscribe through a group of their own, so they
+    /// receive every event whatever the consumer group of the process. They acknowledge the
+    /// events once handled with `ack`, the others are delivered again.
+    pub async fn subscribe_group<T>(
+        &self,
+        group: &str,
+        channel: Channel<T>,
+        after_event_id: Option<i64>,
+    ) -> Result<impl futures::Stream<Item = Delivery>, Error>
+    where
+        T: 'static,
+    {
+        let receiver = {
+            let mut groups = self.0.groups.lock().await;
+            match groups.get(&(group.to_string(), channel.name())) {
+                Some(consumer) => consumer.sender.subscribe(),
+                None => {
+                    let (sender, receiver) = tokio::sync::broadcast::channel(BROADCAST_CAPACITY);
+                    let (acks, acks_receiver) = tokio::sync::mpsc::unbounded_channel();
+                    let bus = self.0.bus.clone();
+                    let consumer_group = group.to_string();
+                    let consumer_sender = sender.clone();
+                    tokio::spawn(async move {
+                        bus.consume_group(&consumer_group, channel.name(), consumer_sender, acks_receiver).await
+                    });
+
+                    groups.insert((group.to_string(), channel.name()), GroupConsumer { sender, acks });
+                    receiver
+                }
+            }
+        };
+
+        self.subscribe_to(receiver, channel, after_event_id).await
+    }
 
-        stream.filter_map(|event| {
+    /// Acknowledge the event handled by the consumer group, along with the earlier events of its channel
+    pub async fn ack(&self, group: &str, event: &BrokerEvent) {
+        let groups = self.0.groups.lock().await;
+        if let Some(consumer) = groups.get(&(group.to_string(), event.event.channel())) {
+            // The consumer only stops with the process
+            let _ = consumer.acks.send(event.id);
+        }
+    }
+
+    async fn subscribe_to<T>(
+        &self,
+        receiver: Receiver<BrokerEvent>,
+        channel: Channel<T>,
+        after_event_id: Option<i64>,
+    ) -> Result<impl futures::Stream<Item = Delivery>, Error>
+    where
+        T: 'static,
+    {
+        // Subscribed before replaying, so events published meanwhile are not lost
+        let live = BroadcastStream::from(receiver);
+        let channel = channel.name();
+
+        let mut replayed = Vec::new();
+        let mut last_event_id = after_event_id;
+        let mut overflow = false;
+
+        if let Some(after_event_id) = after_event_id {
+            // Events older than the retention period can't be replayed anymore
+            let pruned_id = BrokerEventStore::find_pruned_id(self.0.store.read()).await?;
+            overflow = pruned_id.is_some_and(|id| after_event_id < id);
+
+            let events = BrokerEventStore::find_after(
+                self.0.store.read(),
+                channel.to_string(),
+                after_event_id,
+                REPLAY_LIMIT,
+            )
+            .await?;
+
+            overflow |= events.len() as i64 == REPLAY_LIMIT;
+
+            for event in events {
+                last_event_id = Some(event.id);
+
+                match Event::decode(&event.channel, event.payload) {
+                    Some(Ok(decoded)) => replayed.push(Delivery::Event(Box::new(BrokerEvent { id: event.id, event: decoded }))),
+                    Some(Err(e)) => warn!("Failed to decode stored event {}: {e:?}", event.id),
+                    None => warn!("Stored event {} has not supported channel {}", event.id, event.channel),
+                }
+            }
+        }
+
+        if overflow {
+            replayed.push(Delivery::Lagged);
+        }
+
+        let live = live.filter_map(move |event| {
             futures::future::ready(match event {
-                Ok(event) => Some(event),
-                _ => None,
+                Ok(event) if event.event.channel() != channel => None,
+                Ok(event) if last_event_id.is_some_and(|id| event.id <= id) => None,
+                Ok(event) => Some(Delivery::Event(Box::new(event))),
+                Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+                    warn!("Subscriber of {channel} lagged behind, {skipped} events skipped");
+                    Some(Delivery::Lagged)
+                }
             })
-        })
+        });
+
+        Ok(futures::stream::iter(replayed).chain(live))
     }
 }
 
@@ -115,6 +413,16 @@ impl MessageBrokerService {
 impl ServiceFactory for MessageBrokerService {
     async fn factory(services: ServiceProvider) -> Result<Self, Error> {
         let config = services.get_service_unchecked::<ConfigService>().await;
-        Self::new(config.redis.clone()).await
+        let store = services.get_service_unchecked::<StoreService>().await;
+
+        let bus: Arc<dyn MessageBus> = match config.message_bus.backend {
+            MessageBusBackend::Redis => Arc::new(RedisMessageBus::new(config.redis.clone()).await?),
+            MessageBusBackend::Memory => Arc::new(InMemoryMessageBus::new()),
+            MessageBusBackend::Postgres => Arc::new(PostgresMessageBus::new(store.clone())),
+        };
+
+        let retention = Duration::days(config.message_bus.retention_days as i64);
+
+        Ok(Self::new(bus, store, retention))
     }
 }
@@ -0,0 +1,79 @@
+use super::MessageBrokerService;
+use crate::common::shutdown::{await_shutdown_signal, ShutdownFlag};
+use crate::services::ServiceProvider;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info, Instrument};
+
+/// How often the outbox is checked for committed messages
+const RELAY_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Number of messages published per outbox transaction
+const RELAY_BATCH_SIZE: i64 = 100;
+
+/// How often the outbox messages and broker events past their retention period are deleted
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Number of rows deleted per statement
+const PRUNE_BATCH_SIZE: i64 = 10_000;
+
+/// Publish the committed outbox messages to the broker until shutdown, and delete the rows past their retention period.
+///
+/// Every process writing to the outbox runs a relay, relays share the messages through their claims
+/// and persist the events one transaction at a time, see `MessageBrokerService::persist`.
+pub fn spawn(services: ServiceProvider, shutdown: ShutdownFlag) -> JoinHandle<()> {
+    let span = tracing::info_span!("outbox");
+
+    tokio::spawn(
+        async move {
+            let message_broker = services.get_service_unchecked::<MessageBrokerService>().await;
+
+            info!("Starting outbox relay");
+
+            let mut interval = tokio::time::interval(RELAY_INTERVAL);
+            let mut prune_interval = tokio::time::interval(PRUNE_INTERVAL);
+
+            loop {
+                tokio::select! {
+                    _ = await_shutdown_signal(shutdown.clone()) => break,
+                    _ = interval.tick() => {
+                        // Drain the backlog before waiting for the next tick
+                        loop {
+                            match message_broker.relay_outbox(RELAY_BATCH_SIZE).await {
+                                Ok(published) if published as i64 == RELAY_BATCH_SIZE => continue,
+                                Ok(_) => break,
+                                Err(e) => {
+                                    error!(reason = ?e, "Failed to relay outbox messages");
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                    _ = prune_interval.tick() => {
+                        let mut pruned = 0;
+                        loop {
+                            match message_broker.prune(PRUNE_BATCH_SIZE).await {
+                                Ok(deleted) => {
+                                    pruned += deleted;
+                                    if (deleted as i64) < PRUNE_BATCH_SIZE {
+                                        break;
+                                    }
+                                }
+                                Err(e) => {
+                                    error!(reason = ?e, "Failed to prune outbox messages and broker events");
+                                    break;
+                                }
+                            }
+                        }
+                        if pruned > 0 {
+                            info!("Pruned {pruned} outbox messages and broker events");
+                        }
+                    }
+                }
+            }
+
+            info!("Outbox relay stopped");
+        }
+        .instrument(span),
+    )
+}
@@ -0,0 +1,468 @@
+use crate::define_find_optional_fns;
+use entity::{broker_event::BrokerEventModel, outbox::OutboxModel};
+use chrono::{DateTime, Utc};
+use error_stack::{Result, ResultExt};
+use lib::error::Error;
+use sqlx::{Acquire, Postgres};
+use std::future::Future;
+use uuid::Uuid;
+
+/// Key of the advisory lock serializing the writers of broker events
+const BROKER_EVENT_LOCK_KEY: i64 = 0x6272_6f6b_6572;
+
+pub struct BrokerEventStore;
+
+impl BrokerEventStore {
+    define_find_optional_fns!(
+        find_by_id,
+        try_find_by_id,
+        "SELECT * FROM broker_event WHERE id = $1",
+        i64,
+        BrokerEventModel
+    );
+
+    /// Lock the broker events until the transaction ends.
+    ///
+    /// Ids are assigned on insert but become visible on commit, writers hold the lock from the
+    /// insert to the commit so events never become visible out of id order.
+    #[allow(clippy::manual_async_fn)]
+    pub fn lock<'a, 'c, Conn>(conn: Conn) -> impl Future<Output = Result<(), Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn
+                .acquire()
+                .await
+                .change_context(Error::StoreTransactionFailed)?;
+
+            sqlx::query("SELECT pg_advisory_xact_lock($1)")
+                .bind(BROKER_EVENT_LOCK_KEY) // Bind the key of the lock
+                .execute(conn.as_mut())
+                .await
+                .change_context(Error::Store)?;
+
+            Ok(())
+        }
+    }
+
+    /// Persist an event, the returned id orders it among all published events.
+    ///
+    /// An event with an already persisted deduplication key is returned as is.
+    #[allow(clippy::manual_async_fn)]
+    pub fn create<'a, 'c, Conn>(
+        conn: Conn,
+        channel: String,
+        payload: serde_json::Value,
+        dedup_key: Option<String>,
+    ) -> impl Future<Output = Result<BrokerEventModel, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn
+                .acquire()
+                .await
+                .change_context(Error::StoreTransactionFailed)?;
+
+            let query = r#"
+                INSERT INTO broker_event (channel, payload, dedup_key)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (dedup_key) DO UPDATE
+                SET dedup_key = EXCLUDED.dedup_key
+                RETURNING *
+            "#;
+
+            let event = sqlx::query_as(query)
+                .bind(channel) // Bind the channel name
+                .bind(payload) // Bind the JSON payload
+                .bind(dedup_key) // Bind the optional deduplication key
+                .fetch_one(conn.as_mut())
+                .await
+                .change_context(Error::StoreInsertFailed)?;
+
+            Ok(event)
+        }
+    }
+
+    /// Events of the channel published after `after_id`, oldest first
+    #[allow(clippy::manual_async_fn)]
+    pub fn find_after<'a, 'c, Conn>(
+        conn: Conn,
+        channel: String,
+        after_id: i64,
+        limit: i64,
+    ) -> impl Future<Output = Result<Vec<BrokerEventModel>, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn.acquire().await.change_context(Error::Store)?;
+
+            let query = r#"
+                SELECT * FROM broker_event
+                WHERE channel = $1 AND id > $2
+                ORDER BY id ASC
+                LIMIT $3
+            "#;
+
+            let events = sqlx::query_as(query)
+                .bind(channel) // Bind the channel name
+                .bind(after_id) // Bind the last event id the subscriber received
+                .bind(limit) // Bind the replay limit
+                .fetch_all(&mut *conn)
+                .await
+                .change_context(Error::Store)?;
+
+            Ok(events)
+        }
+    }
+
+    /// Id of the latest event deleted past the retention period, `None` when none was deleted
+    #[allow(clippy::manual_async_fn)]
+    pub fn find_pruned_id<'a, 'c, Conn>(conn: Conn) -> impl Future<Output = Result<Option<i64>, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn.acquire().await.change_context(Error::Store)?;
+
+            let id = sqlx::query_scalar("SELECT pruned_id FROM broker_event_watermark")
+                .fetch_optional(&mut *conn)
+                .await
+                .change_context(Error::Store)?;
+
+            Ok(id)
+        }
+    }
+
+    /// Id of the last event stored per channel, channels without event are left out
+    #[allow(clippy::manual_async_fn)]
+    pub fn find_last_ids<'a, 'c, Conn>(
+        conn: Conn,
+        channels: Vec<String>,
+    ) -> impl Future<Output = Result<Vec<(String, i64)>, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn.acquire().await.change_context(Error::Store)?;
+
+            let query = r#"
+                SELECT channel, MAX(id)
+                FROM broker_event
+                WHERE channel = ANY($1)
+                GROUP BY channel
+            "#;
+
+            let ids = sqlx::query_as(query)
+                .bind(channels) // Bind the channels
+                .fetch_all(&mut *conn)
+                .await
+                .change_context(Error::Store)?;
+
+            Ok(ids)
+        }
+    }
+
+    /// Delete a batch of the events created before `before`, oldest first.
+    ///
+    /// The id of the latest deleted event is kept, ids have gaps so the oldest remaining
+    /// event doesn't tell whether the events before it were deleted.
+    #[allow(clippy::manual_async_fn)]
+    pub fn delete_before<'a, 'c, Conn>(
+        conn: Conn,
+        before: DateTime<Utc>,
+        limit: i64,
+    ) -> impl Future<Output = Result<u64, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn.acquire().await.change_context(Error::Store)?;
+
+            let query = r#"
+                WITH deleted AS (
+                    DELETE FROM broker_event
+                    WHERE id IN (
+                        SELECT id FROM broker_event
+                        WHERE created_at < $1
+                        ORDER BY id ASC
+                        LIMIT $2
+                    )
+                    RETURNING id
+                ),
+                watermark AS (
+                    INSERT INTO broker_event_watermark (id, pruned_id)
+                    SELECT 1, MAX(id) FROM deleted HAVING COUNT(*) > 0
+                    ON CONFLICT (id) DO UPDATE
+                    SET pruned_id = GREATEST(broker_event_watermark.pruned_id, EXCLUDED.pruned_id)
+                )
+                SELECT COUNT(*) FROM deleted
+            "#;
+
+            let deleted: i64 = sqlx::query_scalar(query)
+                .bind(before) // Bind the end of the retention period
+                .bind(limit) // Bind the batch size
+                .fetch_one(&mut *conn)
+                .await
+                .change_context(Error::Store)?;
+
+            Ok(deleted as u64)
+        }
+    }
+
+    /// Notify the id of a published event on the Postgres channel
+    #[allow(clippy::manual_async_fn)]
+    pub fn notify<'a, 'c, Conn>(
+        conn: Conn,
+        channel: String,
+        id: i64,
+    ) -> impl Future<Output = Result<(), Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn.acquire().await.change_context(Error::Store)?;
+
+            sqlx::query("SELECT pg_notify($1, $2)")
+                .bind(channel) // Bind the Postgres channel
+                .bind(id.to_string()) // Bind the event id as payload
+                .execute(&mut *conn)
+                .await
+                .change_context(Error::Store)?;
+
+            Ok(())
+        }
+    }
+}
+
+pub struct OutboxStore;
+
+impl OutboxStore {
+    /// Enqueue a message, a message with an already enqueued deduplication key is ignored
+    #[allow(clippy::manual_async_fn)]
+    pub fn create<'a, 'c, Conn>(
+        conn: Conn,
+        channel: String,
+        payload: serde_json::Value,
+        dedup_key: String,
+    ) -> impl Future<Output = Result<(), Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn
+                .acquire()
+                .await
+                .change_context(Error::StoreTransactionFailed)?;
+
+            let query = r#"
+                INSERT INTO outbox (id, channel, payload, dedup_key)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (dedup_key) DO NOTHING
+            "#;
+
+            sqlx::query(query)
+                .bind(Uuid::new_v4()) // Generate a new UUID for the message
+                .bind(channel) // Bind the channel name
+                .bind(payload) // Bind the JSON payload
+                .bind(dedup_key) // Bind the deduplication key
+                .execute(conn.as_mut())
+                .await
+                .change_context(Error::StoreInsertFailed)?;
+
+            Ok(())
+        }
+    }
+
+    /// Claim the oldest unpublished messages for `lease_secs`, messages claimed by another relay are skipped.
+    ///
+    /// Messages whose claim expired, because their relay stopped, can be claimed again.
+    #[allow(clippy::manual_async_fn)]
+    pub fn claim_pending<'a, 'c, Conn>(
+        conn: Conn,
+        limit: i64,
+        lease_secs: i64,
+    ) -> impl Future<Output = Result<Vec<OutboxModel>, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn.acquire().await.change_context(Error::Store)?;
+
+            let query = r#"
+                UPDATE outbox
+                SET claimed_until = NOW() + make_interval(secs => $2)
+                WHERE id IN (
+                    SELECT id FROM outbox
+                    WHERE published_at IS NULL AND (claimed_until IS NULL OR claimed_until < NOW())
+                    ORDER BY created_at ASC
+                    LIMIT $1
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING *
+            "#;
+
+            let mut messages: Vec<OutboxModel> = sqlx::query_as(query)
+                .bind(limit) // Bind the batch size
+                .bind(lease_secs as f64) // Bind the claim duration
+                .fetch_all(&mut *conn)
+                .await
+                .change_context(Error::Store)?;
+
+            // RETURNING doesn't keep the order of the subquery
+            messages.sort_by_key(|message| message.created_at);
+
+            Ok(messages)
+        }
+    }
+
+    /// Whether messages of the channels are still waiting to be persisted as broker events
+    #[allow(clippy::manual_async_fn)]
+    pub fn has_unpersisted<'a, 'c, Conn>(
+        conn: Conn,
+        channels: Vec<String>,
+    ) -> impl Future<Output = Result<bool, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn.acquire().await.change_context(Error::Store)?;
+
+            let query = r#"
+                SELECT EXISTS (
+                    SELECT 1 FROM outbox
+                    WHERE published_at IS NULL
+                        AND channel = ANY($1)
+                        AND NOT EXISTS (SELECT 1 FROM broker_event WHERE broker_event.dedup_key = outbox.dedup_key)
+                )
+            "#;
+
+            let pending = sqlx::query_scalar(query)
+                .bind(channels) // Bind the channels
+                .fetch_one(&mut *conn)
+                .await
+                .change_context(Error::Store)?;
+
+            Ok(pending)
+        }
+    }
+
+    /// Give up the claim of the messages, so they are published by the next relay run
+    #[allow(clippy::manual_async_fn)]
+    pub fn release<'a, 'c, Conn>(
+        conn: Conn,
+        ids: Vec<Uuid>,
+    ) -> impl Future<Output = Result<(), Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn
+                .acquire()
+                .await
+                .change_context(Error::StoreTransactionFailed)?;
+
+            sqlx::query("UPDATE outbox SET claimed_until = NULL WHERE id = ANY($1) AND published_at IS NULL")
+                .bind(ids) // Bind the IDs of the messages to release
+                .execute(conn.as_mut())
+                .await
+                .change_context(Error::StoreUpdateFailed)?;
+
+            Ok(())
+        }
+    }
+
+    /// Delete a batch of the messages published before `before`, oldest first
+    #[allow(clippy::manual_async_fn)]
+    pub fn delete_published_before<'a, 'c, Conn>(
+        conn: Conn,
+        before: DateTime<Utc>,
+        limit: i64,
+    ) -> impl Future<Output = Result<u64, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn.acquire().await.change_context(Error::Store)?;
+
+            let query = r#"
+                DELETE FROM outbox
+                WHERE id IN (
+                    SELECT id FROM outbox
+                    WHERE published_at < $1
+                    ORDER BY published_at ASC
+                    LIMIT $2
+                )
+            "#;
+
+            let result = sqlx::query(query)
+                .bind(before) // Bind the end of the retention period
+                .bind(limit) // Bind the batch size
+                .execute(&mut *conn)
+                .await
+                .change_context(Error::Store)?;
+
+            Ok(result.rows_affected())
+        }
+    }
+
+    /// Mark the message as handed over to the broker
+    #[allow(clippy::manual_async_fn)]
+    pub fn mark_published<'a, 'c, Conn>(
+        conn: Conn,
+        id: Uuid,
+    ) -> impl Future<Output = Result<(), Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn
+                .acquire()
+                .await
+                .change_context(Error::StoreTransactionFailed)?;
+
+            sqlx::query("UPDATE outbox SET published_at = NOW(), claimed_until = NULL WHERE id = $1")
+                .bind(id) // Bind the message ID to update
+                .execute(conn.as_mut())
+                .await
+                .change_context(Error::StoreUpdateFailed)?;
+
+            Ok(())
+        }
+    }
+
+    /// Record a failed publication attempt, the message stays pending and is released
+    #[allow(clippy::manual_async_fn)]
+    pub fn mark_failed<'a, 'c, Conn>(
+        conn: Conn,
+        id: Uuid,
+        error: String,
+    ) -> impl Future<Output = Result<(), Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn
+                .acquire()
+                .await
+                .change_context(Error::StoreTransactionFailed)?;
+
+            let query = r#"
+                UPDATE outbox
+                SET attempts = attempts + 1, last_error = $2, claimed_until = NULL
+                WHERE id = $1
+            "#;
+
+            sqlx::query(query)
+                .bind(id) // Bind the message ID to update
+                .bind(error) // Bind the publication error
+                .execute(conn.as_mut())
+                .await
+                .change_context(Error::StoreUpdateFailed)?;
+
+            Ok(())
+        }
+    }
+}
@@ -1,26 +1,30 @@
 pub mod store;
 pub mod types;
 use chrono::Utc;
-use entity::{draw::DrawModel, prelude::LotteryModel, prize::PrizeModel, ticket::TicketModel};
+use entity::{draw::DrawModel, prelude::LotteryModel, prize::{PrizeModel, PrizeSource, PrizeStatus}, ticket::TicketModel};
+use rust_decimal::Decimal;
+use uuid::Uuid;
 use lib::error::Error;
 use serenity::async_trait;
-use error_stack::{Result, ResultExt};
+use error_stack::{Report, Result, ResultExt};
 use store::{PrizeStore};
 use types::{CreatePrize,};
-use crate::{chain::types::EventContext, prelude::{ServiceProvider, StoreService}, services::ServiceFactory, store::service::DatabaseTransaction, transaction::{service::TransactionService, types::{CreateTransaction, TransactionSideEffect}}};
+use crate::{asset::store::AssetStore, chain::types::EventContext, message_broker::{channels, dedup_key, MessageBrokerService}, lottery::store::LotteryStore, prelude::{ServiceProvider, StoreService}, services::ServiceFactory, store::service::DatabaseTransaction, transaction::{service::TransactionService, types::{CreateTransaction, TransactionSideEffect}}};
 
 use std::sync::Arc;
 
 pub struct PrizeService {
    pub store: Arc<StoreService>,
    pub transaction_service: Arc<TransactionService>,
+   pub message_broker: Arc<MessageBrokerService>,
 }
 
 impl PrizeService {
-    pub fn new(store: Arc<StoreService>, transaction_service: Arc<TransactionService>) -> Self {
+    pub fn new(store: Arc<StoreService>, transaction_service: Arc<TransactionService>, message_broker: Arc<MessageBrokerService>) -> Self {
         Self {
             store,
             transaction_service,
+            message_broker,
         }
     }
     
@@ -32,9 +36,60 @@ impl PrizeService {
     ) -> Result<PrizeModel, Error> {
         let prize = PrizeStore::create(db_tx.as_mut(), input).await?;
         
+        let side_effects = vec![TransactionSideEffect::new(prize.id, "prize")];
+        self.transaction_service.record_side_effects(context, side_effects, db_tx).await?;
+        
         Ok(prize)
     }
     
+    /// Add a sponsor contribution to the prize of the lottery
+    ///
+    /// Contributions are kept as separate prize rows, so the breakdown of the jackpot
+    /// between ticket sales and each sponsor stays visible.
+    pub async fn sponsor(
+        &self,
+        lottery_id: Uuid,
+        sponsor: Uuid,
+        prize_asset: Uuid,
+        value: Decimal,
+        context: Option<EventContext>,
+        db_tx: &mut DatabaseTransaction<'_>,
+    ) -> Result<PrizeModel, Error> {
+        if value <= Decimal::ZERO {
+            return Err(Report::new(Error::PrizeServiceInvalidValue));
+        }
+        
+        if AssetStore::try_find_by_id(db_tx.as_mut(), prize_asset).await?.is_none() {
+            return Err(Report::new(Error::PrizeServiceUnknownAsset));
+        }
+        
+        let lottery = LotteryStore::find_by_id(db_tx.as_mut(), lottery_id).await?;
+        
+        let dto = CreatePrize {
+            lottery_id: lottery.id,
+            prize_asset,
+            value,
+            status: PrizeStatus::Active,
+            source: PrizeSource::Sponsor,
+            sponsor: Some(sponsor),
+        };
+        
+        let prize = self.create(dto, context.clone(), db_tx).await?;
+        let dedup_key = dedup_key(channels::PRIZE_POOL_UPDATED, prize.id, context.as_ref());
+        
+        self.message_broker.enqueue(channels::PRIZE_POOL_UPDATED, &prize, dedup_key, db_tx).await?;
+        
+        Ok(prize)
+    }
+}
+
+/// Value of the prizes of the lottery in the asset, sponsor prizes included
+pub fn prize_pool(prizes: &[PrizeModel], asset_id: Uuid) -> Decimal {
+    prizes
+        .iter()
+        .filter(|prize| prize.prize_asset == asset_id)
+        .map(|prize| prize.value)
+        .sum()
 }
 
 #[async_trait]
@@ -45,6 +100,39 @@ impl ServiceFactory for PrizeService {
         Ok(Self {
             store,
             transaction_service: services.get_service_unchecked::<TransactionService>().await,
+            message_broker: services.get_service_unchecked::<MessageBrokerService>().await,
         })
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prize(prize_asset: Uuid, value: Decimal, source: PrizeSource) -> PrizeModel {
+        PrizeModel {
+            id: Uuid::new_v4(),
+            lottery_id: Uuid::new_v4(),
+            prize_asset,
+            value,
+            status: PrizeStatus::Active,
+            source,
+            sponsor: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_prize_pool_includes_sponsor_prizes_in_asset() {
+        let asset = Uuid::new_v4();
+        let prizes = vec![
+            prize(asset, Decimal::from(800), PrizeSource::Tickets),
+            prize(asset, Decimal::from(200), PrizeSource::Sponsor),
+            prize(Uuid::new_v4(), Decimal::from(5_000), PrizeSource::Sponsor),
+        ];
+
+        assert_eq!(prize_pool(&prizes, asset), Decimal::from(1_000));
+        assert_eq!(prize_pool(&[], asset), Decimal::ZERO);
+    }
+}
@@ -21,10 +21,24 @@ impl PrizeStore {
     define_find_optional_fns!(
             find_by_lottery_id,
             try_find_by_lottery_id,
-            "SELECT * FROM prize WHERE lottery_id = $1",
+            "SELECT * FROM prize WHERE lottery_id = $1 AND source = 'TICKETS'",
             Uuid,
             PrizeModel
         );
+        
+    define_find_all_fns!(
+        find_all_by_lottery_id,
+        "SELECT * FROM prize WHERE lottery_id = $1 ORDER BY source DESC, created_at ASC",
+        Uuid,
+        PrizeModel
+    );
+    
+    define_find_all_fns!(
+        find_all_by_lottery_ids,
+        "SELECT * FROM prize WHERE lottery_id = ANY($1) ORDER BY source DESC, created_at ASC",
+        Vec<Uuid>,
+        PrizeModel
+    );
 
 
     // Create a new prize
@@ -44,9 +58,9 @@ impl PrizeStore {
 
             let query = r#"
                 INSERT INTO prize (
-                    id, lottery_id, prize_asset, value, status, created_at, updated_at
+                    id, lottery_id, prize_asset, value, status, source, sponsor, created_at, updated_at
                 )
-                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
                 RETURNING *
             "#;
 
@@ -56,6 +70,8 @@ impl PrizeStore {
                 .bind(input.prize_asset) // Bind the prize asset
                 .bind(input.value) // Bind the prize value
                 .bind(input.status) // Bind the status
+                .bind(input.source) // Bind the source
+                .bind(input.sponsor) // Bind the optional sponsor
                 .bind(Utc::now()) // Bind the created_at timestamp
                 .bind(Utc::now()) // Bind the updated_at timestamp
                 .fetch_one(conn.as_mut())
@@ -1,5 +1,5 @@
 use chrono::{DateTime, Utc};
-use entity::{prelude::LotteryStatus, prize::PrizeStatus};
+use entity::{prelude::LotteryStatus, prize::{PrizeSource, PrizeStatus}};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -10,6 +10,8 @@ pub struct CreatePrize {
     pub prize_asset: Uuid,
     pub value: Decimal,
     pub status: PrizeStatus,
+    pub source: PrizeSource,
+    pub sponsor: Option<Uuid>,
 }
 
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
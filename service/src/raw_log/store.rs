@@ -0,0 +1,129 @@
+use crate::{define_find_all_fns, raw_log::types::CreateRawLog};
+use entity::raw_log::RawLogModel;
+use error_stack::{Result, ResultExt};
+use lib::error::Error;
+use sqlx::{Acquire, Postgres};
+use std::future::Future;
+use uuid::Uuid;
+
+pub struct RawLogStore;
+
+impl RawLogStore {
+    define_find_all_fns!(
+        find_pending_by_chain,
+        r#"
+            SELECT * FROM raw_log
+            WHERE chain = $1 AND reprocessed_at IS NULL
+            ORDER BY block_number ASC NULLS LAST, log_index ASC NULLS LAST
+        "#,
+        String,
+        RawLogModel
+    );
+
+    /// Archive a rejected log, a log rejected again only refreshes its reason
+    #[allow(clippy::manual_async_fn)]
+    pub fn create<'a, 'c, Conn>(
+        conn: Conn,
+        input: CreateRawLog,
+    ) -> impl Future<Output = Result<RawLogModel, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn
+                .acquire()
+                .await
+                .change_context(Error::StoreTransactionFailed)?;
+
+            let query = r#"
+                INSERT INTO raw_log (
+                    id, chain, address, topics, data, block_number, transaction_hash, log_index, reason
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                ON CONFLICT (transaction_hash, log_index) DO UPDATE
+                SET reason = EXCLUDED.reason, updated_at = NOW()
+                RETURNING *
+            "#;
+
+            let raw_log = sqlx::query_as(query)
+                .bind(Uuid::new_v4()) // Generate a new UUID for the raw log
+                .bind(input.chain) // Bind the chain name
+                .bind(input.address) // Bind the emitter address
+                .bind(input.topics) // Bind the topics
+                .bind(input.data) // Bind the data
+                .bind(input.block_number) // Bind the optional block number
+                .bind(input.transaction_hash) // Bind the optional transaction hash
+                .bind(input.log_index) // Bind the optional log index
+                .bind(input.reason) // Bind the rejection reason
+                .fetch_one(conn.as_mut())
+                .await
+                .change_context(Error::StoreInsertFailed)?;
+
+            Ok(raw_log)
+        }
+    }
+
+    /// Mark the raw log as decoded and handled
+    pub fn mark_reprocessed<'a, 'c, Conn>(
+        conn: Conn,
+        id: Uuid,
+    ) -> impl Future<Output = Result<RawLogModel, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn
+                .acquire()
+                .await
+                .change_context(Error::StoreTransactionFailed)?;
+
+            let query = r#"
+                UPDATE raw_log
+                SET reprocessed_at = NOW(), updated_at = NOW()
+                WHERE id = $1
+                RETURNING *
+            "#;
+
+            let raw_log = sqlx::query_as(query)
+                .bind(id) // Bind the raw log ID to update
+                .fetch_one(conn.as_mut())
+                .await
+                .change_context(Error::StoreUpdateFailed)?;
+
+            Ok(raw_log)
+        }
+    }
+
+    /// Refresh the reason of a raw log which still can't be decoded
+    pub fn update_reason<'a, 'c, Conn>(
+        conn: Conn,
+        id: Uuid,
+        reason: String,
+    ) -> impl Future<Output = Result<RawLogModel, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn
+                .acquire()
+                .await
+                .change_context(Error::StoreTransactionFailed)?;
+
+            let query = r#"
+                UPDATE raw_log
+                SET reason = $2, updated_at = NOW()
+                WHERE id = $1
+                RETURNING *
+            "#;
+
+            let raw_log = sqlx::query_as(query)
+                .bind(id) // Bind the raw log ID to update
+                .bind(reason) // Bind the rejection reason
+                .fetch_one(conn.as_mut())
+                .await
+                .change_context(Error::StoreUpdateFailed)?;
+
+            Ok(raw_log)
+        }
+    }
+}
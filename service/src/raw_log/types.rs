@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateRawLog {
+    pub chain: String,
+    pub address: String,
+    pub topics: Vec<String>,
+    pub data: String,
+    pub block_number: Option<i64>,
+    pub transaction_hash: Option<String>,
+    pub log_index: Option<i32>,
+    pub reason: String,
+}
@@ -0,0 +1,414 @@
+pub mod store;
+pub mod types;
+
+use std::{str::FromStr, sync::Arc};
+
+use chrono::Utc;
+use entity::{
+    draw::DrawStatus,
+    lottery::{LotteryModel, LotteryStatus},
+    reconciliation_report::{ReconciliationDriftKind, ReconciliationReportModel},
+};
+use error_stack::{Report, Result};
+use ethers::types::{Address, H256};
+use lib::error::Error;
+use serenity::async_trait;
+use store::ReconciliationStore;
+use tracing::{error, info, warn};
+use types::CreateReconciliationReport;
+use uuid::Uuid;
+
+use crate::{
+    account::{store::AccountStore, types::CreateAccount, AccountService},
+    chain::{
+        provider::ChainProvider,
+        traits::string::ToHexString,
+        utils::get_lottery_provider::{get_lottery_data, get_number_of_tickets_by_address, LotteryChainData},
+    },
+    chain_state::ChainStateService,
+    config::service::ReconciliationConfig,
+    draw::{store::DrawStore, types::UpdateDraw, DrawService},
+    lottery::{store::LotteryStore, LotteryService},
+    prelude::{ServiceProvider, StoreService},
+    services::ServiceFactory,
+    store::service::DatabaseTransaction,
+    ticket::store::TicketStore,
+    transaction::store::TransactionStore,
+};
+
+/// Compare indexed lotteries with the state held by the lottery provider contract
+pub struct ReconciliationService {
+    pub store: Arc<StoreService>,
+    pub account_service: Arc<AccountService>,
+    pub chain_state_service: Arc<ChainStateService>,
+    pub draw_service: Arc<DrawService>,
+    pub lottery_service: Arc<LotteryService>,
+}
+
+impl ReconciliationService {
+    pub fn new(
+        store: Arc<StoreService>,
+        account_service: Arc<AccountService>,
+        chain_state_service: Arc<ChainStateService>,
+        draw_service: Arc<DrawService>,
+        lottery_service: Arc<LotteryService>,
+    ) -> Self {
+        Self {
+            store,
+            account_service,
+            chain_state_service,
+            draw_service,
+            lottery_service,
+        }
+    }
+
+    /// Reconcile every non-final lottery against the chain of the provider.
+    ///
+    /// The chain is read at the last block fully committed by the indexer, so events still
+    /// being indexed are never reported as drifts. Each drift is stored as a report entry and
+    /// raised as an error log, failures on a single lottery are logged and don't stop the run.
+    pub async fn reconcile<Provider: ChainProvider>(
+        &self,
+        provider: &Provider,
+        config: &ReconciliationConfig,
+    ) -> Result<Vec<ReconciliationReportModel>, Error> {
+        let chain_config = provider.get_config();
+        let Some(contract_address) = chain_config.contracts.get("provider").copied() else {
+            return Err(Report::new(Error::ConfigInvalid)
+                .attach_printable("Missing `provider` contract address"));
+        };
+
+        // Events of the stored block may still be processed, only the previous one is complete
+        let Some(state) = self.chain_state_service.get_state(provider.name()).await? else {
+            info!(chain = provider.name(), "Nothing indexed yet, skipping reconciliation");
+            return Ok(Vec::new());
+        };
+        let block_number = state.block_number.saturating_sub(1);
+
+        let lotteries = LotteryStore::find_all_non_final(self.store.read()).await?;
+        let mut reports = Vec::new();
+
+        for lottery in lotteries {
+            match self.reconcile_lottery(provider, contract_address, block_number, &lottery, config).await {
+                Ok(lottery_reports) => reports.extend(lottery_reports),
+                Err(e) => warn!(
+                    lottery_id = lottery.id.to_string(),
+                    reason = ?e,
+                    "Failed to reconcile lottery",
+                ),
+            }
+        }
+
+        info!(
+            chain = provider.name(),
+            block_number,
+            drifts = reports.len(),
+            "Reconciliation finished",
+        );
+
+        Ok(reports)
+    }
+
+    async fn reconcile_lottery<Provider: ChainProvider>(
+        &self,
+        provider: &Provider,
+        contract_address: Address,
+        block_number: u64,
+        lottery: &LotteryModel,
+        config: &ReconciliationConfig,
+    ) -> Result<Vec<ReconciliationReportModel>, Error> {
+        let Ok(lottery_uid) = H256::from_str(&lottery.uid) else {
+            warn!(lottery_uid = lottery.uid, "Lottery UID is not a valid bytes32");
+            return Ok(Vec::new());
+        };
+
+        // Read the chain before opening the transaction, so it is never held across RPC calls
+        let chain_data = get_lottery_data(provider, contract_address, lottery_uid, Some(block_number)).await?;
+
+        // Unknown lotteries are zeroed by the contract, they belong to another chain
+        if chain_data.entrance_token_address.is_zero() {
+            return Ok(Vec::new());
+        }
+
+        let participants = TicketStore::sample_participants_by_lottery_id(
+            self.store.read(),
+            lottery.id,
+            config.participants_sample,
+        )
+        .await?;
+
+        let mut participant_tickets = Vec::with_capacity(participants.len());
+        for participant in participants {
+            let Ok(address) = Address::from_str(&participant.address) else {
+                continue;
+            };
+
+            let tickets = get_number_of_tickets_by_address(
+                provider,
+                contract_address,
+                lottery_uid,
+                address,
+                Some(block_number),
+            )
+            .await?;
+
+            participant_tickets.push((participant, tickets));
+        }
+
+        let chain = provider.name();
+        let mut db_tx = self.store.begin_transaction().await?;
+
+        // The indexer went past the block read on-chain, the lottery is checked on the next run
+        if self.is_ahead_of_block(&chain, lottery, block_number as i64, &mut db_tx).await? {
+            self.store.rollback_transaction(db_tx).await?;
+            return Ok(Vec::new());
+        }
+
+        let mut drifts = Vec::new();
+
+        let sold_tickets = TicketStore::count_sold_by_lottery_id(db_tx.as_mut(), lottery.id).await?;
+        if sold_tickets != chain_data.sold_tickets as i64 {
+            // Missing or extra purchases can only be fixed by reindexing
+            drifts.push(CreateReconciliationReport {
+                chain: chain.clone(),
+                lottery_id: lottery.id,
+                account_id: None,
+                kind: ReconciliationDriftKind::SoldTickets,
+                expected: chain_data.sold_tickets.to_string(),
+                actual: sold_tickets.to_string(),
+                repaired: false,
+            });
+        }
+
+        if let Some(drift) = self.check_status(&chain, lottery, &chain_data, config, &mut db_tx).await? {
+            drifts.push(drift);
+        }
+
+        if let Some(drift) = self.check_winner(&chain, lottery, &chain_data, config, &mut db_tx).await? {
+            drifts.push(drift);
+        }
+
+        for (participant, tickets) in participant_tickets {
+            if tickets as i64 != participant.amount {
+                drifts.push(CreateReconciliationReport {
+                    chain: chain.clone(),
+                    lottery_id: lottery.id,
+                    account_id: Some(participant.account_id),
+                    kind: ReconciliationDriftKind::ParticipantTickets,
+                    expected: tickets.to_string(),
+                    actual: participant.amount.to_string(),
+                    repaired: false,
+                });
+            }
+        }
+
+        let mut reports = Vec::with_capacity(drifts.len());
+        for drift in drifts {
+            let report = ReconciliationStore::create(db_tx.as_mut(), drift).await?;
+
+            error!(
+                alert = "reconciliation_drift",
+                chain = report.chain,
+                lottery_id = lottery.id.to_string(),
+                lottery_uid = lottery.uid,
+                kind = report.kind.to_string(),
+                expected = report.expected,
+                actual = report.actual,
+                repaired = report.repaired,
+                "Lottery state drifted from chain",
+            );
+
+            reports.push(report);
+        }
+
+        self.store.commit_transaction(db_tx).await?;
+
+        Ok(reports)
+    }
+
+    /// Whether the lottery, its draws or its tickets were changed by events after the block
+    async fn is_ahead_of_block(
+        &self,
+        chain: &str,
+        lottery: &LotteryModel,
+        block_number: i64,
+        db_tx: &mut DatabaseTransaction<'_>,
+    ) -> Result<bool, Error> {
+        if TicketStore::has_purchases_after(db_tx, lottery.id, block_number, i32::MAX).await? {
+            return Ok(true);
+        }
+
+        let draws = DrawStore::find_all_by_lottery_id(db_tx.as_mut(), lottery.id).await?;
+        let entity_ids: Vec<Uuid> = std::iter::once(lottery.id)
+            .chain(draws.iter().map(|draw| draw.id))
+            .collect();
+
+        TransactionStore::has_side_effects_after(db_tx, chain, &entity_ids, block_number).await
+    }
+
+    /// An active lottery on-chain must be ongoing in the database, and vice versa
+    async fn check_status(
+        &self,
+        chain: &str,
+        lottery: &LotteryModel,
+        chain_data: &LotteryChainData,
+        config: &ReconciliationConfig,
+        db_tx: &mut DatabaseTransaction<'_>,
+    ) -> Result<Option<CreateReconciliationReport>, Error> {
+        let Some(expected) = expected_status(lottery.status, chain_data.is_active) else {
+            return Ok(None);
+        };
+
+        // Only transitions allowed by the lottery lifecycle can be repaired
+        let repaired = config.auto_repair && lottery.status.can_transition_to(expected);
+        if repaired {
+            self.lottery_service.transition_status(lottery.id, expected, None, db_tx).await?;
+        }
+
+        Ok(Some(CreateReconciliationReport {
+            chain: chain.to_string(),
+            lottery_id: lottery.id,
+            account_id: None,
+            kind: ReconciliationDriftKind::Status,
+            expected: expected.to_string(),
+            actual: lottery.status.to_string(),
+            repaired,
+        }))
+    }
+
+    /// The winner picked on-chain must match the winner of the draw
+    async fn check_winner(
+        &self,
+        chain: &str,
+        lottery: &LotteryModel,
+        chain_data: &LotteryChainData,
+        config: &ReconciliationConfig,
+        db_tx: &mut DatabaseTransaction<'_>,
+    ) -> Result<Option<CreateReconciliationReport>, Error> {
+        let draws = DrawStore::find_winners_by_lottery_id(db_tx.as_mut(), lottery.id).await?;
+
+        let mut winners = Vec::with_capacity(draws.len());
+        for account_id in draws.iter().filter_map(|draw| draw.winner) {
+            winners.push(AccountStore::find_by_id(db_tx.as_mut(), account_id).await?);
+        }
+
+        // Lotteries paying several places expose a single winner on-chain, any of the
+        // winners of the draws may match it
+        let expected = chain_data.winner.to_hex_string();
+        let addresses: Vec<&str> = winners.iter().map(|account| account.address.as_str()).collect();
+        if is_winner_matching(chain_data.winner, &addresses) {
+            return Ok(None);
+        }
+
+        let winner = winners.first().cloned();
+        let actual = winner
+            .as_ref()
+            .map(|account| account.address.to_lowercase())
+            .unwrap_or_else(|| Address::zero().to_hex_string());
+
+        // A winner can only be repaired when the chain has one
+        let repaired = config.auto_repair && !chain_data.winner.is_zero();
+        let account_id = if repaired {
+            let dto = CreateAccount {
+                address: expected.clone(),
+                created_at: Utc::now(),
+                referral_code: None,
+            };
+
+            let account = self.account_service.create_if_no_exists(dto, db_tx).await?;
+
+            let dto = UpdateDraw {
+                winner: Some(account.id),
+                draw_date: Some(Utc::now()),
+                status: Some(DrawStatus::Completed),
+                ..Default::default()
+            };
+
+            self.draw_service.mark_winner_as_drawn(lottery.id, dto, None, db_tx).await?;
+
+            Some(account.id)
+        } else {
+            winner.map(|account| account.id)
+        };
+
+        Ok(Some(CreateReconciliationReport {
+            chain: chain.to_string(),
+            lottery_id: lottery.id,
+            account_id,
+            kind: ReconciliationDriftKind::Winner,
+            expected,
+            actual,
+            repaired,
+        }))
+    }
+}
+
+/// Status the lottery should have given its on-chain activity, `None` when it is consistent.
+///
+/// Scheduled lotteries are not opened on-chain yet, so being inactive is expected.
+fn expected_status(status: LotteryStatus, is_active: bool) -> Option<LotteryStatus> {
+    match (status, is_active) {
+        (LotteryStatus::Scheduled, true) => Some(LotteryStatus::Ongoing),
+        (LotteryStatus::Ongoing, false) => Some(LotteryStatus::Completed),
+        (LotteryStatus::Completed | LotteryStatus::Cancelled, true) => Some(LotteryStatus::Ongoing),
+        _ => None,
+    }
+}
+
+/// Lotteries paying several places expose a single winner on-chain, any of the winners of
+/// the draws may match it
+fn is_winner_matching(chain_winner: Address, winners: &[&str]) -> bool {
+    if chain_winner.is_zero() {
+        return winners.is_empty();
+    }
+
+    let expected = chain_winner.to_hex_string();
+    winners.iter().any(|address| address.to_lowercase() == expected)
+}
+
+#[async_trait]
+impl ServiceFactory for ReconciliationService {
+    async fn factory(services: ServiceProvider) -> Result<Self, Error> {
+        let store = services.get_service_unchecked::<StoreService>().await;
+        let account_service = services.get_service_unchecked::<AccountService>().await;
+        let chain_state_service = services.get_service_unchecked::<ChainStateService>().await;
+        let draw_service = services.get_service_unchecked::<DrawService>().await;
+        let lottery_service = services.get_service_unchecked::<LotteryService>().await;
+
+        Ok(Self {
+            store,
+            account_service,
+            chain_state_service,
+            draw_service,
+            lottery_service,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expected_status() {
+        assert_eq!(expected_status(LotteryStatus::Scheduled, false), None);
+        assert_eq!(expected_status(LotteryStatus::Scheduled, true), Some(LotteryStatus::Ongoing));
+        assert_eq!(expected_status(LotteryStatus::Ongoing, true), None);
+        assert_eq!(expected_status(LotteryStatus::Ongoing, false), Some(LotteryStatus::Completed));
+        assert_eq!(expected_status(LotteryStatus::Completed, false), None);
+        assert_eq!(expected_status(LotteryStatus::Cancelled, true), Some(LotteryStatus::Ongoing));
+    }
+
+    #[test]
+    fn test_is_winner_matching() {
+        let winner = Address::from_low_u64_be(42);
+        let address = winner.to_hex_string();
+        let other = Address::from_low_u64_be(7).to_hex_string();
+
+        assert!(is_winner_matching(Address::zero(), &[]));
+        assert!(!is_winner_matching(Address::zero(), &[&address]));
+        assert!(!is_winner_matching(winner, &[]));
+        assert!(is_winner_matching(winner, &[&other, &address.to_uppercase().replace("0X", "0x")]));
+        assert!(!is_winner_matching(winner, &[&other]));
+    }
+}
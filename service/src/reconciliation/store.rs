@@ -0,0 +1,60 @@
+use crate::{define_find_all_fns, reconciliation::types::CreateReconciliationReport};
+use chrono::Utc;
+use entity::reconciliation_report::ReconciliationReportModel;
+use error_stack::{Result, ResultExt};
+use lib::error::Error;
+use sqlx::{Acquire, Postgres};
+use std::future::Future;
+use uuid::Uuid;
+
+pub struct ReconciliationStore;
+
+impl ReconciliationStore {
+    define_find_all_fns!(
+        find_by_lottery_id,
+        "SELECT * FROM reconciliation_report WHERE lottery_id = $1 ORDER BY created_at DESC",
+        Uuid,
+        ReconciliationReportModel
+    );
+
+    // Create a new report entry
+    #[allow(clippy::manual_async_fn)]
+    pub fn create<'a, 'c, Conn>(
+        conn: Conn,
+        input: CreateReconciliationReport,
+    ) -> impl Future<Output = Result<ReconciliationReportModel, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn
+                .acquire()
+                .await
+                .change_context(Error::StoreTransactionFailed)?;
+
+            let query = r#"
+                INSERT INTO reconciliation_report (
+                    id, chain, lottery_id, account_id, kind, expected, actual, repaired, created_at
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                RETURNING *
+            "#;
+
+            let report = sqlx::query_as(query)
+                .bind(Uuid::new_v4()) // Generate a new UUID for the report
+                .bind(input.chain) // Bind the chain name
+                .bind(input.lottery_id) // Bind the lottery ID
+                .bind(input.account_id) // Bind the optional account ID
+                .bind(input.kind) // Bind the drift kind
+                .bind(input.expected) // Bind the on-chain value
+                .bind(input.actual) // Bind the indexed value
+                .bind(input.repaired) // Bind the repaired flag
+                .bind(Utc::now()) // Bind the created_at timestamp
+                .fetch_one(conn.as_mut())
+                .await
+                .change_context(Error::StoreInsertFailed)?;
+
+            Ok(report)
+        }
+    }
+}
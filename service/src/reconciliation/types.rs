@@ -0,0 +1,14 @@
+use entity::reconciliation_report::ReconciliationDriftKind;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateReconciliationReport {
+    pub chain: String,
+    pub lottery_id: Uuid,
+    pub account_id: Option<Uuid>,
+    pub kind: ReconciliationDriftKind,
+    pub expected: String,
+    pub actual: String,
+    pub repaired: bool,
+}
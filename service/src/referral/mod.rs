@@ -0,0 +1,156 @@
+pub mod store;
+pub mod types;
+
+use crate::account::store::AccountStore;
+use crate::asset::store::AssetStore;
+use crate::config::service::ConfigService;
+use crate::prelude::ServiceProvider;
+use crate::services::ServiceFactory;
+use crate::store::service::DatabaseTransaction;
+use entity::account::AccountModel;
+use entity::referral_reward::ReferralRewardModel;
+use entity::ticket::TicketModel;
+use error_stack::{Result, ResultExt};
+use lib::error::Error;
+use rand::{thread_rng, Rng};
+use serenity::async_trait;
+use sqlx::{types::Decimal, Acquire};
+use std::sync::Arc;
+use store::ReferralStore;
+use types::CreateReferralReward;
+use uuid::Uuid;
+
+/// Characters of the referral codes, without the ones easily mistaken for each other
+const REFERRAL_CODE_ALPHABET: [char; 31] = [
+    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'J', 'K', 'L', 'M', 'N', 'P', 'Q', 'R', 'S', 'T', 'U',
+    'V', 'W', 'X', 'Y', 'Z', '2', '3', '4', '5', '6', '7', '8',
+];
+
+const REFERRAL_CODE_LENGTH: usize = 8;
+
+/// Codes generated before giving up, each one colliding with an existing code
+const REFERRAL_CODE_ATTEMPTS: usize = 5;
+
+fn generate_referral_code() -> String {
+    let mut rng = thread_rng();
+
+    (0..REFERRAL_CODE_LENGTH)
+        .map(|_| REFERRAL_CODE_ALPHABET[rng.gen_range(0..REFERRAL_CODE_ALPHABET.len())])
+        .collect()
+}
+
+/// Part of the amount spent accrued to the referrer
+fn reward_amount(spent: Decimal, rate_bps: u32) -> Decimal {
+    spent * Decimal::from(rate_bps) / Decimal::from(10_000)
+}
+
+pub struct ReferralService {
+    config: Arc<ConfigService>,
+}
+
+impl ReferralService {
+    pub fn new(config: Arc<ConfigService>) -> Self {
+        Self { config }
+    }
+
+    /// Generate the referral code of the account, the existing one is kept if any
+    pub async fn generate_code(
+        &self,
+        account_id: Uuid,
+        db_tx: &mut DatabaseTransaction<'_>,
+    ) -> Result<AccountModel, Error> {
+        for _ in 1..REFERRAL_CODE_ATTEMPTS {
+            // The savepoint keeps the transaction usable when the code is already taken
+            let mut savepoint = db_tx.as_mut().begin().await.change_context(Error::StoreTransactionFailed)?;
+            match AccountStore::set_referral_code(savepoint.as_mut(), account_id, generate_referral_code()).await {
+                Ok(account) => {
+                    savepoint.commit().await.change_context(Error::StoreTransactionFailed)?;
+                    return Ok(account);
+                }
+                Err(e) if matches!(e.current_context(), Error::ReferralCodeTaken) => {
+                    savepoint.rollback().await.change_context(Error::StoreTransactionFailed)?;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        AccountStore::set_referral_code(db_tx.as_mut(), account_id, generate_referral_code()).await
+    }
+
+    /// Link to share for new accounts to claim the code
+    pub fn link(&self, code: &str) -> String {
+        format!("{}{code}", self.config.referral.link_base_url)
+    }
+
+    /// Accrue the reward of the purchase to the referrer of the buyer.
+    ///
+    /// Returns `None` when the buyer wasn't referred, the asset isn't rewarded,
+    /// or the purchase was already rewarded.
+    pub async fn accrue(
+        &self,
+        ticket: &TicketModel,
+        db_tx: &mut DatabaseTransaction<'_>,
+    ) -> Result<Option<ReferralRewardModel>, Error> {
+        let buyer = AccountStore::find_by_id(db_tx.as_mut(), ticket.account_id).await?;
+        let Some(referrer_id) = buyer.referrer_id else {
+            return Ok(None);
+        };
+
+        let asset = AssetStore::find_by_id(db_tx.as_mut(), ticket.ticket_asset).await?;
+        let Some(rate_bps) = self.config.referral.reward_bps(&asset.address) else {
+            return Ok(None);
+        };
+
+        let spent = ticket.ticket_price * Decimal::from(ticket.amount);
+        let dto = CreateReferralReward {
+            referrer_id,
+            referred_id: buyer.id,
+            ticket_id: ticket.id,
+            asset_id: asset.id,
+            spent,
+            rate_bps: rate_bps as i32,
+            amount: reward_amount(spent, rate_bps),
+        };
+
+        ReferralStore::create_reward(db_tx.as_mut(), dto).await
+    }
+}
+
+#[async_trait]
+impl ServiceFactory for ReferralService {
+    async fn factory(services: ServiceProvider) -> Result<Self, Error> {
+        let config = services.get_service_unchecked::<ConfigService>().await;
+
+        Ok(Self { config })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn reward_amount_is_the_rate_of_the_amount_spent() {
+        let spent = Decimal::from_str("12.5").unwrap();
+
+        assert_eq!(reward_amount(spent, 500), Decimal::from_str("0.625").unwrap());
+        assert_eq!(reward_amount(spent, 10_000), spent);
+        assert_eq!(reward_amount(spent, 0), Decimal::ZERO);
+    }
+
+    #[test]
+    fn reward_amount_keeps_fractions_of_small_purchases() {
+        let spent = Decimal::from_str("0.000001").unwrap();
+
+        assert_eq!(reward_amount(spent, 1), Decimal::from_str("0.0000000001").unwrap());
+    }
+
+    #[test]
+    fn generated_codes_use_the_alphabet() {
+        let code = generate_referral_code();
+
+        assert_eq!(code.len(), REFERRAL_CODE_LENGTH);
+        assert!(code.chars().all(|c| REFERRAL_CODE_ALPHABET.contains(&c)));
+    }
+}
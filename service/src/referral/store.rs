@@ -0,0 +1,72 @@
+use crate::account::types::AssetAmount;
+use crate::referral::types::CreateReferralReward;
+use entity::referral_reward::ReferralRewardModel;
+use error_stack::{Result, ResultExt};
+use lib::error::Error;
+use sqlx::{Acquire, PgPool, Postgres};
+use std::future::Future;
+use uuid::Uuid;
+
+pub struct ReferralStore;
+
+impl ReferralStore {
+    /// Create the reward of the purchase, `None` if it was already rewarded
+    #[allow(clippy::manual_async_fn)]
+    pub fn create_reward<'a, 'c, Conn>(
+        conn: Conn,
+        input: CreateReferralReward,
+    ) -> impl Future<Output = Result<Option<ReferralRewardModel>, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn
+                .acquire()
+                .await
+                .change_context(Error::StoreTransactionFailed)?;
+
+            let query = r#"
+                INSERT INTO referral_reward (
+                    id, referrer_id, referred_id, ticket_id, asset_id, spent, rate_bps, amount
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                ON CONFLICT (ticket_id) DO NOTHING
+                RETURNING *
+            "#;
+
+            let reward = sqlx::query_as(query)
+                .bind(Uuid::new_v4()) // Generate a new UUID for the reward
+                .bind(input.referrer_id) // Bind the rewarded referrer
+                .bind(input.referred_id) // Bind the buyer
+                .bind(input.ticket_id) // Bind the rewarded purchase
+                .bind(input.asset_id) // Bind the asset of the purchase
+                .bind(input.spent) // Bind the amount spent
+                .bind(input.rate_bps) // Bind the reward rate
+                .bind(input.amount) // Bind the accrued amount
+                .fetch_optional(conn.as_mut())
+                .await
+                .change_context(Error::StoreInsertFailed)?;
+
+            Ok(reward)
+        }
+    }
+
+    /// Rewards accrued to the referrer, per asset
+    pub async fn find_totals_by_referrer_id(
+        pool: &PgPool,
+        referrer_id: Uuid,
+    ) -> Result<Vec<AssetAmount>, Error> {
+        let query = r#"
+            SELECT asset_id, SUM(amount) AS amount
+            FROM referral_reward
+            WHERE referrer_id = $1
+            GROUP BY asset_id
+        "#;
+
+        sqlx::query_as(query)
+            .bind(referrer_id) // Bind the referrer ID
+            .fetch_all(pool)
+            .await
+            .change_context(Error::Store)
+    }
+}
@@ -0,0 +1,13 @@
+use sqlx::types::Decimal;
+use uuid::Uuid;
+
+#[derive(Clone, Debug)]
+pub struct CreateReferralReward {
+    pub referrer_id: Uuid,
+    pub referred_id: Uuid,
+    pub ticket_id: Uuid,
+    pub asset_id: Uuid,
+    pub spent: Decimal,
+    pub rate_bps: i32,
+    pub amount: Decimal,
+}
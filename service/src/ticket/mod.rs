@@ -11,22 +11,25 @@ use rust_decimal::Decimal;
 use serenity::async_trait;
 use error_stack::{Report, Result};
 use store::TicketStore;
-use tracing::{error, info};
 use types::CreateTicket;
-use crate::{chain::types::EventContext, lottery::store::LotteryStore, message_broker::MessageBrokerService, prelude::{ServiceProvider, StoreService}, prize::{store::PrizeStore, types::UpdatePrize}, services::ServiceFactory, store::service::DatabaseTransaction, transaction::{service::TransactionService, types::{CreateTransaction, TransactionSideEffect}}};
+use crate::{achievement::{AchievementService, PURCHASE_METRICS}, chain::types::EventContext, lottery::store::LotteryStore, message_broker::{channels, dedup_key, MessageBrokerService}, prelude::{ServiceProvider, StoreService}, prize::{store::PrizeStore, types::UpdatePrize}, referral::ReferralService, services::ServiceFactory, store::service::DatabaseTransaction, transaction::{service::TransactionService, types::{CreateTransaction, TransactionSideEffect}}};
 
 pub struct TicketService {
    pub store: Arc<StoreService>,
    pub transaction_service: Arc<TransactionService>,
    pub message_broker: Arc<MessageBrokerService>,
+   pub referral_service: Arc<ReferralService>,
+   pub achievement_service: Arc<AchievementService>,
 }
 
 impl TicketService {
-    pub fn new(store: Arc<StoreService>, transaction_service: Arc<TransactionService>, message_broker: Arc<MessageBrokerService>) -> Self {
+    pub fn new(store: Arc<StoreService>, transaction_service: Arc<TransactionService>, message_broker: Arc<MessageBrokerService>, referral_service: Arc<ReferralService>, achievement_service: Arc<AchievementService>) -> Self {
         Self {
             store,
             transaction_service,
-            message_broker
+            message_broker,
+            referral_service,
+            achievement_service,
         }
     }
     
@@ -36,8 +39,14 @@ impl TicketService {
         context: Option<EventContext>,
         db_tx: &mut DatabaseTransaction<'_>,
     ) -> Result<TicketModel, Error> {
-        let tickets = TicketStore::create(db_tx.as_mut(), input.clone()).await?;
-        
+        let mut tickets = TicketStore::create(db_tx.as_mut(), input.clone()).await?;
+
+        // Purchase indexed out of on-chain order, ranges of the lottery must be shifted
+        if TicketStore::has_purchases_after(db_tx, tickets.lottery_id, tickets.block_number, tickets.log_index).await? {
+            TicketStore::renumber_by_lottery_id(db_tx, tickets.lottery_id).await?;
+            tickets = TicketStore::find_by_id(db_tx.as_mut(), tickets.id).await?;
+        }
+
         // We should now update prize pool
         let lottery = LotteryStore::find_by_id(db_tx.as_mut(), tickets.lottery_id).await?;
         
@@ -60,9 +69,28 @@ impl TicketService {
         
         let prize = PrizeStore::update(db_tx.as_mut(), prize_pool.id, dto).await?;
         
-        if let Err(e)  = self.message_broker.send("ticket_bought".to_string(), tickets.clone()).await {
-            error!("Failed to send ticket bought event: {e:?}");
+        let mut side_effects = vec![
+            TransactionSideEffect::new(tickets.id, "ticket"),
+            TransactionSideEffect::new(prize.id, "prize"),
+        ];
+        
+        // Purchases of referred accounts accrue a reward to their referrer
+        if let Some(reward) = self.referral_service.accrue(&tickets, db_tx).await? {
+            side_effects.push(TransactionSideEffect::new(reward.id, "referral_reward"));
         }
+        
+        for unlock in self.achievement_service.evaluate(tickets.account_id, &PURCHASE_METRICS, context.as_ref(), db_tx).await? {
+            side_effects.push(TransactionSideEffect::new(unlock.id, "account_achievement"));
+        }
+        
+        // Published once the transaction is committed, subscribers never see rolled back tickets
+        let ticket_key = dedup_key(channels::TICKET_BOUGHT, tickets.id, context.as_ref());
+        self.message_broker.enqueue(channels::TICKET_BOUGHT, &tickets, ticket_key, db_tx).await?;
+        
+        let prize_key = dedup_key(channels::PRIZE_POOL_UPDATED, prize.id, context.as_ref());
+        self.message_broker.enqueue(channels::PRIZE_POOL_UPDATED, &prize, prize_key, db_tx).await?;
+        
+        self.transaction_service.record_side_effects(context, side_effects, db_tx).await?;
     
         Ok(tickets)
     }
@@ -79,6 +107,8 @@ impl ServiceFactory for TicketService {
             store,
             transaction_service: services.get_service_unchecked::<TransactionService>().await,
             message_broker,
+            referral_service: services.get_service_unchecked::<ReferralService>().await,
+            achievement_service: services.get_service_unchecked::<AchievementService>().await,
         })
     }
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-use crate::{define_find_all_fns, define_find_optional_fns, ticket::types::{CreateTicket, UpdateTicket}};
+use crate::{common::types::KeysetCursor, define_find_all_fns, define_find_optional_fns, store::service::DatabaseTransaction, ticket::types::{CreateTicket, LotteryTicketStats, ParticipantTickets, UpdateTicket}};
 use chrono::{DateTime, Utc};
 use entity::ticket::{TicketModel};
 use error_stack::{Result, ResultExt};
@@ -27,17 +27,22 @@ impl TicketStore {
     );
     
     define_find_all_fns!(
-        find_last_bought_tickets_by_lottery_id,
-        "SELECT * FROM ticket WHERE lottery_id = $1 ORDER BY purchased_at DESC",
+        find_by_lottery_id,
+        "SELECT * FROM ticket WHERE lottery_id = $1 ORDER BY first_number ASC",
         Uuid,
         TicketModel
     );
     
     define_find_all_fns!(
-        find_by_lottery_id,
-        "SELECT * FROM ticket WHERE lottery_id = $1",
-        Uuid,
-        TicketModel
+        find_stats_by_lottery_ids,
+        r#"
+            SELECT lottery_id, SUM(amount) AS tickets_sold, COUNT(DISTINCT account_id) AS participants
+            FROM ticket
+            WHERE lottery_id = ANY($1)
+            GROUP BY lottery_id
+        "#,
+        Vec<Uuid>,
+        LotteryTicketStats
     );
     
 
@@ -56,11 +61,19 @@ impl TicketStore {
                 .await
                 .change_context(Error::StoreTransactionFailed)?;
 
+            // Ticket numbers continue from the last purchase of the lottery, events are
+            // processed in on-chain order so ranges follow (block_number, log_index)
             let query = r#"
                 INSERT INTO ticket (
-                    id, lottery_id, account_id, ticket_price, ticket_asset, amount, transaction_hash, purchased_at, created_at, updated_at
+                    id, lottery_id, account_id, ticket_price, ticket_asset, amount, transaction_hash, purchased_at,
+                    block_number, log_index, first_number, last_number, created_at, updated_at
                 )
-                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                SELECT $1, $2, $3, $4, $5, $6, $7, $8, $9, $10,
+                    COALESCE(MAX(last_number), 0) + 1,
+                    COALESCE(MAX(last_number), 0) + $6,
+                    $11, $12
+                FROM ticket
+                WHERE lottery_id = $2
                 RETURNING *
             "#;
 
@@ -73,6 +86,8 @@ impl TicketStore {
                 .bind(input.amount) // Bind the number of tickets
                 .bind(input.transaction_hash) // Bind the transaction hash
                 .bind(input.purchased_at) // Bind the purchase timestamp
+                .bind(input.block_number) // Bind the block number of the purchase
+                .bind(input.log_index) // Bind the log index of the purchase
                 .bind(Utc::now()) // Bind the created_at timestamp
                 .bind(Utc::now()) // Bind the updated_at timestamp
                 .fetch_one(conn.as_mut())
@@ -154,43 +169,233 @@ impl TicketStore {
         Ok(tickets)
     }
     
-    pub async fn find_by_lottery_id_and_account_id(
-        pool: &PgPool,
+    pub fn find_by_lottery_id_and_account_id<'a, 'c, Conn>(
+        conn: Conn,
         lottery_id: Uuid,
         account_id: Uuid,
-    ) -> Result<Option<TicketModel>, Error> {
+    ) -> impl Future<Output = Result<Option<TicketModel>, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn.acquire().await.change_context(Error::Store)?;
+
+            let query = r#"
+                SELECT * FROM ticket WHERE lottery_id = $1 AND account_id = $2
+                ORDER BY first_number ASC
+                LIMIT 1
+                "#;
+
+            let tickets = sqlx::query_as(query)
+                .bind(lottery_id)
+                .bind(account_id)
+                .fetch_optional(&mut *conn)
+                .await
+                .change_context(Error::Store)?;
+
+            Ok(tickets)
+        }
+    }
+
+    /// Find the purchase which holds provided ticket number in the lottery
+    pub fn try_find_by_lottery_id_and_number<'a, 'c, Conn>(
+        conn: Conn,
+        lottery_id: Uuid,
+        number: i32,
+    ) -> impl Future<Output = Result<Option<TicketModel>, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn.acquire().await.change_context(Error::Store)?;
+
+            let query = r#"
+                SELECT * FROM ticket
+                WHERE lottery_id = $1 AND first_number <= $2 AND last_number >= $2
+                LIMIT 1
+                "#;
+
+            let ticket = sqlx::query_as(query)
+                .bind(lottery_id)
+                .bind(number)
+                .fetch_optional(&mut *conn)
+                .await
+                .change_context(Error::Store)?;
+
+            Ok(ticket)
+        }
+    }
+
+    /// Count tickets sold in the lottery, which is the last assigned ticket number
+    pub fn count_sold_by_lottery_id<'a, 'c, Conn>(
+        conn: Conn,
+        lottery_id: Uuid,
+    ) -> impl Future<Output = Result<i64, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn.acquire().await.change_context(Error::Store)?;
+
+            let query = r#"
+                SELECT COALESCE(MAX(last_number), 0)::BIGINT FROM ticket WHERE lottery_id = $1
+                "#;
+
+            let sold = sqlx::query_scalar(query)
+                .bind(lottery_id)
+                .fetch_one(&mut *conn)
+                .await
+                .change_context(Error::Store)?;
+
+            Ok(sold)
+        }
+    }
+
+    /// Random sample of participants of the lottery with their total tickets
+    pub async fn sample_participants_by_lottery_id(
+        pool: &PgPool,
+        lottery_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<ParticipantTickets>, Error> {
+        let query = r#"
+            SELECT account.id AS account_id, account.address, SUM(ticket.amount)::BIGINT AS amount
+            FROM ticket
+            INNER JOIN account ON ticket.account_id = account.id
+            WHERE ticket.lottery_id = $1
+            GROUP BY account.id, account.address
+            ORDER BY RANDOM()
+            LIMIT $2
+            "#;
+
+        let participants = sqlx::query_as(query)
+            .bind(lottery_id)
+            .bind(limit)
+            .fetch_all(pool)
+            .await
+            .change_context(Error::Store)?;
+
+        Ok(participants)
+    }
+
+    /// Check if the lottery has purchases placed on-chain after provided position
+    pub async fn has_purchases_after(
+        db_tx: &mut DatabaseTransaction<'_>,
+        lottery_id: Uuid,
+        block_number: i64,
+        log_index: i32,
+    ) -> Result<bool, Error> {
         let query = r#"
-            SELECT * FROM ticket WHERE lottery_id = $1 AND account_id = $2
-            LIMIT 1
+            SELECT EXISTS (
+                SELECT 1 FROM ticket
+                WHERE lottery_id = $1 AND (block_number, log_index) > ($2, $3)
+            )
             "#;
 
+        let exists = sqlx::query_scalar(query)
+            .bind(lottery_id)
+            .bind(block_number)
+            .bind(log_index)
+            .fetch_one(db_tx.as_mut())
+            .await
+            .change_context(Error::Store)?;
+
+        Ok(exists)
+    }
+
+    /// Re-assign ticket ranges of the lottery following on-chain purchase order
+    pub async fn renumber_by_lottery_id(
+        db_tx: &mut DatabaseTransaction<'_>,
+        lottery_id: Uuid,
+    ) -> Result<(), Error> {
+        let query = r#"
+            UPDATE ticket
+            SET first_number = numbered.last_number - ticket.amount + 1,
+                last_number = numbered.last_number,
+                updated_at = NOW()
+            FROM (
+                SELECT id, SUM(amount) OVER (
+                    ORDER BY block_number, log_index, purchased_at, created_at, id
+                ) AS last_number
+                FROM ticket
+                WHERE lottery_id = $1
+            ) numbered
+            WHERE numbered.id = ticket.id
+            "#;
+
+        sqlx::query(query)
+            .bind(lottery_id)
+            .execute(db_tx.as_mut())
+            .await
+            .change_context(Error::StoreUpdateFailed)?;
+
+        Ok(())
+    }
+
+    /// Sort of the cursors of the paginated ticket queries
+    pub const PAGE_SORT: &'static str = "purchased_at";
+
+    /// Find the last bought tickets of a lottery, starting after the cursor
+    pub async fn find_last_bought_tickets_by_lottery_id(
+        pool: &PgPool,
+        lottery_id: Uuid,
+        after: Option<KeysetCursor>,
+        limit: i64,
+    ) -> Result<Vec<TicketModel>, Error> {
+        let query = r#"
+            SELECT *
+            FROM ticket
+            WHERE lottery_id = $1
+                AND ($2::TIMESTAMPTZ IS NULL OR (purchased_at, id) < ($2, $3))
+            ORDER BY purchased_at DESC, id DESC
+            LIMIT $4
+            "#;
+
+        let (key, id) = match after {
+            Some(cursor) => (Some(cursor.timestamp(Self::PAGE_SORT)?), Some(cursor.id)),
+            None => (None, None),
+        };
         let tickets = sqlx::query_as(query)
             .bind(lottery_id)
-            .bind(account_id)
-            .fetch_optional(pool)
+            .bind(key)
+            .bind(id)
+            .bind(limit)
+            .fetch_all(pool)
             .await
             .change_context(Error::Store)?;
-        
+
         Ok(tickets)
     }
-    
+
+    /// Find the last bought tickets of an account, starting after the cursor
     pub async fn find_by_address(
         pool: &PgPool,
         address: String,
+        after: Option<KeysetCursor>,
+        limit: i64,
     ) -> Result<Vec<TicketModel>, Error> {
         let query = r#"
-            SELECT *
+            SELECT ticket.*
             FROM ticket
             INNER JOIN account ON ticket.account_id = account.id
             WHERE LOWER(account.address) = LOWER($1)
+                AND ($2::TIMESTAMPTZ IS NULL OR (ticket.purchased_at, ticket.id) < ($2, $3))
+            ORDER BY ticket.purchased_at DESC, ticket.id DESC
+            LIMIT $4
             "#;
 
+        let (key, id) = match after {
+            Some(cursor) => (Some(cursor.timestamp(Self::PAGE_SORT)?), Some(cursor.id)),
+            None => (None, None),
+        };
         let tickets = sqlx::query_as(query)
             .bind(address)
+            .bind(key)
+            .bind(id)
+            .bind(limit)
             .fetch_all(pool)
             .await
             .change_context(Error::Store)?;
         
         Ok(tickets)
     }
-}
\ No newline at end of file
+}
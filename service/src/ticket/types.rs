@@ -12,6 +12,8 @@ pub struct CreateTicket {
     pub amount: i32,
     pub transaction_hash: String,
     pub purchased_at: DateTime<Utc>,
+    pub block_number: i64,
+    pub log_index: i32,
 }
 
 #[derive(Clone, Default, Debug, Serialize, Deserialize)]
@@ -20,4 +22,19 @@ pub struct UpdateTicket {
     pub account_id: Option<Uuid>,
     pub amount: Option<i32>,
     pub purchased_at: Option<DateTime<Utc>>,
-}
\ No newline at end of file
+}
+/// Tickets held by a participant of a lottery
+#[derive(Clone, Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ParticipantTickets {
+    pub account_id: Uuid,
+    pub address: String,
+    pub amount: i64,
+}
+
+/// Ticket sales of a lottery
+#[derive(Clone, Debug, Default, Serialize, Deserialize, sqlx::FromRow)]
+pub struct LotteryTicketStats {
+    pub lottery_id: Uuid,
+    pub tickets_sold: i64,
+    pub participants: i64,
+}
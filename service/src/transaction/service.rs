@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use error_stack::Result;
 
-use super::types::{CreateTransaction, CreateTransactionSideEffect};
+use super::types::{CreateTransaction, CreateTransactionSideEffect, TransactionSideEffect};
 use crate::store::service::DatabaseTransaction;
 use crate::transaction::store::TransactionStore;
 use crate::{
@@ -48,6 +48,53 @@ impl TransactionService {
         Ok(transaction_log_model)
     }
 
+    /// Find the transaction log of the event, creating it when it doesn't exist yet
+    ///
+    /// Several services may handle the same event within one database transaction,
+    /// they all share a single transaction log.
+    pub async fn find_or_create(
+        &self,
+        context: EventContext,
+        db_tx: &mut DatabaseTransaction<'_>,
+    ) -> Result<TransactionLogModel, Error> {
+        let transaction_log = TransactionStore::try_find_by_hash_and_log_index(
+            db_tx.as_mut(),
+            context.transaction_hash,
+            context.log_index,
+        )
+        .await?;
+
+        match transaction_log {
+            Some(transaction_log) => Ok(transaction_log),
+            None => self.create_without_side_effects(context, db_tx).await,
+        }
+    }
+
+    /// Record entities created or changed while handling the event
+    ///
+    /// Nothing is recorded when the change wasn't triggered by a chain event.
+    pub async fn record_side_effects(
+        &self,
+        context: Option<EventContext>,
+        side_effects: Vec<TransactionSideEffect>,
+        db_tx: &mut DatabaseTransaction<'_>,
+    ) -> Result<(), Error> {
+        let Some(context) = context else {
+            return Ok(());
+        };
+
+        let transaction_log = self.find_or_create(context, db_tx).await?;
+
+        let side_effects = CreateTransactionSideEffect {
+            side_effects,
+            transaction_log_id: transaction_log.id,
+        };
+
+        TransactionStore::create_side_effects(db_tx.as_mut(), side_effects).await?;
+
+        Ok(())
+    }
+
     pub async fn create_without_side_effects(
         &self,
         context: EventContext,
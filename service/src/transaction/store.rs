@@ -24,6 +24,20 @@ impl TransactionStore {
         TransactionLogSideEffectModel
     );
 
+    define_find_all_fns!(
+        find_side_effects_by_transaction_log_id,
+        "SELECT * FROM transaction_log_side_effect WHERE transaction_log_id = $1 ORDER BY created_at ASC",
+        Uuid,
+        TransactionLogSideEffectModel
+    );
+
+    define_find_all_fns!(
+        find_side_effects_by_entity_id,
+        "SELECT * FROM transaction_log_side_effect WHERE entity_id = $1 ORDER BY created_at ASC",
+        Uuid,
+        TransactionLogSideEffectModel
+    );
+
     define_find_optional_fns!(
         find_by_transaction_hash,
         try_find_by_transaction_hash,
@@ -184,25 +198,60 @@ impl TransactionStore {
         Ok(())
     }
 
-    pub async fn try_find_by_hash_and_log_index(
-        pool: &PgPool,
+    pub fn try_find_by_hash_and_log_index<'a, 'c, Conn>(
+        conn: Conn,
         hash: H256,
         log_index: U256,
-    ) -> Result<Option<TransactionLogModel>, Error> {
+    ) -> impl Future<Output = Result<Option<TransactionLogModel>, Error>> + Send + 'a
+    where
+        Conn: Acquire<'c, Database = Postgres> + Send + 'a,
+    {
+        async move {
+            let mut conn = conn.acquire().await.change_context(Error::Store)?;
+
+            let query = r#"
+                SELECT * 
+                FROM transaction_log
+                WHERE transaction_hash = $1
+                AND log_index = $2
+            "#;
+
+            let transaction_log = query_as(query)
+                .bind(hash.to_hex_string())
+                .bind(log_index.as_u32() as i32)
+                .fetch_optional(&mut *conn)
+                .await
+                .change_context(Error::Store)?;
+
+            Ok(transaction_log)
+        }
+    }
+
+    /// Check if any of the entities was changed by an event of the chain after provided block
+    pub async fn has_side_effects_after(
+        db_tx: &mut DatabaseTransaction<'_>,
+        chain: &str,
+        entity_ids: &[Uuid],
+        block_number: i64,
+    ) -> Result<bool, Error> {
         let query = r#"
-            SELECT * 
-            FROM transaction_log
-            WHERE transaction_hash = $1
-            AND log_index = $2
-        "#;
+            SELECT EXISTS (
+                SELECT 1 FROM transaction_log_side_effect
+                INNER JOIN transaction_log ON transaction_log.id = transaction_log_side_effect.transaction_log_id
+                WHERE transaction_log.chain = $1
+                AND transaction_log_side_effect.entity_id = ANY($2)
+                AND transaction_log.block_number > $3
+            )
+            "#;
 
-        let transaction_log = query_as(query)
-            .bind(hash.to_hex_string())
-            .bind(log_index.as_u32() as i32)
-            .fetch_optional(pool)
+        let exists = sqlx::query_scalar(query)
+            .bind(chain)
+            .bind(entity_ids)
+            .bind(block_number)
+            .fetch_one(db_tx.as_mut())
             .await
             .change_context(Error::Store)?;
 
-        Ok(transaction_log)
+        Ok(exists)
     }
 }
@@ -20,3 +20,12 @@ pub struct TransactionSideEffect {
     pub entity_id: Uuid,
     pub entity_type: String,
 }
+
+impl TransactionSideEffect {
+    pub fn new(entity_id: Uuid, entity_type: &str) -> Self {
+        Self {
+            entity_id,
+            entity_type: entity_type.to_string(),
+        }
+    }
+}
@@ -42,6 +42,21 @@ pub enum Commands {
     },
     #[clap(name = "graphql", about = "Start the GraphQL server")]
     GraphQL,
+    #[clap(name = "reprocess-raw-logs", about = "Decode and handle archived raw logs again")]
+    ReprocessRawLogs {
+        #[clap(
+            long,
+            help = "List of chains to reprocess, if not provided all chains will be reprocessed"
+        )]
+        chains: Option<Vec<String>>,
+    },
+    #[clap(name = "bootstrap-admin", about = "Grant the admin role to the first admin account")]
+    BootstrapAdmin {
+        #[clap(long, help = "Address of the account to grant the admin role to")]
+        address: String,
+    },
+    #[clap(name = "rebuild-leaderboards", about = "Rebuild the leaderboards of the current periods from the database")]
+    RebuildLeaderboards,
 }
 
 /// Log levels which allow to specify the verbosity of the logs output.
@@ -71,6 +86,9 @@ impl ToString for Commands {
         match self {
             Commands::Indexer { .. } => "indexer".to_string(),
             Commands::GraphQL => "graphql".to_string(),
+            Commands::ReprocessRawLogs { .. } => "reprocess-raw-logs".to_string(),
+            Commands::BootstrapAdmin { .. } => "bootstrap-admin".to_string(),
+            Commands::RebuildLeaderboards => "rebuild-leaderboards".to_string(),
         }
     }
 }
\ No newline at end of file
@@ -2,7 +2,11 @@ mod cli;
 
 use clap::Parser;
 use cli::Cli;
+use entity::account::AccountModel;
+use lib::error::Error;
+use service::account::AccountService;
 use service::config::service::ConfigService;
+use service::leaderboard::LeaderboardService;
 use service::prelude::{ServiceProvider, StoreService};
 use service::telemetry;
 use sqlx::migrate::Migrator;
@@ -41,11 +45,52 @@ async fn main() {
                 error!(reason = ?e, "Failed to start GraphQL");
             }
         }
+        cli::Commands::ReprocessRawLogs { chains } => {
+            match indexer::reprocess_raw_logs(config, chains).await {
+                Ok(count) => info!(count, "Raw logs reprocessing finished"),
+                Err(e) => error!(reason = ?e, "Failed to reprocess raw logs"),
+            }
+        }
+        cli::Commands::BootstrapAdmin { address } => {
+            match bootstrap_admin(&config, &address).await {
+                Ok(account) => info!(address = account.address, "Admin role granted"),
+                Err(e) => error!(reason = ?e, "Failed to grant admin role"),
+            }
+        }
+        cli::Commands::RebuildLeaderboards => {
+            match rebuild_leaderboards(&config).await {
+                Ok(count) => info!(count, "Leaderboards rebuilt"),
+                Err(e) => error!(reason = ?e, "Failed to rebuild leaderboards"),
+            }
+        }
     }
 
     telemetry::shutdown().await.expect("Failed to shutdown telemetry");
 }
 
+async fn bootstrap_admin(
+    config: &ConfigService,
+    address: &str,
+) -> error_stack::Result<AccountModel, Error> {
+    let services_provider = ServiceProvider::new();
+    services_provider.add_service(config.to_owned()).await;
+    let account_service = services_provider
+        .get_service_unchecked::<AccountService>()
+        .await;
+
+    account_service.bootstrap_admin(address).await
+}
+
+async fn rebuild_leaderboards(config: &ConfigService) -> error_stack::Result<usize, Error> {
+    let services_provider = ServiceProvider::new();
+    services_provider.add_service(config.to_owned()).await;
+    let leaderboard_service = services_provider
+        .get_service_unchecked::<LeaderboardService>()
+        .await;
+
+    leaderboard_service.rebuild().await
+}
+
 async fn run_migrations(config: &ConfigService) {
     info!("Running migrations!");
     let services_provider = ServiceProvider::new();